@@ -0,0 +1,142 @@
+use anchor_amm_q4_25::{
+    events::{ConfigUpdated, DepositEvent, PoolClosed, PoolInitialized, SwapEvent, WithdrawEvent},
+    ID,
+};
+use anchor_lang::{event::EVENT_IX_TAG_LE, prelude::Pubkey, AnchorDeserialize, Discriminator};
+
+// Any event of the program
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AmmEvent {
+    Swap(SwapEvent),
+    Deposit(DepositEvent),
+    Withdraw(WithdrawEvent),
+    PoolInitialized(PoolInitialized),
+    ConfigUpdated(ConfigUpdated),
+    PoolClosed(PoolClosed),
+}
+
+// Decodes the data of an inner instruction `emit_cpi!` made, None for any
+// other instruction, the program's own included
+pub fn decode_event(data: &[u8]) -> Option<AmmEvent> {
+    let data = data.strip_prefix(EVENT_IX_TAG_LE)?;
+    let (discriminator, data) = data.split_at_checked(8)?;
+
+    match discriminator {
+        d if d == SwapEvent::DISCRIMINATOR => parse(data).map(AmmEvent::Swap),
+        d if d == DepositEvent::DISCRIMINATOR => parse(data).map(AmmEvent::Deposit),
+        d if d == WithdrawEvent::DISCRIMINATOR => parse(data).map(AmmEvent::Withdraw),
+        d if d == PoolInitialized::DISCRIMINATOR => parse(data).map(AmmEvent::PoolInitialized),
+        d if d == ConfigUpdated::DISCRIMINATOR => parse(data).map(AmmEvent::ConfigUpdated),
+        d if d == PoolClosed::DISCRIMINATOR => parse(data).map(AmmEvent::PoolClosed),
+        _ => None,
+    }
+}
+
+fn parse<E: AnchorDeserialize>(data: &[u8]) -> Option<E> {
+    E::try_from_slice(data).ok()
+}
+
+// Events among a transaction's inner instructions, given as (program id, data)
+// in execution order
+pub fn decode_events<'a>(
+    instructions: impl IntoIterator<Item = (&'a Pubkey, &'a [u8])>,
+) -> Vec<AmmEvent> {
+    instructions
+        .into_iter()
+        .filter(|(program_id, _)| **program_id == ID)
+        .filter_map(|(_, data)| decode_event(data))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_amm_q4_25::state::CurveType;
+    use anchor_lang::Event;
+
+    use super::*;
+    use crate::{instructions, Pool};
+
+    // What `emit_cpi!` puts in the self-CPI
+    fn cpi_data(event: &impl Event) -> Vec<u8> {
+        [EVENT_IX_TAG_LE, &event.data()].concat()
+    }
+
+    fn swap_event() -> SwapEvent {
+        SwapEvent {
+            pool: Pubkey::new_from_array([1; 32]),
+            user: Pubkey::new_from_array([2; 32]),
+            is_x: true,
+            amount_in: 5_000_000,
+            amount_out: 4_523_139,
+            fee: 15_000,
+            fee_bps: 30,
+            reserve_x: 105_000_000,
+            reserve_y: 95_476_861,
+        }
+    }
+
+    #[test]
+    fn decodes_every_event() {
+        let swap = swap_event();
+        let initialized = PoolInitialized {
+            pool: Pubkey::new_unique(),
+            initializer: Pubkey::new_unique(),
+            mint_x: Pubkey::new_unique(),
+            mint_y: Pubkey::new_unique(),
+            fee: 30,
+            curve_type: CurveType::StableSwap,
+            amp: 100,
+            authority: None,
+            index: 7,
+        };
+        let updated = ConfigUpdated {
+            pool: Pubkey::new_unique(),
+            authority: Some(Pubkey::new_unique()),
+            locked: true,
+            gate: false,
+            fee: 50,
+            max_fee: 200,
+            volatility_factor: 5_000,
+            protocol_fee_bps: 2_000,
+            target_amp: 0,
+            ramp_stop_ts: 0,
+        };
+        let closed = PoolClosed {
+            pool: Pubkey::new_unique(),
+            receiver: Pubkey::new_unique(),
+            amount_x: 1_000,
+            amount_y: 1_000,
+        };
+
+        assert_eq!(decode_event(&cpi_data(&swap)), Some(AmmEvent::Swap(swap)));
+        assert_eq!(
+            decode_event(&cpi_data(&initialized)),
+            Some(AmmEvent::PoolInitialized(initialized))
+        );
+        assert_eq!(
+            decode_event(&cpi_data(&updated)),
+            Some(AmmEvent::ConfigUpdated(updated))
+        );
+        assert_eq!(
+            decode_event(&cpi_data(&closed)),
+            Some(AmmEvent::PoolClosed(closed))
+        );
+    }
+
+    #[test]
+    fn skips_everything_but_events() {
+        let event = cpi_data(&swap_event());
+        let pool = Pool::new(1, Pubkey::new_unique(), Pubkey::new_unique());
+        let swap = instructions::swap(&Pubkey::new_unique(), &pool, true, 1, 1).data;
+        let other_program = Pubkey::new_unique();
+
+        let events = decode_events([
+            (&ID, swap.as_slice()),
+            (&other_program, event.as_slice()),
+            (&ID, &event[..event.len() - 1]),
+            (&ID, event.as_slice()),
+        ]);
+
+        assert_eq!(events, vec![AmmEvent::Swap(swap_event())]);
+    }
+}
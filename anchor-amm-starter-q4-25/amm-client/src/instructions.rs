@@ -0,0 +1,962 @@
+use anchor_amm_q4_25::{
+    accounts, instruction,
+    state::{CurveType, Order},
+    ID,
+};
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        system_program, sysvar,
+    },
+    InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token, token_2022,
+};
+
+use crate::{
+    allow_entry_address, event_authority_address, pool_entry_address, pool_index_address,
+    program_data_address, registry_address, tick_array_address, ConcentratedPool, Pool,
+};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn deposit_accounts(user: &Pubkey, pool: &Pool) -> accounts::Deposit {
+    accounts::Deposit {
+        user: *user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        config: pool.config,
+        allow_entry: pool.allow_entry(user),
+        oracle: pool.oracle,
+        mint_lp: pool.mint_lp,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x: pool.user_x(user),
+        user_y: pool.user_y(user),
+        user_lp: pool.user_lp(user),
+        config_lp: pool.config_lp(),
+        token_program: pool.token_program,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+        associated_token_program: associated_token::ID,
+        event_authority: event_authority_address(),
+        program: ID,
+    }
+}
+
+fn withdraw_accounts(user: &Pubkey, pool: &Pool) -> accounts::Withdraw {
+    accounts::Withdraw {
+        user: *user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        config: pool.config,
+        allow_entry: pool.allow_entry(user),
+        oracle: pool.oracle,
+        mint_lp: pool.mint_lp,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x: pool.user_x(user),
+        user_y: pool.user_y(user),
+        user_lp: pool.user_lp(user),
+        token_program: pool.token_program,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+        associated_token_program: associated_token::ID,
+        event_authority: event_authority_address(),
+        program: ID,
+    }
+}
+
+fn swap_accounts(user: &Pubkey, pool: &Pool) -> accounts::Swap {
+    accounts::Swap {
+        user: *user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        config: pool.config,
+        allow_entry: pool.allow_entry(user),
+        oracle: pool.oracle,
+        mint_lp: pool.mint_lp,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x: pool.user_x(user),
+        user_y: pool.user_y(user),
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+        associated_token_program: associated_token::ID,
+        event_authority: event_authority_address(),
+        program: ID,
+    }
+}
+
+fn flash_loan_accounts(user: &Pubkey, pool: &Pool) -> accounts::FlashLoan {
+    accounts::FlashLoan {
+        user: *user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        config: pool.config,
+        allow_entry: pool.allow_entry(user),
+        oracle: pool.oracle,
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x: pool.user_x(user),
+        user_y: pool.user_y(user),
+        instruction_sysvar: sysvar::instructions::ID,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+        associated_token_program: associated_token::ID,
+    }
+}
+
+fn update_accounts(authority: &Pubkey, pool: &Pool) -> accounts::Update {
+    accounts::Update {
+        authority: *authority,
+        config: pool.config,
+        event_authority: event_authority_address(),
+        program: ID,
+    }
+}
+
+fn update_registry_accounts(admin: &Pubkey) -> accounts::UpdateRegistry {
+    accounts::UpdateRegistry {
+        admin: *admin,
+        registry: registry_address(),
+    }
+}
+
+fn modify_liquidity_accounts(
+    owner: &Pubkey,
+    pool: &ConcentratedPool,
+    tick_spacing: u16,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> accounts::ModifyLiquidity {
+    accounts::ModifyLiquidity {
+        owner: *owner,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        pool: pool.pool,
+        position: pool.position(owner, tick_lower, tick_upper),
+        tick_array_lower: pool.tick_array(tick_lower, tick_spacing),
+        tick_array_upper: pool.tick_array(tick_upper, tick_spacing),
+        vault_x: pool.vault_x,
+        vault_y: pool.vault_y,
+        user_x: pool.user_x(owner),
+        user_y: pool.user_y(owner),
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+        event_authority: event_authority_address(),
+        program: ID,
+    }
+}
+
+// `index` is the registry's next_index, what the pool gets registered at
+pub fn initialize(
+    initializer: &Pubkey,
+    pool: &Pool,
+    fee: u16,
+    index: u64,
+    authority: Option<Pubkey>,
+    curve_type: CurveType,
+    amp: u64,
+) -> Instruction {
+    build(
+        accounts::Initialize {
+            initializer: *initializer,
+            mint_x: pool.mint_x,
+            mint_y: pool.mint_y,
+            registry: registry_address(),
+            pool_entry: pool_entry_address(&pool.mint_x, &pool.mint_y, fee),
+            pool_index: pool_index_address(index),
+            mint_lp: pool.mint_lp,
+            vault_x: pool.vault_x,
+            vault_y: pool.vault_y,
+            config: pool.config,
+            oracle: pool.oracle,
+            token_program: pool.token_program,
+            token_program_x: pool.token_program_x,
+            token_program_y: pool.token_program_y,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        },
+        instruction::Initialize {
+            seed: pool.seed,
+            fee,
+            authority,
+            curve_type,
+            amp,
+        },
+    )
+}
+
+pub fn initialize_registry(payer: &Pubkey, admin: Pubkey, fee_tiers: Vec<u16>) -> Instruction {
+    build(
+        accounts::InitializeRegistry {
+            payer: *payer,
+            program: ID,
+            program_data: program_data_address(),
+            registry: registry_address(),
+            system_program: system_program::ID,
+        },
+        instruction::InitializeRegistry { admin, fee_tiers },
+    )
+}
+
+pub fn add_fee_tier(admin: &Pubkey, fee: u16) -> Instruction {
+    build(
+        update_registry_accounts(admin),
+        instruction::AddFeeTier { fee },
+    )
+}
+
+pub fn remove_fee_tier(admin: &Pubkey, fee: u16) -> Instruction {
+    build(
+        update_registry_accounts(admin),
+        instruction::RemoveFeeTier { fee },
+    )
+}
+
+pub fn set_registry_admin(admin: &Pubkey, new_admin: Pubkey) -> Instruction {
+    build(
+        update_registry_accounts(admin),
+        instruction::SetRegistryAdmin { new_admin },
+    )
+}
+
+pub fn deposit(user: &Pubkey, pool: &Pool, amount: u64, max_x: u64, max_y: u64) -> Instruction {
+    build(
+        deposit_accounts(user, pool),
+        instruction::Deposit {
+            amount,
+            max_x,
+            max_y,
+        },
+    )
+}
+
+pub fn deposit_single(
+    user: &Pubkey,
+    pool: &Pool,
+    is_x: bool,
+    amount_in: u64,
+    min_lp_out: u64,
+) -> Instruction {
+    build(
+        deposit_accounts(user, pool),
+        instruction::DepositSingle {
+            is_x,
+            amount_in,
+            min_lp_out,
+        },
+    )
+}
+
+pub fn withdraw(user: &Pubkey, pool: &Pool, amount: u64, min_x: u64, min_y: u64) -> Instruction {
+    build(
+        withdraw_accounts(user, pool),
+        instruction::Withdraw {
+            amount,
+            max_x: min_x,
+            max_y: min_y,
+        },
+    )
+}
+
+pub fn withdraw_single(
+    user: &Pubkey,
+    pool: &Pool,
+    is_x: bool,
+    lp_amount: u64,
+    min_out: u64,
+) -> Instruction {
+    build(
+        withdraw_accounts(user, pool),
+        instruction::WithdrawSingle {
+            is_x,
+            lp_amount,
+            min_out,
+        },
+    )
+}
+
+pub fn swap(
+    user: &Pubkey,
+    pool: &Pool,
+    is_x: bool,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    build(
+        swap_accounts(user, pool),
+        instruction::Swap {
+            is_x,
+            amount_in,
+            min_amount_out,
+        },
+    )
+}
+
+pub fn swap_exact_out(
+    user: &Pubkey,
+    pool: &Pool,
+    is_x: bool,
+    amount_out: u64,
+    max_amount_in: u64,
+) -> Instruction {
+    build(
+        swap_accounts(user, pool),
+        instruction::SwapExactOut {
+            is_x,
+            amount_out,
+            max_amount_in,
+        },
+    )
+}
+
+// `hops` are (pool, is_x) pairs, the user's token accounts for every mint on
+// the route have to exist beforehand
+pub fn route_swap(
+    user: &Pubkey,
+    hops: &[(Pool, bool)],
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let mut ix = build(
+        accounts::RouteSwap {
+            user: *user,
+            token_program: token::ID,
+            token_program_2022: token_2022::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        },
+        instruction::RouteSwap {
+            path: hops.iter().map(|(_, is_x)| *is_x).collect(),
+            amount_in,
+            min_amount_out,
+        },
+    );
+
+    for (pool, _) in hops {
+        ix.accounts.extend([
+            AccountMeta::new(pool.config, false),
+            AccountMeta::new(pool.oracle, false),
+            AccountMeta::new_readonly(pool.mint_x, false),
+            AccountMeta::new_readonly(pool.mint_y, false),
+            AccountMeta::new_readonly(pool.mint_lp, false),
+            AccountMeta::new(pool.vault_x, false),
+            AccountMeta::new(pool.vault_y, false),
+            AccountMeta::new(pool.user_x(user), false),
+            AccountMeta::new(pool.user_y(user), false),
+        ]);
+    }
+
+    ix
+}
+
+// Only succeeds in a transaction that also carries `flash_repay` for the pool
+pub fn flash_borrow(user: &Pubkey, pool: &Pool, is_x: bool, amount: u64) -> Instruction {
+    build(
+        flash_loan_accounts(user, pool),
+        instruction::FlashBorrow { is_x, amount },
+    )
+}
+
+pub fn flash_repay(user: &Pubkey, pool: &Pool) -> Instruction {
+    build(flash_loan_accounts(user, pool), instruction::FlashRepay {})
+}
+
+// Meant to be simulated, the TWAP comes back as return data
+pub fn observe(pool: &Pool, window: i64) -> Instruction {
+    build(
+        accounts::Observe {
+            config: pool.config,
+            oracle: pool.oracle,
+        },
+        instruction::Observe { window },
+    )
+}
+
+pub fn lock(authority: &Pubkey, pool: &Pool) -> Instruction {
+    build(update_accounts(authority, pool), instruction::Lock {})
+}
+
+pub fn unlock(authority: &Pubkey, pool: &Pool) -> Instruction {
+    build(update_accounts(authority, pool), instruction::Unlock {})
+}
+
+pub fn set_gate(authority: &Pubkey, pool: &Pool, gate: bool) -> Instruction {
+    build(
+        update_accounts(authority, pool),
+        instruction::SetGate { gate },
+    )
+}
+
+// Also renews the entry of a user already on the allowlist
+pub fn add_to_allowlist(
+    authority: &Pubkey,
+    pool: &Pool,
+    user: Pubkey,
+    expires_at: i64,
+) -> Instruction {
+    build(
+        accounts::AddToAllowlist {
+            authority: *authority,
+            config: pool.config,
+            allow_entry: allow_entry_address(&pool.config, &user),
+            system_program: system_program::ID,
+        },
+        instruction::AddToAllowlist { user, expires_at },
+    )
+}
+
+pub fn remove_from_allowlist(authority: &Pubkey, pool: &Pool, user: &Pubkey) -> Instruction {
+    build(
+        accounts::RemoveFromAllowlist {
+            authority: *authority,
+            config: pool.config,
+            allow_entry: allow_entry_address(&pool.config, user),
+        },
+        instruction::RemoveFromAllowlist {},
+    )
+}
+
+// Moves the pool from the `current_fee` tier to `fee`
+pub fn update_fee(authority: &Pubkey, pool: &Pool, current_fee: u16, fee: u16) -> Instruction {
+    build(
+        accounts::UpdateFee {
+            authority: *authority,
+            config: pool.config,
+            registry: registry_address(),
+            pool_entry: pool_entry_address(&pool.mint_x, &pool.mint_y, current_fee),
+            new_pool_entry: pool_entry_address(&pool.mint_x, &pool.mint_y, fee),
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        },
+        instruction::UpdateFee { fee },
+    )
+}
+
+pub fn set_dynamic_fee(
+    authority: &Pubkey,
+    pool: &Pool,
+    max_fee: u16,
+    volatility_factor: u16,
+    decay_period: i64,
+) -> Instruction {
+    build(
+        update_accounts(authority, pool),
+        instruction::SetDynamicFee {
+            max_fee,
+            volatility_factor,
+            decay_period,
+        },
+    )
+}
+
+pub fn update_protocol_fee(authority: &Pubkey, pool: &Pool, protocol_fee_bps: u16) -> Instruction {
+    build(
+        update_accounts(authority, pool),
+        instruction::UpdateProtocolFee { protocol_fee_bps },
+    )
+}
+
+pub fn ramp_amp(
+    authority: &Pubkey,
+    pool: &Pool,
+    target_amp: u64,
+    ramp_stop_ts: i64,
+) -> Instruction {
+    build(
+        update_accounts(authority, pool),
+        instruction::RampAmp {
+            target_amp,
+            ramp_stop_ts,
+        },
+    )
+}
+
+pub fn collect_protocol_fees(
+    authority: &Pubkey,
+    pool: &Pool,
+    treasury_x: &Pubkey,
+    treasury_y: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CollectProtocolFees {
+            authority: *authority,
+            mint_x: pool.mint_x,
+            mint_y: pool.mint_y,
+            config: pool.config,
+            vault_x: pool.vault_x,
+            vault_y: pool.vault_y,
+            treasury_x: *treasury_x,
+            treasury_y: *treasury_y,
+            token_program_x: pool.token_program_x,
+            token_program_y: pool.token_program_y,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::CollectProtocolFees {},
+    )
+}
+
+// `fee` is the pool's fee tier and `index` the one in its pool entry, the
+// entry and the index are closed too. `admin` is the registry's, it gets the
+// dust left in the vaults.
+pub fn close_pool(
+    authority: &Pubkey,
+    pool: &Pool,
+    fee: u16,
+    index: u64,
+    receiver: &Pubkey,
+    admin: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::ClosePool {
+            authority: *authority,
+            receiver: *receiver,
+            mint_x: pool.mint_x,
+            mint_y: pool.mint_y,
+            registry: registry_address(),
+            admin: *admin,
+            pool_entry: pool_entry_address(&pool.mint_x, &pool.mint_y, fee),
+            pool_index: pool_index_address(index),
+            config: pool.config,
+            oracle: pool.oracle,
+            farm: pool.farm,
+            mint_lp: pool.mint_lp,
+            config_lp: pool.config_lp(),
+            vault_x: pool.vault_x,
+            vault_y: pool.vault_y,
+            treasury_x: pool.user_x(admin),
+            treasury_y: pool.user_y(admin),
+            token_program: pool.token_program,
+            token_program_x: pool.token_program_x,
+            token_program_y: pool.token_program_y,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        },
+        instruction::ClosePool {},
+    )
+}
+
+pub fn transfer_authority(authority: &Pubkey, pool: &Pool, new_authority: Pubkey) -> Instruction {
+    build(
+        update_accounts(authority, pool),
+        instruction::TransferAuthority { new_authority },
+    )
+}
+
+pub fn renounce_authority(authority: &Pubkey, pool: &Pool) -> Instruction {
+    build(
+        update_accounts(authority, pool),
+        instruction::RenounceAuthority {},
+    )
+}
+
+// Registered like `initialize`, `index` is the registry's next_index
+pub fn initialize_cl_pool(
+    initializer: &Pubkey,
+    pool: &ConcentratedPool,
+    fee: u16,
+    index: u64,
+    tick_spacing: u16,
+    sqrt_price: u128,
+) -> Instruction {
+    build(
+        accounts::InitializeClPool {
+            initializer: *initializer,
+            mint_x: pool.mint_x,
+            mint_y: pool.mint_y,
+            registry: registry_address(),
+            pool_entry: pool_entry_address(&pool.mint_x, &pool.mint_y, fee),
+            pool_index: pool_index_address(index),
+            pool: pool.pool,
+            vault_x: pool.vault_x,
+            vault_y: pool.vault_y,
+            token_program_x: pool.token_program_x,
+            token_program_y: pool.token_program_y,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeClPool {
+            seed: pool.seed,
+            fee,
+            tick_spacing,
+            sqrt_price,
+        },
+    )
+}
+
+pub fn initialize_tick_array(
+    payer: &Pubkey,
+    pool: &ConcentratedPool,
+    start_tick_index: i32,
+) -> Instruction {
+    build(
+        accounts::InitializeTickArray {
+            payer: *payer,
+            pool: pool.pool,
+            tick_array: tick_array_address(&pool.pool, start_tick_index),
+            system_program: system_program::ID,
+        },
+        instruction::InitializeTickArray { start_tick_index },
+    )
+}
+
+pub fn open_position(
+    owner: &Pubkey,
+    pool: &ConcentratedPool,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Instruction {
+    build(
+        accounts::OpenPosition {
+            owner: *owner,
+            pool: pool.pool,
+            position: pool.position(owner, tick_lower, tick_upper),
+            system_program: system_program::ID,
+        },
+        instruction::OpenPosition {
+            tick_lower,
+            tick_upper,
+        },
+    )
+}
+
+// The position is identified by its range, `tick_spacing` locates its tick arrays
+pub fn increase_liquidity(
+    owner: &Pubkey,
+    pool: &ConcentratedPool,
+    tick_spacing: u16,
+    (tick_lower, tick_upper): (i32, i32),
+    liquidity: u128,
+    max_x: u64,
+    max_y: u64,
+) -> Instruction {
+    build(
+        modify_liquidity_accounts(owner, pool, tick_spacing, tick_lower, tick_upper),
+        instruction::IncreaseLiquidity {
+            liquidity,
+            max_x,
+            max_y,
+        },
+    )
+}
+
+pub fn decrease_liquidity(
+    owner: &Pubkey,
+    pool: &ConcentratedPool,
+    tick_spacing: u16,
+    (tick_lower, tick_upper): (i32, i32),
+    liquidity: u128,
+    min_x: u64,
+    min_y: u64,
+) -> Instruction {
+    build(
+        modify_liquidity_accounts(owner, pool, tick_spacing, tick_lower, tick_upper),
+        instruction::DecreaseLiquidity {
+            liquidity,
+            min_x,
+            min_y,
+        },
+    )
+}
+
+pub fn collect_fees(
+    owner: &Pubkey,
+    pool: &ConcentratedPool,
+    tick_spacing: u16,
+    (tick_lower, tick_upper): (i32, i32),
+) -> Instruction {
+    build(
+        modify_liquidity_accounts(owner, pool, tick_spacing, tick_lower, tick_upper),
+        instruction::CollectFees {},
+    )
+}
+
+// `tick_arrays` as returned by `ConcentratedPool::swap_tick_arrays`
+pub fn cl_swap(
+    user: &Pubkey,
+    pool: &ConcentratedPool,
+    tick_arrays: &[Pubkey],
+    is_x: bool,
+    amount_in: u64,
+    min_amount_out: u64,
+    sqrt_price_limit: u128,
+) -> Instruction {
+    let mut ix = build(
+        accounts::ClSwap {
+            user: *user,
+            mint_x: pool.mint_x,
+            mint_y: pool.mint_y,
+            pool: pool.pool,
+            vault_x: pool.vault_x,
+            vault_y: pool.vault_y,
+            user_x: pool.user_x(user),
+            user_y: pool.user_y(user),
+            token_program_x: pool.token_program_x,
+            token_program_y: pool.token_program_y,
+            system_program: system_program::ID,
+            associated_token_program: associated_token::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        },
+        instruction::ClSwap {
+            is_x,
+            amount_in,
+            min_amount_out,
+            sqrt_price_limit,
+        },
+    );
+
+    ix.accounts.extend(
+        tick_arrays
+            .iter()
+            .map(|tick_array| AccountMeta::new(*tick_array, false)),
+    );
+
+    ix
+}
+
+fn stake_lp_accounts(user: &Pubkey, pool: &Pool) -> accounts::StakeLp {
+    accounts::StakeLp {
+        user: *user,
+        farm: pool.farm,
+        mint_lp: pool.mint_lp,
+        farm_lp: pool.farm_lp(),
+        user_lp: pool.user_lp(user),
+        stake: pool.stake(user),
+        token_program: pool.token_program,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn initialize_farm(authority: &Pubkey, pool: &Pool) -> Instruction {
+    build(
+        accounts::InitializeFarm {
+            authority: *authority,
+            config: pool.config,
+            mint_lp: pool.mint_lp,
+            farm: pool.farm,
+            farm_lp: pool.farm_lp(),
+            token_program: pool.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeFarm {},
+    )
+}
+
+// Pulls the whole schedule's emissions from the authority's associated token
+// account up front, `token_program` owns `reward_mint`
+pub fn fund_reward(
+    authority: &Pubkey,
+    pool: &Pool,
+    reward_mint: &Pubkey,
+    token_program: &Pubkey,
+    emissions_per_second: u64,
+    start_ts: i64,
+    end_ts: i64,
+) -> Instruction {
+    build(
+        accounts::FundReward {
+            authority: *authority,
+            config: pool.config,
+            farm: pool.farm,
+            reward_mint: *reward_mint,
+            reward_vault: pool.reward_vault(reward_mint, token_program),
+            authority_reward: get_associated_token_address_with_program_id(
+                authority,
+                reward_mint,
+                token_program,
+            ),
+            token_program: *token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::FundReward {
+            emissions_per_second,
+            start_ts,
+            end_ts,
+        },
+    )
+}
+
+pub fn reclaim_rewards(
+    authority: &Pubkey,
+    pool: &Pool,
+    reward_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::ReclaimRewards {
+            authority: *authority,
+            config: pool.config,
+            farm: pool.farm,
+            reward_mint: *reward_mint,
+            reward_vault: pool.reward_vault(reward_mint, token_program),
+            authority_reward: get_associated_token_address_with_program_id(
+                authority,
+                reward_mint,
+                token_program,
+            ),
+            token_program: *token_program,
+        },
+        instruction::ReclaimRewards {},
+    )
+}
+
+pub fn stake_lp(user: &Pubkey, pool: &Pool, amount: u64) -> Instruction {
+    build(
+        stake_lp_accounts(user, pool),
+        instruction::StakeLp { amount },
+    )
+}
+
+pub fn unstake_lp(user: &Pubkey, pool: &Pool, amount: u64) -> Instruction {
+    build(
+        stake_lp_accounts(user, pool),
+        instruction::UnstakeLp { amount },
+    )
+}
+
+// Pays out one reward token, `token_program` owns `reward_mint`
+pub fn harvest(
+    user: &Pubkey,
+    pool: &Pool,
+    reward_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::Harvest {
+            user: *user,
+            farm: pool.farm,
+            stake: pool.stake(user),
+            reward_mint: *reward_mint,
+            reward_vault: pool.reward_vault(reward_mint, token_program),
+            user_reward: get_associated_token_address_with_program_id(
+                user,
+                reward_mint,
+                token_program,
+            ),
+            token_program: *token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::Harvest {},
+    )
+}
+
+// Escrows `amount` of the side sold when `is_x`, to be sold for at least
+// `min_amount_out`. `keeper_reward` lamports go to whoever fills it.
+pub fn place_limit_order(
+    owner: &Pubkey,
+    pool: &Pool,
+    is_x: bool,
+    id: u64,
+    amount: u64,
+    min_amount_out: u64,
+    keeper_reward: u64,
+) -> Instruction {
+    let order = pool.order(owner, id);
+    let (mint_in, token_program) = pool.mint_in(is_x);
+
+    build(
+        accounts::PlaceLimitOrder {
+            owner: *owner,
+            config: pool.config,
+            mint_in,
+            owner_in: get_associated_token_address_with_program_id(owner, &mint_in, &token_program),
+            order,
+            escrow: pool.escrow(&order, is_x),
+            token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::PlaceLimitOrder {
+            id,
+            amount,
+            min_amount_out,
+            keeper_reward,
+        },
+    )
+}
+
+pub fn cancel_order(owner: &Pubkey, pool: &Pool, is_x: bool, id: u64) -> Instruction {
+    let order = pool.order(owner, id);
+    let (mint_in, token_program) = pool.mint_in(is_x);
+
+    build(
+        accounts::CancelOrder {
+            owner: *owner,
+            order,
+            mint_in,
+            escrow: pool.escrow(&order, is_x),
+            owner_in: get_associated_token_address_with_program_id(owner, &mint_in, &token_program),
+            token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CancelOrder {},
+    )
+}
+
+// `orders` are decoded order accounts of the pool, filled in that order. The
+// owners' token accounts for the output mint have to exist beforehand.
+pub fn crank_orders(keeper: &Pubkey, pool: &Pool, orders: &[Order]) -> Instruction {
+    let mut ix = build(
+        accounts::CrankOrders {
+            keeper: *keeper,
+            mint_x: pool.mint_x,
+            mint_y: pool.mint_y,
+            config: pool.config,
+            oracle: pool.oracle,
+            mint_lp: pool.mint_lp,
+            vault_x: pool.vault_x,
+            vault_y: pool.vault_y,
+            token_program_x: pool.token_program_x,
+            token_program_y: pool.token_program_y,
+            event_authority: event_authority_address(),
+            program: ID,
+        },
+        instruction::CrankOrders {},
+    );
+
+    for order in orders {
+        let address = pool.order(&order.owner, order.id);
+        let is_x = order.mint_in == pool.mint_x;
+        let owner_out = match is_x {
+            true => pool.user_y(&order.owner),
+            false => pool.user_x(&order.owner),
+        };
+
+        ix.accounts.extend([
+            AccountMeta::new(address, false),
+            AccountMeta::new(pool.escrow(&address, is_x), false),
+            AccountMeta::new(owner_out, false),
+            AccountMeta::new(order.owner, false),
+        ]);
+    }
+
+    ix
+}
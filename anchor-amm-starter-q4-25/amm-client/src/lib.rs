@@ -0,0 +1,13 @@
+// Off-chain side of anchor-amm-q4-25: pool addresses, account decoding,
+// quotes that follow the program's math, instruction builders and an event
+// decoder
+
+pub mod events;
+pub mod instructions;
+pub mod pool;
+pub mod quote;
+
+pub use anchor_amm_q4_25::{state::Config, ID};
+pub use events::*;
+pub use pool::*;
+pub use quote::*;
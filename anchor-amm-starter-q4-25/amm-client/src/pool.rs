@@ -0,0 +1,355 @@
+use anchor_amm_q4_25::{
+    state::{
+        AllowEntry, ClPool, Config, Farm, Order, PoolEntry, PoolIndex, Registry, Stake, TickArray,
+        TICK_ARRAY_SIZE,
+    },
+    ID,
+};
+use anchor_lang::{
+    prelude::Pubkey, solana_program::bpf_loader_upgradeable, AccountDeserialize, Result,
+};
+use anchor_spl::{associated_token::get_associated_token_address_with_program_id, token};
+
+pub fn config_address(seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"config", &seed.to_le_bytes()], &ID).0
+}
+
+pub fn mint_lp_address(config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lp", config.as_ref()], &ID).0
+}
+
+pub fn oracle_address(config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"oracle", config.as_ref()], &ID).0
+}
+
+pub fn farm_address(config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"farm", config.as_ref()], &ID).0
+}
+
+pub fn stake_address(farm: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"stake", farm.as_ref(), owner.as_ref()], &ID).0
+}
+
+pub fn order_address(config: &Pubkey, owner: &Pubkey, id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"order", config.as_ref(), owner.as_ref(), &id.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+pub fn allow_entry_address(config: &Pubkey, user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"allow", config.as_ref(), user.as_ref()], &ID).0
+}
+
+// Signs the CPIs the program emits its events with
+pub fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &ID).0
+}
+
+pub fn registry_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"registry"], &ID).0
+}
+
+// Holds the program's upgrade authority, the only one who can create the registry
+pub fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+// Canonical entry of a pair at a fee tier, the mints have to be sorted
+pub fn pool_entry_address(mint_x: &Pubkey, mint_y: &Pubkey, fee: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"pool",
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+            &fee.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+// Pool created at `index`, from 0 up to Registry.next_index
+pub fn pool_index_address(index: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool_index", &index.to_le_bytes()], &ID).0
+}
+
+// Orders a pair the way `initialize` expects it, as (mint_x, mint_y)
+pub fn sort_mints(a: Pubkey, b: Pubkey) -> (Pubkey, Pubkey) {
+    match a < b {
+        true => (a, b),
+        false => (b, a),
+    }
+}
+
+pub fn cl_pool_address(seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"cl_pool", &seed.to_le_bytes()], &ID).0
+}
+
+pub fn tick_array_address(pool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            pool.as_ref(),
+            &start_tick_index.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn position_address(pool: &Pubkey, owner: &Pubkey, tick_lower: i32, tick_upper: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"position",
+            pool.as_ref(),
+            owner.as_ref(),
+            &tick_lower.to_le_bytes(),
+            &tick_upper.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+// Checks the account discriminator, so any other account of the program fails
+pub fn decode_config(data: &[u8]) -> Result<Config> {
+    Config::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_registry(data: &[u8]) -> Result<Registry> {
+    Registry::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_pool_entry(data: &[u8]) -> Result<PoolEntry> {
+    PoolEntry::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_pool_index(data: &[u8]) -> Result<PoolIndex> {
+    PoolIndex::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_farm(data: &[u8]) -> Result<Farm> {
+    Farm::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_stake(data: &[u8]) -> Result<Stake> {
+    Stake::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_order(data: &[u8]) -> Result<Order> {
+    Order::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_allow_entry(data: &[u8]) -> Result<AllowEntry> {
+    AllowEntry::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_cl_pool(data: &[u8]) -> Result<ClPool> {
+    ClPool::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_tick_array(data: &[u8]) -> Result<TickArray> {
+    TickArray::try_deserialize(&mut &data[..])
+}
+
+// Every address the program derives for a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pool {
+    pub seed: u64,
+    pub config: Pubkey,
+    pub oracle: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub mint_lp: Pubkey,
+    pub farm: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub token_program: Pubkey, // Owns the LP mint
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
+    pub gate: bool, // As `Config.gate`, builders then pass the allowlist entry
+}
+
+impl Pool {
+    // Pool between two classic SPL Token mints
+    pub fn new(seed: u64, mint_x: Pubkey, mint_y: Pubkey) -> Self {
+        Self::with_token_programs(seed, mint_x, mint_y, token::ID, token::ID)
+    }
+
+    pub fn with_token_programs(
+        seed: u64,
+        mint_x: Pubkey,
+        mint_y: Pubkey,
+        token_program_x: Pubkey,
+        token_program_y: Pubkey,
+    ) -> Self {
+        let config = config_address(seed);
+
+        Self {
+            seed,
+            config,
+            oracle: oracle_address(&config),
+            mint_x,
+            mint_y,
+            mint_lp: mint_lp_address(&config),
+            farm: farm_address(&config),
+            vault_x: get_associated_token_address_with_program_id(
+                &config,
+                &mint_x,
+                &token_program_x,
+            ),
+            vault_y: get_associated_token_address_with_program_id(
+                &config,
+                &mint_y,
+                &token_program_y,
+            ),
+            token_program: token::ID,
+            token_program_x,
+            token_program_y,
+            gate: false,
+        }
+    }
+
+    // The token programs are the owners of the mints, which `Config` doesn't store
+    pub fn from_config(config: &Config, token_program_x: Pubkey, token_program_y: Pubkey) -> Self {
+        Self {
+            gate: config.gate,
+            ..Self::with_token_programs(
+                config.seed,
+                config.mint_x,
+                config.mint_y,
+                token_program_x,
+                token_program_y,
+            )
+        }
+    }
+
+    pub fn user_x(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.mint_x, &self.token_program_x)
+    }
+
+    pub fn user_y(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.mint_y, &self.token_program_y)
+    }
+
+    pub fn user_lp(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.mint_lp, &self.token_program)
+    }
+
+    // Holds the MINIMUM_LIQUIDITY locked by the first deposit
+    pub fn config_lp(&self) -> Pubkey {
+        self.user_lp(&self.config)
+    }
+
+    // Holds the LP tokens staked in the farm
+    pub fn farm_lp(&self) -> Pubkey {
+        self.user_lp(&self.farm)
+    }
+
+    pub fn stake(&self, owner: &Pubkey) -> Pubkey {
+        stake_address(&self.farm, owner)
+    }
+
+    // Reward tokens are held by the farm, `token_program` owns `mint`
+    pub fn reward_vault(&self, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(&self.farm, mint, token_program)
+    }
+
+    // Entry `user` has to pass on a gated pool, None when the pool isn't gated
+    pub fn allow_entry(&self, user: &Pubkey) -> Option<Pubkey> {
+        self.gate.then(|| allow_entry_address(&self.config, user))
+    }
+
+    pub fn order(&self, owner: &Pubkey, id: u64) -> Pubkey {
+        order_address(&self.config, owner, id)
+    }
+
+    // (mint, token program) of the side sold when `is_x`
+    pub fn mint_in(&self, is_x: bool) -> (Pubkey, Pubkey) {
+        match is_x {
+            true => (self.mint_x, self.token_program_x),
+            false => (self.mint_y, self.token_program_y),
+        }
+    }
+
+    // Holds the tokens an order sells until it's filled or cancelled
+    pub fn escrow(&self, order: &Pubkey, is_x: bool) -> Pubkey {
+        let (mint, token_program) = self.mint_in(is_x);
+        get_associated_token_address_with_program_id(order, &mint, &token_program)
+    }
+}
+
+// Every address the program derives for a concentrated-liquidity pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConcentratedPool {
+    pub seed: u64,
+    pub pool: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
+}
+
+impl ConcentratedPool {
+    // Pool between two classic SPL Token mints
+    pub fn new(seed: u64, mint_x: Pubkey, mint_y: Pubkey) -> Self {
+        Self::with_token_programs(seed, mint_x, mint_y, token::ID, token::ID)
+    }
+
+    pub fn with_token_programs(
+        seed: u64,
+        mint_x: Pubkey,
+        mint_y: Pubkey,
+        token_program_x: Pubkey,
+        token_program_y: Pubkey,
+    ) -> Self {
+        let pool = cl_pool_address(seed);
+
+        Self {
+            seed,
+            pool,
+            mint_x,
+            mint_y,
+            vault_x: get_associated_token_address_with_program_id(&pool, &mint_x, &token_program_x),
+            vault_y: get_associated_token_address_with_program_id(&pool, &mint_y, &token_program_y),
+            token_program_x,
+            token_program_y,
+        }
+    }
+
+    pub fn user_x(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.mint_x, &self.token_program_x)
+    }
+
+    pub fn user_y(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.mint_y, &self.token_program_y)
+    }
+
+    // Tick array holding `tick`
+    pub fn tick_array(&self, tick: i32, tick_spacing: u16) -> Pubkey {
+        tick_array_address(&self.pool, TickArray::start_index(tick, tick_spacing))
+    }
+
+    pub fn position(&self, owner: &Pubkey, tick_lower: i32, tick_upper: i32) -> Pubkey {
+        position_address(&self.pool, owner, tick_lower, tick_upper)
+    }
+
+    // The first `count` tick arrays a swap can cross from the pool's current
+    // tick, in the order `cl_swap` expects them
+    pub fn swap_tick_arrays(&self, state: &ClPool, is_x: bool, count: usize) -> Vec<Pubkey> {
+        let span = state.tick_spacing as i32 * TICK_ARRAY_SIZE as i32;
+        let start = TickArray::start_index(state.tick_current, state.tick_spacing);
+
+        (0..count as i32)
+            .map(|i| match is_x {
+                true => start - i * span,
+                false => start + i * span,
+            })
+            .map(|start| tick_array_address(&self.pool, start))
+            .collect()
+    }
+}
@@ -0,0 +1,748 @@
+use anchor_amm_q4_25::{
+    clmm::{self, amounts_for_liquidity},
+    errors::AmmError,
+    instructions::{exact_out_on_curve, swap_on_curve, zap_swap_amount, MINIMUM_LIQUIDITY},
+    stable_swap,
+    state::{ClPool, Config, CurveType, Farm, Order, Stake, TickArray, MAX_REWARDS},
+};
+use anchor_lang::{prelude::Clock, require, Result};
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::{Account, Mint},
+};
+use constant_product_curve::ConstantProduct;
+
+use crate::{decode_cl_pool, decode_config, decode_tick_array};
+
+// Everything the program reads from a pool's accounts to price an instruction
+#[derive(Clone)]
+pub struct PoolState {
+    pub config: Config,
+    pub vault_x: u64, // Vault balances, protocol fees included
+    pub vault_y: u64,
+    pub lp_supply: u64,
+    pub lp_decimals: u8,
+    pub transfer_fee_x: Option<TransferFee>, // Token-2022 transfer fee of mint X for the current epoch
+    pub transfer_fee_y: Option<TransferFee>,
+    pub timestamp: i64, // Cluster time the quote is for, moves StableSwap amp ramps
+}
+
+impl PoolState {
+    // Raw account data as returned by RPC, `clock` selects the transfer fee and amp in force
+    pub fn from_account_data(
+        config: &[u8],
+        mint_x: &[u8],
+        mint_y: &[u8],
+        mint_lp: &[u8],
+        vault_x: &[u8],
+        vault_y: &[u8],
+        clock: &Clock,
+    ) -> Result<Self> {
+        let mint_lp = StateWithExtensions::<Mint>::unpack(mint_lp)?.base;
+        Ok(Self {
+            config: decode_config(config)?,
+            vault_x: StateWithExtensions::<Account>::unpack(vault_x)?.base.amount,
+            vault_y: StateWithExtensions::<Account>::unpack(vault_y)?.base.amount,
+            lp_supply: mint_lp.supply,
+            lp_decimals: mint_lp.decimals,
+            transfer_fee_x: epoch_transfer_fee(mint_x, clock.epoch)?,
+            transfer_fee_y: epoch_transfer_fee(mint_y, clock.epoch)?,
+            timestamp: clock.unix_timestamp,
+        })
+    }
+
+    // `Config` as the program prices with it, right after `update_price_cumulatives`
+    // stamped the current time
+    fn config(&self) -> Config {
+        let mut config = self.config.clone();
+        config.last_update_timestamp = self.timestamp;
+        config
+    }
+
+    // (input, output) transfer fees of a swap
+    fn transfer_fees(&self, is_x: bool) -> (Option<&TransferFee>, Option<&TransferFee>) {
+        match is_x {
+            true => (self.transfer_fee_x.as_ref(), self.transfer_fee_y.as_ref()),
+            false => (self.transfer_fee_y.as_ref(), self.transfer_fee_x.as_ref()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,    // Sent by the user
+    pub amount_out: u64,   // Received by the user, after transfer fees
+    pub protocol_fee: u64, // Part of the swap fee set aside for the protocol, in the input token
+    pub fee_bps: u16,      // Fee rate charged, moves with volatility on a dynamic fee
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepositQuote {
+    pub x: u64,  // Token X sent by the user
+    pub y: u64,  // Token Y sent by the user
+    pub lp: u64, // LP tokens minted to the user
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawQuote {
+    pub x: u64, // Token X received by the user, after transfer fees
+    pub y: u64, // Token Y received by the user, after transfer fees
+}
+
+pub fn quote_swap(pool: &PoolState, is_x: bool, amount_in: u64) -> Result<SwapQuote> {
+    require!(!pool.config.locked, AmmError::PoolLocked);
+    require!(amount_in != 0, AmmError::InvalidAmount);
+
+    let (x, y) = pool.config.reserves(pool.vault_x, pool.vault_y)?;
+    let (fee_in, fee_out) = pool.transfer_fees(is_x);
+
+    let received = amount_in - transfer_fee(fee_in, amount_in)?;
+
+    let mut config = pool.config();
+    let fee_bps = config.trade_fee(x, y)?;
+    let (withdraw, _) = swap_on_curve(
+        &mut config,
+        fee_bps,
+        (x, y),
+        pool.lp_supply,
+        is_x,
+        received,
+        0,
+    )?;
+
+    Ok(SwapQuote {
+        amount_in,
+        amount_out: withdraw - transfer_fee(fee_out, withdraw)?,
+        protocol_fee: protocol_fee(&pool.config, &config, is_x),
+        fee_bps,
+    })
+}
+
+pub fn quote_swap_exact_out(pool: &PoolState, is_x: bool, amount_out: u64) -> Result<SwapQuote> {
+    require!(!pool.config.locked, AmmError::PoolLocked);
+    require!(amount_out != 0, AmmError::InvalidAmount);
+
+    let (x, y) = pool.config.reserves(pool.vault_x, pool.vault_y)?;
+    let (reserve_in, reserve_out) = match is_x {
+        true => (x, y),
+        false => (y, x),
+    };
+    let (fee_in, fee_out) = pool.transfer_fees(is_x);
+
+    let mut config = pool.config();
+    let fee_bps = config.trade_fee(x, y)?;
+
+    let withdraw = pre_fee_amount(fee_out, amount_out)?;
+    let received = exact_out_on_curve(&config, fee_bps, reserve_in, reserve_out, withdraw)?;
+    let amount_in = pre_fee_amount(fee_in, received)?;
+
+    let fee = (received as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(AmmError::Overflow)?
+        .checked_div(10_000)
+        .ok_or(AmmError::Overflow)? as u64;
+    config.accrue_protocol_fee(is_x, fee)?;
+
+    Ok(SwapQuote {
+        amount_in,
+        amount_out,
+        protocol_fee: protocol_fee(&pool.config, &config, is_x),
+        fee_bps,
+    })
+}
+
+// Same arguments as the `deposit` instruction. Pass u64::MAX as `max_x` and
+// `max_y` to get the amounts needed for `amount` LP tokens.
+pub fn quote_deposit(
+    pool: &PoolState,
+    amount: u64,
+    max_x: u64,
+    max_y: u64,
+) -> Result<DepositQuote> {
+    require!(!pool.config.locked, AmmError::PoolLocked);
+    require!(amount != 0, AmmError::InvalidAmount);
+
+    let (reserve_x, reserve_y) = pool.config.reserves(pool.vault_x, pool.vault_y)?;
+
+    let first_deposit = pool.lp_supply == 0;
+
+    let (x, y, lp) = match first_deposit {
+        true => {
+            let received_x = max_x - transfer_fee(pool.transfer_fee_x.as_ref(), max_x)?;
+            let received_y = max_y - transfer_fee(pool.transfer_fee_y.as_ref(), max_y)?;
+            let lp = match pool.config.curve_type {
+                CurveType::ConstantProduct => (received_x as u128)
+                    .checked_mul(received_y as u128)
+                    .ok_or(AmmError::Overflow)?
+                    .isqrt() as u64,
+                CurveType::StableSwap => u64::try_from(stable_swap::compute_d(
+                    received_x,
+                    received_y,
+                    pool.config.amp(pool.timestamp),
+                )?)
+                .map_err(|_| AmmError::Overflow)?,
+            };
+            require!(lp > MINIMUM_LIQUIDITY, AmmError::LiquidityLessThanMinimum);
+            (max_x, max_y, lp - MINIMUM_LIQUIDITY)
+        }
+        false => {
+            let amounts = ConstantProduct::xy_deposit_amounts_from_l(
+                reserve_x,
+                reserve_y,
+                pool.lp_supply,
+                amount,
+                pool.lp_decimals.into(),
+            )
+            .map_err(|_| AmmError::CurveError)?;
+            (
+                pre_fee_amount(pool.transfer_fee_x.as_ref(), amounts.x)?,
+                pre_fee_amount(pool.transfer_fee_y.as_ref(), amounts.y)?,
+                amount,
+            )
+        }
+    };
+
+    require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+    require!(lp >= amount, AmmError::SlippageExceeded);
+
+    Ok(DepositQuote { x, y, lp })
+}
+
+pub fn quote_deposit_single(pool: &PoolState, is_x: bool, amount_in: u64) -> Result<DepositQuote> {
+    require!(!pool.config.locked, AmmError::PoolLocked);
+    require!(amount_in != 0, AmmError::InvalidAmount);
+    require!(pool.lp_supply != 0, AmmError::NoLiquidityInPool);
+
+    let (reserve_x, reserve_y) = pool.config.reserves(pool.vault_x, pool.vault_y)?;
+    let fee = pool.config().trade_fee(reserve_x, reserve_y)?;
+
+    let received = match is_x {
+        true => amount_in - transfer_fee(pool.transfer_fee_x.as_ref(), amount_in)?,
+        false => amount_in - transfer_fee(pool.transfer_fee_y.as_ref(), amount_in)?,
+    };
+
+    let lp = match pool.config.curve_type {
+        CurveType::ConstantProduct => zap_lp(pool, is_x, received, fee, (reserve_x, reserve_y))?,
+        CurveType::StableSwap => {
+            stable_swap::deposit_single(
+                reserve_x,
+                reserve_y,
+                pool.config.amp(pool.timestamp),
+                fee,
+                pool.lp_supply,
+                is_x,
+                received,
+            )?
+            .0
+        }
+    };
+
+    require!(lp != 0, AmmError::InvalidAmount);
+
+    Ok(match is_x {
+        true => DepositQuote {
+            x: amount_in,
+            y: 0,
+            lp,
+        },
+        false => DepositQuote {
+            x: 0,
+            y: amount_in,
+            lp,
+        },
+    })
+}
+
+pub fn quote_withdraw(pool: &PoolState, amount: u64) -> Result<WithdrawQuote> {
+    require!(!pool.config.locked, AmmError::PoolLocked);
+    require!(amount != 0, AmmError::InvalidAmount);
+    require!(pool.lp_supply != 0, AmmError::NoLiquidityInPool);
+    require!(pool.lp_supply >= amount, AmmError::LiquidityLessThanMinimum);
+
+    let (reserve_x, reserve_y) = pool.config.reserves(pool.vault_x, pool.vault_y)?;
+
+    let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
+        reserve_x,
+        reserve_y,
+        pool.lp_supply,
+        amount,
+        pool.lp_decimals.into(),
+    )
+    .map_err(|_| AmmError::CurveError)?;
+
+    Ok(WithdrawQuote {
+        x: amounts.x - transfer_fee(pool.transfer_fee_x.as_ref(), amounts.x)?,
+        y: amounts.y - transfer_fee(pool.transfer_fee_y.as_ref(), amounts.y)?,
+    })
+}
+
+pub fn quote_withdraw_single(pool: &PoolState, is_x: bool, amount: u64) -> Result<WithdrawQuote> {
+    require!(!pool.config.locked, AmmError::PoolLocked);
+    require!(amount != 0, AmmError::InvalidAmount);
+    require!(pool.lp_supply != 0, AmmError::NoLiquidityInPool);
+    require!(pool.lp_supply >= amount, AmmError::LiquidityLessThanMinimum);
+
+    let (reserve_x, reserve_y) = pool.config.reserves(pool.vault_x, pool.vault_y)?;
+
+    let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
+        reserve_x,
+        reserve_y,
+        pool.lp_supply,
+        amount,
+        pool.lp_decimals.into(),
+    )
+    .map_err(|_| AmmError::CurveError)?;
+
+    let mut config = pool.config();
+    let fee = config.trade_fee(reserve_x, reserve_y)?;
+    let (kept, swapped) = match is_x {
+        true => (amounts.x, amounts.y),
+        false => (amounts.y, amounts.x),
+    };
+    let (out, _) = swap_on_curve(
+        &mut config,
+        fee,
+        (reserve_x - amounts.x, reserve_y - amounts.y),
+        pool.lp_supply - amount,
+        !is_x,
+        swapped,
+        0,
+    )?;
+
+    let total = kept + out;
+
+    Ok(match is_x {
+        true => WithdrawQuote {
+            x: total - transfer_fee(pool.transfer_fee_x.as_ref(), total)?,
+            y: 0,
+        },
+        false => WithdrawQuote {
+            x: 0,
+            y: total - transfer_fee(pool.transfer_fee_y.as_ref(), total)?,
+        },
+    })
+}
+
+// Mirrors `Deposit::zap_lp`
+fn zap_lp(
+    pool: &PoolState,
+    is_x: bool,
+    received: u64,
+    fee: u16,
+    (reserve_x, reserve_y): (u64, u64),
+) -> Result<u64> {
+    let reserve_in = match is_x {
+        true => reserve_x,
+        false => reserve_y,
+    };
+
+    let mut config = pool.config();
+    let swapped = zap_swap_amount(reserve_in, received, fee)?;
+    let (out, _) = swap_on_curve(
+        &mut config,
+        fee,
+        (reserve_x, reserve_y),
+        pool.lp_supply,
+        is_x,
+        swapped,
+        0,
+    )?;
+
+    let (reserve_in, reserve_out) = match is_x {
+        true => config.reserves(pool.vault_x + swapped, pool.vault_y - out)?,
+        false => {
+            let (x, y) = config.reserves(pool.vault_x - out, pool.vault_y + swapped)?;
+            (y, x)
+        }
+    };
+
+    let supply = pool.lp_supply as u128;
+    let lp = std::cmp::min(
+        (received - swapped) as u128 * supply / reserve_in as u128,
+        out as u128 * supply / reserve_out as u128,
+    );
+
+    Ok(lp as u64)
+}
+
+// Everything the program reads from a concentrated-liquidity pool's accounts
+#[derive(Clone)]
+pub struct ClPoolState {
+    pub pool: ClPool,
+    pub tick_arrays: Vec<TickArray>, // In the order they'd be passed to `cl_swap`
+    pub transfer_fee_x: Option<TransferFee>,
+    pub transfer_fee_y: Option<TransferFee>,
+}
+
+impl ClPoolState {
+    pub fn from_account_data(
+        pool: &[u8],
+        tick_arrays: &[&[u8]],
+        mint_x: &[u8],
+        mint_y: &[u8],
+        clock: &Clock,
+    ) -> Result<Self> {
+        Ok(Self {
+            pool: decode_cl_pool(pool)?,
+            tick_arrays: tick_arrays
+                .iter()
+                .map(|data| decode_tick_array(data))
+                .collect::<Result<_>>()?,
+            transfer_fee_x: epoch_transfer_fee(mint_x, clock.epoch)?,
+            transfer_fee_y: epoch_transfer_fee(mint_y, clock.epoch)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidityQuote {
+    pub x: u64, // Token X sent by the user, or received after transfer fees when removing liquidity
+    pub y: u64, // Token Y, same as `x`
+}
+
+// Same arguments as the `cl_swap` instruction. A swap that stops at
+// `sqrt_price_limit` only charges for the part that was filled.
+pub fn quote_cl_swap(
+    state: &ClPoolState,
+    is_x: bool,
+    amount_in: u64,
+    sqrt_price_limit: u128,
+) -> Result<SwapQuote> {
+    require!(amount_in != 0, AmmError::InvalidAmount);
+
+    let (fee_in, fee_out) = match is_x {
+        true => (state.transfer_fee_x.as_ref(), state.transfer_fee_y.as_ref()),
+        false => (state.transfer_fee_y.as_ref(), state.transfer_fee_x.as_ref()),
+    };
+
+    let received = amount_in - transfer_fee(fee_in, amount_in)?;
+
+    let mut pool = state.pool.clone();
+    let mut tick_arrays = state.tick_arrays.clone();
+    let mut tick_arrays: Vec<&mut TickArray> = tick_arrays.iter_mut().collect();
+    let (used, withdraw, _) = clmm::swap(
+        &mut pool,
+        &mut tick_arrays,
+        is_x,
+        received,
+        sqrt_price_limit,
+    )?;
+
+    require!(withdraw != 0, AmmError::InvalidAmount);
+
+    Ok(SwapQuote {
+        amount_in: match used == received {
+            true => amount_in,
+            false => pre_fee_amount(fee_in, used)?,
+        },
+        amount_out: withdraw - transfer_fee(fee_out, withdraw)?,
+        protocol_fee: 0,
+        fee_bps: state.pool.fee,
+    })
+}
+
+// Tokens `increase_liquidity` takes for `liquidity` over the range
+pub fn quote_increase_liquidity(
+    state: &ClPoolState,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Result<LiquidityQuote> {
+    let (x, y) = amounts_for_liquidity(&state.pool, tick_lower, tick_upper, liquidity, true)?;
+
+    Ok(LiquidityQuote {
+        x: pre_fee_amount(state.transfer_fee_x.as_ref(), x)?,
+        y: pre_fee_amount(state.transfer_fee_y.as_ref(), y)?,
+    })
+}
+
+// Tokens `decrease_liquidity` pays out for `liquidity` over the range, fees not included
+pub fn quote_decrease_liquidity(
+    state: &ClPoolState,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Result<LiquidityQuote> {
+    let (x, y) = amounts_for_liquidity(&state.pool, tick_lower, tick_upper, liquidity, false)?;
+
+    Ok(LiquidityQuote {
+        x: x - transfer_fee(state.transfer_fee_x.as_ref(), x)?,
+        y: y - transfer_fee(state.transfer_fee_y.as_ref(), y)?,
+    })
+}
+
+// Rewards `harvest` would pay `stake` at `now` in each of the farm's reward
+// slots, before transfer fees
+pub fn pending_rewards(farm: &Farm, stake: &Stake, now: i64) -> Result<[u64; MAX_REWARDS]> {
+    let mut farm = farm.clone();
+    let mut stake = stake.clone();
+    farm.accrue(now)?;
+    stake.settle(&farm)?;
+
+    Ok(stake.rewards_owed)
+}
+
+// Whether `crank_orders` would fill `order` against the pool as it is, `escrow`
+// being the balance of the order's escrow. Orders earlier in the same crank
+// move the pool, so keepers should check them in the order they pass them.
+pub fn order_fillable(pool: &PoolState, order: &Order, escrow: u64) -> bool {
+    let is_x = order.mint_in == pool.config.mint_x;
+
+    quote_swap(pool, is_x, escrow).is_ok_and(|quote| quote.amount_out >= order.min_amount_out)
+}
+
+fn protocol_fee(before: &Config, after: &Config, is_x: bool) -> u64 {
+    match is_x {
+        true => after.protocol_fees_x - before.protocol_fees_x,
+        false => after.protocol_fees_y - before.protocol_fees_y,
+    }
+}
+
+fn epoch_transfer_fee(mint: &[u8], epoch: u64) -> Result<Option<TransferFee>> {
+    let mint = StateWithExtensions::<Mint>::unpack(mint)?;
+    Ok(mint
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|config| *config.get_epoch_fee(epoch)))
+}
+
+// Same as the program's helpers, on a transfer fee read off-chain
+fn transfer_fee(fee: Option<&TransferFee>, amount: u64) -> Result<u64> {
+    match fee {
+        Some(fee) => Ok(fee.calculate_fee(amount).ok_or(AmmError::Overflow)?),
+        None => Ok(0),
+    }
+}
+
+fn pre_fee_amount(fee: Option<&TransferFee>, post_fee_amount: u64) -> Result<u64> {
+    match fee {
+        Some(fee) => Ok(fee
+            .calculate_pre_fee_amount(post_fee_amount)
+            .ok_or(AmmError::Overflow)?),
+        None => Ok(post_fee_amount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_amm_q4_25::state::Reward;
+    use anchor_lang::prelude::Pubkey;
+
+    use super::*;
+
+    fn pool(x: u64, y: u64, lp_supply: u64, fee: u16, protocol_fee_bps: u16) -> PoolState {
+        PoolState {
+            config: Config {
+                seed: 0,
+                authority: None,
+                initializer: Pubkey::default(),
+                mint_x: Pubkey::new_unique(),
+                mint_y: Pubkey::new_unique(),
+                fee,
+                max_fee: fee,
+                volatility_factor: 0,
+                decay_period: 0,
+                reference_price: 0,
+                reference_timestamp: 0,
+                protocol_fee_bps,
+                protocol_fees_x: 0,
+                protocol_fees_y: 0,
+                curve_type: CurveType::ConstantProduct,
+                initial_amp: 0,
+                target_amp: 0,
+                ramp_start_ts: 0,
+                ramp_stop_ts: 0,
+                reserve_x: 0,
+                reserve_y: 0,
+                price_x_cumulative: 0,
+                price_y_cumulative: 0,
+                last_update_slot: 0,
+                last_update_timestamp: 0,
+                locked: false,
+                gate: false,
+                flash_loan: 0,
+                flash_loan_is_x: false,
+                config_bump: 255,
+                lp_bump: 255,
+            },
+            vault_x: x,
+            vault_y: y,
+            lp_supply,
+            lp_decimals: 6,
+            transfer_fee_x: None,
+            transfer_fee_y: None,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn swap_never_decreases_k() {
+        let state = pool(100_000_000, 250_000_000, 158_113_883, 30, 0);
+
+        for amount in [1_000, 1_000_000, 99_000_000] {
+            let quote = quote_swap(&state, true, amount).unwrap();
+            let before = state.vault_x as u128 * state.vault_y as u128;
+            let after =
+                (state.vault_x + amount) as u128 * (state.vault_y - quote.amount_out) as u128;
+            assert!(after >= before);
+        }
+    }
+
+    #[test]
+    fn exact_out_costs_enough_to_buy_the_output() {
+        let state = pool(100_000_000, 100_000_000, 100_000_000, 30, 0);
+
+        let quote = quote_swap_exact_out(&state, false, 1_000_000).unwrap();
+        assert_eq!(quote.amount_out, 1_000_000);
+        assert!(
+            quote_swap(&state, false, quote.amount_in)
+                .unwrap()
+                .amount_out
+                >= 1_000_000
+        );
+    }
+
+    #[test]
+    fn protocol_takes_its_share_of_the_fee() {
+        let state = pool(100_000_000, 100_000_000, 100_000_000, 30, 5_000);
+
+        // 0.3% of 1 token is 3_000, half of it goes to the protocol
+        let quote = quote_swap(&state, true, 1_000_000).unwrap();
+        assert_eq!(quote.protocol_fee, 1_500);
+
+        // Accrued protocol fees are not part of the reserves
+        let mut accrued = state.clone();
+        accrued.config.protocol_fees_y = 50_000_000;
+        assert!(quote_swap(&accrued, true, 1_000_000).unwrap().amount_out < quote.amount_out);
+    }
+
+    #[test]
+    fn first_deposit_locks_minimum_liquidity() {
+        let state = pool(0, 0, 0, 30, 0);
+
+        let quote = quote_deposit(&state, 1, 4_000_000, 1_000_000).unwrap();
+        assert_eq!(quote.lp, 2_000_000 - MINIMUM_LIQUIDITY);
+        assert_eq!((quote.x, quote.y), (4_000_000, 1_000_000));
+    }
+
+    #[test]
+    fn first_deposit_absorbs_donations() {
+        // Sent to the vault before the pool had any LPs
+        let state = pool(1, 0, 0, 30, 0);
+
+        let quote = quote_deposit(&state, 1, 4_000_000, 1_000_000).unwrap();
+        assert_eq!(quote.lp, 2_000_000 - MINIMUM_LIQUIDITY);
+    }
+
+    #[test]
+    fn deposit_and_withdraw_are_proportional() {
+        let state = pool(100_000_000, 300_000_000, 100_000_000, 30, 0);
+
+        let deposit = quote_deposit(&state, 1_000_000, u64::MAX, u64::MAX).unwrap();
+        assert_eq!((deposit.x, deposit.y), (1_000_000, 3_000_000));
+
+        let withdraw = quote_withdraw(&state, 1_000_000).unwrap();
+        assert_eq!((withdraw.x, withdraw.y), (1_000_000, 3_000_000));
+
+        assert_eq!(
+            quote_deposit(&state, 1_000_000, 999_999, u64::MAX).unwrap_err(),
+            AmmError::SlippageExceeded.into()
+        );
+    }
+
+    #[test]
+    fn single_sided_round_trip_pays_the_fee() {
+        let state = pool(100_000_000, 100_000_000, 100_000_000, 30, 0);
+
+        let deposit = quote_deposit_single(&state, true, 1_000_000).unwrap();
+
+        let mut after = state.clone();
+        after.vault_x += deposit.x;
+        after.lp_supply += deposit.lp;
+        let withdraw = quote_withdraw_single(&after, true, deposit.lp).unwrap();
+
+        assert_eq!(withdraw.y, 0);
+        assert!(withdraw.x < deposit.x);
+        assert!(withdraw.x > deposit.x * 99 / 100);
+    }
+
+    #[test]
+    fn rewards_are_shared_by_stake() {
+        let mut farm = Farm {
+            config: Pubkey::new_unique(),
+            mint_lp: Pubkey::new_unique(),
+            total_staked: 300,
+            last_update_ts: 0,
+            rewards: [Reward::default(); MAX_REWARDS],
+            bump: 255,
+        };
+        farm.rewards[1] = Reward {
+            mint: Pubkey::new_unique(),
+            emissions_per_second: 1_000,
+            start_ts: 0,
+            end_ts: 100,
+            reward_per_share: 0,
+            unallocated: 0,
+        };
+        let stake = Stake {
+            farm: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount: 100,
+            reward_per_share: [0; MAX_REWARDS],
+            rewards_owed: [0; MAX_REWARDS],
+            bump: 255,
+        };
+
+        // A third of the stake, rounded down
+        assert_eq!(pending_rewards(&farm, &stake, 50).unwrap(), [0, 16_666, 0]);
+        // Nothing is emitted past the end of the schedule
+        assert_eq!(pending_rewards(&farm, &stake, 500).unwrap(), [0, 33_333, 0]);
+
+        // Emissions with nothing staked are set aside for the authority
+        farm.total_staked = 0;
+        farm.accrue(40).unwrap();
+        assert_eq!(farm.rewards[1].unallocated, 40_000);
+    }
+
+    #[test]
+    fn order_fills_once_the_price_crosses() {
+        let mut state = pool(100_000_000, 100_000_000, 100_000_000, 30, 0);
+        // Sells 1 X for at least 1.05 Y
+        let order = Order {
+            config: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            id: 0,
+            mint_in: state.config.mint_x,
+            min_amount_out: 1_050_000,
+            keeper_reward: 0,
+            bump: 255,
+        };
+
+        assert!(!order_fillable(&state, &order, 1_000_000));
+
+        // X got 10% more expensive, enough to cover the fee and price impact
+        state.vault_y = 110_000_000;
+        assert!(order_fillable(&state, &order, 1_000_000));
+
+        state.config.locked = true;
+        assert!(!order_fillable(&state, &order, 1_000_000));
+    }
+
+    #[test]
+    fn locked_pool_quotes_nothing() {
+        let mut state = pool(100_000_000, 100_000_000, 100_000_000, 30, 0);
+        state.config.locked = true;
+
+        assert_eq!(
+            quote_swap(&state, true, 1_000).unwrap_err(),
+            AmmError::PoolLocked.into()
+        );
+        assert_eq!(
+            quote_withdraw(&state, 1_000).unwrap_err(),
+            AmmError::PoolLocked.into()
+        );
+    }
+}
@@ -0,0 +1,237 @@
+// Fuzzes the program's concentrated-liquidity math: ticks against 1.0001^(i / 2)
+// in floating point, and in-range swaps against the constant product of the
+// virtual reserves x = L / sqrt(p), y = L * sqrt(p)
+use anchor_amm_q4_25::{
+    clmm::{
+        amounts_for_liquidity, fee_growth_inside, liquidity_for_amounts, sqrt_price_from_tick,
+        swap, tick_from_sqrt_price, MAX_SQRT_PRICE, MAX_TICK, MIN_SQRT_PRICE, MIN_TICK,
+    },
+    state::{ClPool, Position, Tick, TickArray, TICK_ARRAY_SIZE},
+};
+use anchor_lang::prelude::Pubkey;
+use proptest::prelude::*;
+
+const Q64: f64 = 18_446_744_073_709_551_616.0;
+const TICK_SPACING: u16 = 8;
+
+fn pool(tick: i32) -> ClPool {
+    let sqrt_price = sqrt_price_from_tick(tick).unwrap();
+    ClPool {
+        seed: 0,
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        fee: 30,
+        tick_spacing: TICK_SPACING,
+        sqrt_price,
+        tick_current: tick,
+        liquidity: 0,
+        fee_growth_global_x: 0,
+        fee_growth_global_y: 0,
+        bump: 255,
+    }
+}
+
+fn tick_array(start_tick_index: i32) -> TickArray {
+    TickArray {
+        pool: Pubkey::new_unique(),
+        start_tick_index,
+        ticks: [Tick::default(); TICK_ARRAY_SIZE],
+        bump: 255,
+    }
+}
+
+// What `increase_liquidity` does to the pool and the ticks, without the transfers
+fn add_position(pool: &mut ClPool, array: &mut TickArray, lower: i32, upper: i32, liquidity: u128) {
+    array
+        .update_tick(lower, pool, liquidity as i128, false)
+        .unwrap();
+    array
+        .update_tick(upper, pool, liquidity as i128, true)
+        .unwrap();
+    if (lower..upper).contains(&pool.tick_current) {
+        pool.liquidity += liquidity;
+    }
+}
+
+fn assert_relative(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= expected.abs() * tolerance,
+        "{actual} is not within {tolerance} of {expected}"
+    );
+}
+
+#[test]
+fn bounds_match_the_extreme_ticks() {
+    assert_eq!(sqrt_price_from_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE);
+    assert_eq!(sqrt_price_from_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE);
+    assert_eq!(sqrt_price_from_tick(0).unwrap(), 1 << 64);
+    assert!(sqrt_price_from_tick(MIN_TICK - 1).is_err());
+    assert!(sqrt_price_from_tick(MAX_TICK + 1).is_err());
+}
+
+#[test]
+fn swap_crosses_into_the_next_position() {
+    let mut pool = pool(0);
+    let mut array = tick_array(0);
+    // [0, 64) is in range, [64, 128) only once the price moves past tick 64
+    add_position(&mut pool, &mut array, 0, 64, 1_000_000_000);
+    add_position(&mut pool, &mut array, 64, 128, 3_000_000_000);
+    let limit = sqrt_price_from_tick(120).unwrap();
+
+    let (amount_in, amount_out, fee) =
+        swap(&mut pool, &mut [&mut array], false, 10_000_000, limit).unwrap();
+
+    assert_eq!(amount_in, 10_000_000);
+    assert!(amount_out > 0 && amount_out < amount_in);
+    // 0.3% over both ranges, each step rounding its share up
+    assert!(fee.abs_diff(30_000) <= 2);
+    assert!(pool.tick_current >= 64 && pool.tick_current < 120);
+    assert_eq!(pool.liquidity, 3_000_000_000);
+    assert!(pool.fee_growth_global_y > 0);
+
+    // Tick 64 now holds the fees earned below it
+    let crossed = array.tick(64, TICK_SPACING).unwrap();
+    assert_ne!(crossed.fee_growth_outside_y, 0);
+}
+
+#[test]
+fn position_earns_the_swap_fee() {
+    let mut pool = pool(0);
+    let mut array = tick_array(0);
+    add_position(&mut pool, &mut array, 0, 128, 1_000_000_000);
+    let mut position = Position {
+        pool: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        tick_lower: 0,
+        tick_upper: 128,
+        liquidity: 1_000_000_000,
+        fee_growth_inside_x: 0,
+        fee_growth_inside_y: 0,
+        fees_owed_x: 0,
+        fees_owed_y: 0,
+        bump: 255,
+    };
+
+    let limit = sqrt_price_from_tick(120).unwrap();
+    swap(&mut pool, &mut [&mut array], false, 1_000_000, limit).unwrap();
+
+    let inside = fee_growth_inside(
+        &pool,
+        (0, array.tick(0, TICK_SPACING).unwrap()),
+        (128, array.tick(128, TICK_SPACING).unwrap()),
+    );
+    position.update(0, inside).unwrap();
+
+    // The only position in range gets the whole 0.3% fee, minus rounding
+    assert_eq!(position.fees_owed_x, 0);
+    assert!((2_999..=3_000).contains(&position.fees_owed_y));
+}
+
+#[test]
+fn swap_stops_at_the_price_limit() {
+    let mut pool = pool(0);
+    let mut array = tick_array(0);
+    add_position(&mut pool, &mut array, 0, 128, 1_000_000_000);
+    let limit = sqrt_price_from_tick(16).unwrap();
+
+    let (amount_in, _, _) =
+        swap(&mut pool, &mut [&mut array], false, u32::MAX as u64, limit).unwrap();
+
+    assert!(amount_in < u32::MAX as u64);
+    assert_eq!(pool.sqrt_price, limit);
+    assert_eq!(pool.tick_current, 16);
+}
+
+#[test]
+fn swap_needs_the_next_tick_array() {
+    let mut pool = pool(0);
+    let mut array = tick_array(0);
+    add_position(&mut pool, &mut array, 0, 8, 1_000_000_000);
+    let limit = sqrt_price_from_tick(-200).unwrap();
+
+    // Moving down from tick 0 leaves the array at its first tick
+    assert!(swap(&mut pool, &mut [&mut array], true, 1_000_000_000, limit).is_err());
+}
+
+proptest! {
+    #[test]
+    fn sqrt_price_matches_reference(tick in MIN_TICK..=MAX_TICK) {
+        let expected = 1.0001f64.powf(tick as f64 / 2.0) * Q64;
+        assert_relative(sqrt_price_from_tick(tick).unwrap() as f64, expected, 1e-8);
+    }
+
+    #[test]
+    fn tick_from_sqrt_price_inverts_sqrt_price_from_tick(tick in MIN_TICK..MAX_TICK) {
+        let sqrt_price = sqrt_price_from_tick(tick).unwrap();
+        let next = sqrt_price_from_tick(tick + 1).unwrap();
+
+        prop_assert_eq!(tick_from_sqrt_price(sqrt_price).unwrap(), tick);
+        prop_assert_eq!(tick_from_sqrt_price(next - 1).unwrap(), tick);
+    }
+
+    #[test]
+    fn liquidity_for_amounts_never_needs_more_than_the_amounts(
+        tick in -1_000i32..1_000,
+        lower in -150i32..150,
+        width in 1i32..150,
+        max_x in 1_000u64..1_000_000_000_000,
+        max_y in 1_000u64..1_000_000_000_000,
+        crossed_down in any::<bool>(),
+    ) {
+        let mut pool = pool(tick);
+        // A swap down that stops exactly on a tick leaves the current tick below it
+        if crossed_down {
+            pool.tick_current -= 1;
+        }
+        let (lower, upper) = (lower * 8, (lower + width) * 8);
+
+        let liquidity = liquidity_for_amounts(&pool, lower, upper, max_x, max_y).unwrap();
+        let (x, y) = amounts_for_liquidity(&pool, lower, upper, liquidity, true).unwrap();
+        prop_assert!(x <= max_x && y <= max_y);
+
+        // Adding rounds up and removing rounds down
+        let (out_x, out_y) = amounts_for_liquidity(&pool, lower, upper, liquidity, false).unwrap();
+        prop_assert!(out_x <= x && out_y <= y);
+    }
+
+    #[test]
+    fn in_range_swap_matches_virtual_reserves(
+        array in -20i32..20,
+        offset in 0i32..16,
+        liquidity in 1_000_000u128..1_000_000_000_000_000,
+        share in 1u64..1_000,
+        is_x in any::<bool>(),
+    ) {
+        // Around the middle of the array, the position covers all of it
+        let span = TICK_SPACING as i32 * TICK_ARRAY_SIZE as i32;
+        let start = array * span;
+        let tick = start + span / 2 + offset;
+        let (lower, upper) = (start, start + span - TICK_SPACING as i32);
+
+        let mut pool = pool(tick);
+        let mut array = tick_array(start);
+        add_position(&mut pool, &mut array, lower, upper, liquidity);
+
+        let sqrt_price = pool.sqrt_price as f64 / Q64;
+        let (x, y) = (liquidity as f64 / sqrt_price, liquidity as f64 * sqrt_price);
+        let (reserve_in, reserve_out) = if is_x { (x, y) } else { (y, x) };
+        // A fraction of a percent of the virtual reserve never leaves the range
+        let amount = (reserve_in * share as f64 / 1_000_000.0) as u64;
+        prop_assume!(amount > 1_000);
+
+        let limit = match is_x {
+            true => sqrt_price_from_tick(lower).unwrap(),
+            false => sqrt_price_from_tick(upper).unwrap(),
+        };
+        let (amount_in, amount_out, fee) =
+            swap(&mut pool, &mut [&mut array], is_x, amount, limit).unwrap();
+        prop_assert_eq!(amount_in, amount);
+        // The input less the fee is rounded down and what it buys rounded up
+        prop_assert!(fee as f64 >= amount as f64 * 0.003 - 3.0);
+
+        let after_fee = amount as f64 * 0.997;
+        let expected = reserve_out * after_fee / (reserve_in + after_fee);
+        prop_assert!(amount_out as f64 <= expected + 1.0);
+        prop_assert!(amount_out as f64 >= expected * (1.0 - 1e-6) - 2.0);
+    }
+}
@@ -0,0 +1,253 @@
+// Fuzzes the constant product pool through the client quotes, which run the
+// program's own swap, deposit and withdraw math. Whatever the pool and the
+// order of operations, the LPs must never lose: k doesn't shrink, a round trip
+// never pays out more than went in and every rounding goes the pool's way.
+use amm_client::{
+    quote_deposit, quote_swap, quote_swap_exact_out, quote_withdraw, DepositQuote, PoolState,
+    SwapQuote, WithdrawQuote,
+};
+use anchor_amm_q4_25::state::{Config, CurveType, MAX_PROTOCOL_FEE_BPS};
+use anchor_lang::prelude::Pubkey;
+use proptest::{prelude::*, test_runner::RngSeed};
+
+// Fixed so a failure reproduces on every machine, not only the one that saw it
+const SEED: u64 = 0x5eed_a770;
+
+fn pool(x: u64, y: u64, fee: u16, protocol_fee_bps: u16) -> PoolState {
+    PoolState {
+        config: Config {
+            seed: 0,
+            authority: None,
+            initializer: Pubkey::default(),
+            mint_x: Pubkey::new_unique(),
+            mint_y: Pubkey::new_unique(),
+            fee,
+            max_fee: fee,
+            volatility_factor: 0,
+            decay_period: 0,
+            reference_price: 0,
+            reference_timestamp: 0,
+            protocol_fee_bps,
+            protocol_fees_x: 0,
+            protocol_fees_y: 0,
+            curve_type: CurveType::ConstantProduct,
+            initial_amp: 0,
+            target_amp: 0,
+            ramp_start_ts: 0,
+            ramp_stop_ts: 0,
+            reserve_x: 0,
+            reserve_y: 0,
+            price_x_cumulative: 0,
+            price_y_cumulative: 0,
+            last_update_slot: 0,
+            last_update_timestamp: 0,
+            locked: false,
+            gate: false,
+            flash_loan: 0,
+            flash_loan_is_x: false,
+            config_bump: 255,
+            lp_bump: 255,
+        },
+        vault_x: x,
+        vault_y: y,
+        // What the first deposit of (x, y) would have minted
+        lp_supply: (x as u128 * y as u128).isqrt() as u64,
+        lp_decimals: 6,
+        transfer_fee_x: None,
+        transfer_fee_y: None,
+        timestamp: 0,
+    }
+}
+
+fn reserves(pool: &PoolState) -> (u64, u64) {
+    pool.config.reserves(pool.vault_x, pool.vault_y).unwrap()
+}
+
+fn k(pool: &PoolState) -> u128 {
+    let (x, y) = reserves(pool);
+    x as u128 * y as u128
+}
+
+// The pool as the swap instruction leaves it
+fn swapped(pool: &PoolState, is_x: bool, quote: &SwapQuote) -> PoolState {
+    let mut pool = pool.clone();
+    match is_x {
+        true => {
+            pool.vault_x += quote.amount_in;
+            pool.vault_y -= quote.amount_out;
+            pool.config.protocol_fees_x += quote.protocol_fee;
+        }
+        false => {
+            pool.vault_y += quote.amount_in;
+            pool.vault_x -= quote.amount_out;
+            pool.config.protocol_fees_y += quote.protocol_fee;
+        }
+    }
+    pool
+}
+
+fn deposited(pool: &PoolState, quote: &DepositQuote) -> PoolState {
+    let mut pool = pool.clone();
+    pool.vault_x += quote.x;
+    pool.vault_y += quote.y;
+    pool.lp_supply += quote.lp;
+    pool
+}
+
+fn withdrawn(pool: &PoolState, amount: u64, quote: &WithdrawQuote) -> PoolState {
+    let mut pool = pool.clone();
+    pool.vault_x -= quote.x;
+    pool.vault_y -= quote.y;
+    pool.lp_supply -= amount;
+    pool
+}
+
+// Reserve backing each LP token never goes down: after / supply_after >= before / supply_before
+fn share_kept(before: &PoolState, after: &PoolState) -> bool {
+    let (x, y) = reserves(before);
+    let (x_after, y_after) = reserves(after);
+    x_after as u128 * before.lp_supply as u128 >= x as u128 * after.lp_supply as u128
+        && y_after as u128 * before.lp_supply as u128 >= y as u128 * after.lp_supply as u128
+}
+
+fn reserve() -> impl Strategy<Value = u64> {
+    1_000u64..1_000_000_000_000
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 512,
+        rng_seed: RngSeed::Fixed(SEED),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn k_never_decreases(
+        x in reserve(),
+        y in reserve(),
+        fee in 0u16..1_000,
+        protocol_fee_bps in 0u16..=MAX_PROTOCOL_FEE_BPS,
+        swaps in prop::collection::vec((any::<bool>(), 1u64..=u64::MAX), 1..20),
+    ) {
+        let mut pool = pool(x, y, fee, protocol_fee_bps);
+
+        for (is_x, amount_in) in swaps {
+            // Up to doubling the input reserve
+            let reserve_in = match is_x {
+                true => pool.vault_x,
+                false => pool.vault_y,
+            };
+            let Ok(quote) = quote_swap(&pool, is_x, amount_in % reserve_in + 1) else {
+                continue;
+            };
+
+            let after = swapped(&pool, is_x, &quote);
+            prop_assert!(k(&after) >= k(&pool));
+            pool = after;
+        }
+    }
+
+    #[test]
+    fn exact_out_never_decreases_k(
+        x in reserve(),
+        y in reserve(),
+        fee in 0u16..1_000,
+        is_x in any::<bool>(),
+        out in 1u64..=u64::MAX,
+    ) {
+        let pool = pool(x, y, fee, 0);
+        let reserve_out = match is_x {
+            true => y,
+            false => x,
+        };
+        let quote = quote_swap_exact_out(&pool, is_x, out % (reserve_out - 1) + 1).unwrap();
+
+        prop_assert!(k(&swapped(&pool, is_x, &quote)) >= k(&pool));
+    }
+
+    #[test]
+    fn swap_round_trip_never_profits(
+        x in reserve(),
+        y in reserve(),
+        fee in 0u16..1_000,
+        is_x in any::<bool>(),
+        amount_in in 1u64..=u64::MAX,
+    ) {
+        let pool = pool(x, y, fee, 0);
+        let amount_in = amount_in % x.min(y) + 1;
+        let Ok(there) = quote_swap(&pool, is_x, amount_in) else {
+            return Ok(());
+        };
+        let pool = swapped(&pool, is_x, &there);
+        let Ok(back) = quote_swap(&pool, !is_x, there.amount_out) else {
+            return Ok(());
+        };
+
+        prop_assert!(back.amount_out <= amount_in);
+    }
+
+    #[test]
+    fn exact_out_charges_at_least_exact_in(
+        x in reserve(),
+        y in reserve(),
+        fee in 0u16..1_000,
+        is_x in any::<bool>(),
+        out in 1u64..=u64::MAX,
+    ) {
+        let pool = pool(x, y, fee, 0);
+        let reserve_out = match is_x {
+            true => y,
+            false => x,
+        };
+        let amount_out = out % (reserve_out - 1) + 1;
+        let exact_out = quote_swap_exact_out(&pool, is_x, amount_out).unwrap();
+
+        // Paying what exact out asks for buys at least as much through exact in
+        if let Ok(exact_in) = quote_swap(&pool, is_x, exact_out.amount_in) {
+            prop_assert!(exact_in.amount_out >= amount_out);
+        }
+    }
+
+    #[test]
+    fn deposit_rounds_up(
+        x in reserve(),
+        y in reserve(),
+        lp in 1u64..=u64::MAX,
+    ) {
+        let pool = pool(x, y, 30, 0);
+        let lp = lp % pool.lp_supply + 1;
+        let quote = quote_deposit(&pool, lp, u64::MAX, u64::MAX).unwrap();
+
+        prop_assert_eq!(quote.lp, lp);
+        prop_assert!(share_kept(&pool, &deposited(&pool, &quote)));
+    }
+
+    #[test]
+    fn withdraw_rounds_down(
+        x in reserve(),
+        y in reserve(),
+        lp in 1u64..=u64::MAX,
+    ) {
+        let pool = pool(x, y, 30, 0);
+        let lp = lp % pool.lp_supply + 1;
+        let quote = quote_withdraw(&pool, lp).unwrap();
+
+        prop_assert!(share_kept(&pool, &withdrawn(&pool, lp, &quote)));
+    }
+
+    #[test]
+    fn deposit_round_trip_never_profits(
+        x in reserve(),
+        y in reserve(),
+        lp in 1u64..=u64::MAX,
+    ) {
+        let pool = pool(x, y, 30, 0);
+        let lp = lp % pool.lp_supply + 1;
+        let deposit = quote_deposit(&pool, lp, u64::MAX, u64::MAX).unwrap();
+        let pool = deposited(&pool, &deposit);
+        let withdraw = quote_withdraw(&pool, lp).unwrap();
+
+        prop_assert!(withdraw.x <= deposit.x);
+        prop_assert!(withdraw.y <= deposit.y);
+    }
+}
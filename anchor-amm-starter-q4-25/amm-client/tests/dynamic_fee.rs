@@ -0,0 +1,156 @@
+// Runs the program's dynamic fee along simulated price paths. Every step is a
+// trade at (timestamp, reserve_x, reserve_y), priced the way `swap` does it:
+// right after `update_price_cumulatives` stamped the time.
+use anchor_amm_q4_25::state::{Config, CurveType};
+use anchor_lang::prelude::Pubkey;
+use proptest::prelude::*;
+
+const BASE_FEE: u16 = 30;
+const MAX_FEE: u16 = 500;
+const DECAY_PERIOD: i64 = 100;
+
+fn config(volatility_factor: u16) -> Config {
+    Config {
+        seed: 0,
+        authority: None,
+        initializer: Pubkey::default(),
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        fee: BASE_FEE,
+        max_fee: MAX_FEE,
+        volatility_factor,
+        decay_period: DECAY_PERIOD,
+        reference_price: 0,
+        reference_timestamp: 0,
+        protocol_fee_bps: 0,
+        protocol_fees_x: 0,
+        protocol_fees_y: 0,
+        curve_type: CurveType::ConstantProduct,
+        initial_amp: 0,
+        target_amp: 0,
+        ramp_start_ts: 0,
+        ramp_stop_ts: 0,
+        reserve_x: 0,
+        reserve_y: 0,
+        price_x_cumulative: 0,
+        price_y_cumulative: 0,
+        last_update_slot: 0,
+        last_update_timestamp: 0,
+        locked: false,
+        gate: false,
+        flash_loan: 0,
+        flash_loan_is_x: false,
+        config_bump: 255,
+        lp_bump: 255,
+    }
+}
+
+fn stable_config(volatility_factor: u16, amp: u64) -> Config {
+    Config {
+        curve_type: CurveType::StableSwap,
+        initial_amp: amp,
+        target_amp: amp,
+        ..config(volatility_factor)
+    }
+}
+
+fn fees(config: &mut Config, path: &[(i64, u64, u64)]) -> Vec<u16> {
+    path.iter()
+        .map(|&(timestamp, x, y)| {
+            config.last_update_timestamp = timestamp;
+            config.trade_fee(x, y).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn static_fee_ignores_price_moves() {
+    let mut config = config(0);
+    let path = [(0, 1_000, 1_000), (1, 1_000, 3_000), (2, 5_000, 1_000)];
+
+    assert_eq!(fees(&mut config, &path), vec![BASE_FEE; 3]);
+    assert_eq!(config.reference_price, 0);
+}
+
+#[test]
+fn fee_jumps_with_the_price_and_decays_back() {
+    let mut config = config(1_000);
+    // The price of X goes from 1 to 1.1 and stays there
+    let path = [
+        (0, 1_000_000, 1_000_000),
+        (1, 1_000_000, 1_100_000),
+        (51, 1_000_000, 1_100_000),
+        (101, 1_000_000, 1_100_000),
+        (201, 1_000_000, 1_100_000),
+    ];
+
+    let fees = fees(&mut config, &path);
+
+    // A ~9.9% move from the reference, a tenth of it in fees
+    assert_eq!(fees[0], BASE_FEE);
+    assert_eq!(fees[1], BASE_FEE + 98);
+    // Halfway decayed, then halfway again
+    assert_eq!(fees[2], BASE_FEE + 47);
+    assert_eq!(fees[3], BASE_FEE + 23);
+    // A full decay period later the reference has caught up
+    assert_eq!(fees[4], BASE_FEE);
+}
+
+#[test]
+fn fee_is_capped() {
+    let mut config = config(10_000);
+    let path = [(0, 1_000, 1_000), (1, 1_000, 2_000), (2, 1_000, 100_000)];
+
+    assert_eq!(fees(&mut config, &path), vec![BASE_FEE, MAX_FEE, MAX_FEE]);
+}
+
+#[test]
+fn choppy_prices_cost_more_than_calm_ones() {
+    let calm: Vec<_> = (0..20).map(|t| (t * 10, 1_000_000, 1_000_000)).collect();
+    let choppy: Vec<_> = (0..20)
+        .map(|t| match t % 2 {
+            0 => (t * 10, 1_000_000, 1_000_000),
+            _ => (t * 10, 1_000_000, 1_050_000),
+        })
+        .collect();
+
+    let calm = fees(&mut config(2_000), &calm);
+    let choppy = fees(&mut config(2_000), &choppy);
+
+    assert!(calm.iter().all(|&fee| fee == BASE_FEE));
+    assert!(choppy[1..].iter().all(|&fee| fee > BASE_FEE));
+}
+
+#[test]
+fn stable_pool_absorbs_an_imbalance_near_the_peg() {
+    // 30% of the pool's X swapped for Y, in one trade
+    let path = [(0, 1_000_000, 1_000_000), (1, 1_300_000, 700_000)];
+
+    let stable = fees(&mut stable_config(1_000, 1_000), &path);
+    let product = fees(&mut config(1_000), &path);
+
+    // Still trades close to 1:1, where the same reserves are a 46% move
+    assert_eq!(stable, vec![BASE_FEE; 2]);
+    assert_eq!(product[1], BASE_FEE + 459);
+}
+
+proptest! {
+    #[test]
+    fn fee_stays_between_base_and_max(
+        factor in 1u16..=u16::MAX,
+        steps in prop::collection::vec((0i64..300, 1u64..u64::MAX, 1u64..u64::MAX), 1..30),
+    ) {
+        let mut config = config(factor);
+        let mut timestamp = 0;
+
+        for (elapsed, x, y) in steps {
+            timestamp += elapsed;
+            config.last_update_timestamp = timestamp;
+            let fee = config.trade_fee(x, y).unwrap();
+            prop_assert!((BASE_FEE..=MAX_FEE).contains(&fee));
+
+            // Every swap of an instruction is charged the same
+            prop_assert_eq!(config.trade_fee(x, y).unwrap(), fee);
+        }
+    }
+}
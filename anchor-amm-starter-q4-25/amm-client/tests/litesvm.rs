@@ -0,0 +1,983 @@
+use amm_client::{
+    cl_pool_address, decode_allow_entry, decode_cl_pool, decode_config, decode_events, decode_farm,
+    decode_order, decode_pool_entry, decode_pool_index, decode_registry, decode_stake,
+    instructions, order_fillable, pending_rewards, pool_entry_address, pool_index_address,
+    program_data_address, quote_cl_swap, quote_decrease_liquidity, quote_deposit,
+    quote_deposit_single, quote_increase_liquidity, quote_swap, quote_swap_exact_out,
+    quote_withdraw, quote_withdraw_single, registry_address, sort_mints, AmmEvent, ClPoolState,
+    ConcentratedPool, Pool, PoolState,
+};
+use anchor_amm_q4_25::{
+    clmm::{liquidity_for_amounts, sqrt_price_from_tick},
+    events::SwapEvent,
+    state::{CurveType, Twap},
+};
+use anchor_lang::{
+    prelude::Clock,
+    solana_program::{bpf_loader_upgradeable, program_option::COption, program_pack::Pack},
+    AnchorDeserialize,
+};
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token::spl_token::{
+        self,
+        state::{Account as TokenAccount, AccountState, Mint},
+    },
+};
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_sdk::{
+    account::Account, instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+    signature::Keypair, signer::Signer, transaction::Transaction,
+};
+
+// Built by `anchor build`
+const PROGRAM: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../target/deploy/anchor_amm_q4_25.so"
+);
+
+// Deploys the program as upgradeable with `authority` as its upgrade authority,
+// which the registry checks. The loader's account layouts are written by hand:
+// the ProgramData header is a u32 tag, the u64 slot and the Option<Pubkey>
+// authority, followed by the program itself.
+fn deploy(svm: &mut LiteSVM, authority: &Pubkey) {
+    let program_data = program_data_address();
+
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(authority.as_ref());
+    data.extend_from_slice(&std::fs::read(PROGRAM).unwrap());
+    set_loader_account(svm, program_data, data, false);
+
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(program_data.as_ref());
+    set_loader_account(svm, amm_client::ID, data, true);
+
+    // Programs are visible from the slot after they were deployed
+    svm.warp_to_slot(1);
+}
+
+fn set_loader_account(svm: &mut LiteSVM, address: Pubkey, data: Vec<u8>, executable: bool) {
+    let lamports = svm.minimum_balance_for_rent_exemption(data.len());
+    svm.set_account(
+        address,
+        Account {
+            lamports,
+            data,
+            owner: bpf_loader_upgradeable::ID,
+            executable,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+}
+
+struct Harness {
+    svm: LiteSVM,
+    user: Keypair,
+    pool: Pool,
+}
+
+impl Harness {
+    fn new() -> Self {
+        Self::with_curve(CurveType::ConstantProduct, 0)
+    }
+
+    // Pool seeded 1:1 with 100 tokens a side, the user keeps 900 of each
+    fn with_curve(curve_type: CurveType, amp: u64) -> Self {
+        let mut svm = LiteSVM::new();
+        let user = Keypair::new();
+        deploy(&mut svm, &user.pubkey());
+        svm.airdrop(&user.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+        let (mint_x, mint_y) = sort_mints(Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = Pool::new(42, mint_x, mint_y);
+        let mut harness = Self { svm, user, pool };
+        let user = harness.user.pubkey();
+
+        harness.send(instructions::initialize_registry(&user, user, vec![5, 30]));
+
+        harness.set_mint(pool.mint_x);
+        harness.set_mint(pool.mint_y);
+        harness.set_token_account(pool.user_x(&user), pool.mint_x, 1_000_000_000);
+        harness.set_token_account(pool.user_y(&user), pool.mint_y, 1_000_000_000);
+
+        harness.send(instructions::initialize(
+            &user,
+            &pool,
+            30,
+            0,
+            Some(user),
+            curve_type,
+            amp,
+        ));
+        harness.send(instructions::update_protocol_fee(&user, &pool, 2_000));
+        harness.send(instructions::deposit(
+            &user,
+            &pool,
+            1,
+            100_000_000,
+            100_000_000,
+        ));
+
+        harness
+    }
+
+    fn set_mint(&mut self, mint: Pubkey) {
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            mint_authority: COption::None,
+            supply: 2_000_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.set_account(mint, data);
+    }
+
+    fn set_token_account(&mut self, address: Pubkey, mint: Pubkey, amount: u64) {
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount {
+            mint,
+            owner: self.user.pubkey(),
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.set_account(address, data);
+    }
+
+    fn set_account(&mut self, address: Pubkey, data: Vec<u8>) {
+        let lamports = self.svm.minimum_balance_for_rent_exemption(data.len());
+        self.svm
+            .set_account(
+                address,
+                Account {
+                    lamports,
+                    data,
+                    owner: spl_token::ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    // Returns the events the instruction emitted
+    fn send(&mut self, ix: Instruction) -> Vec<AmmEvent> {
+        let (keys, result) = self.execute(&[ix]);
+        let meta = result.unwrap();
+
+        decode_events(meta.inner_instructions.iter().flatten().map(|inner| {
+            let program_id = &keys[inner.instruction.program_id_index as usize];
+            (program_id, inner.instruction.data.as_slice())
+        }))
+    }
+
+    fn try_send(&mut self, ixs: &[Instruction]) -> bool {
+        self.execute(ixs).1.is_ok()
+    }
+
+    fn execute(&mut self, ixs: &[Instruction]) -> (Vec<Pubkey>, TransactionResult) {
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&self.user.pubkey()),
+            &[&self.user],
+            self.svm.latest_blockhash(),
+        );
+        let keys = tx.message.account_keys.clone();
+        let result = self.svm.send_transaction(tx);
+        self.svm.expire_blockhash();
+        (keys, result)
+    }
+
+    // Return data of the instruction, without landing it
+    fn simulate(&self, ix: Instruction) -> Vec<u8> {
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.user.pubkey()),
+            &[&self.user],
+            self.svm.latest_blockhash(),
+        );
+        self.svm
+            .simulate_transaction(tx)
+            .unwrap()
+            .meta
+            .return_data
+            .data
+    }
+
+    fn warp_to(&mut self, unix_timestamp: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    fn data(&self, address: &Pubkey) -> Vec<u8> {
+        self.svm.get_account(address).unwrap().data
+    }
+
+    fn state(&self) -> PoolState {
+        PoolState::from_account_data(
+            &self.data(&self.pool.config),
+            &self.data(&self.pool.mint_x),
+            &self.data(&self.pool.mint_y),
+            &self.data(&self.pool.mint_lp),
+            &self.data(&self.pool.vault_x),
+            &self.data(&self.pool.vault_y),
+            &self.svm.get_sysvar::<Clock>(),
+        )
+        .unwrap()
+    }
+
+    // User's (x, y, lp) balances
+    fn balances(&self) -> (u64, u64, u64) {
+        let user = self.user.pubkey();
+        let amount = |address: Pubkey| {
+            self.svm.get_account(&address).map_or(0, |account| {
+                TokenAccount::unpack(&account.data).unwrap().amount
+            })
+        };
+        (
+            amount(self.pool.user_x(&user)),
+            amount(self.pool.user_y(&user)),
+            amount(self.pool.user_lp(&user)),
+        )
+    }
+}
+
+#[test]
+fn swap_matches_quote() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+
+    let quote = quote_swap(&h.state(), true, 5_000_000).unwrap();
+    let (x, y, _) = h.balances();
+
+    h.send(instructions::swap(
+        &user,
+        &h.pool,
+        true,
+        5_000_000,
+        quote.amount_out,
+    ));
+
+    let (x_after, y_after, _) = h.balances();
+    assert_eq!(x - x_after, quote.amount_in);
+    assert_eq!(y_after - y, quote.amount_out);
+    let config = decode_config(&h.data(&h.pool.config)).unwrap();
+    assert_eq!(config.protocol_fees_x, quote.protocol_fee);
+}
+
+#[test]
+fn swap_emits_event() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    let quote = quote_swap(&h.state(), true, 5_000_000).unwrap();
+
+    let events = h.send(instructions::swap(
+        &user,
+        &h.pool,
+        true,
+        5_000_000,
+        quote.amount_out,
+    ));
+
+    // 0.3% of the input, the protocol's share included
+    let state = h.state();
+    let (reserve_x, reserve_y) = state.config.reserves(state.vault_x, state.vault_y).unwrap();
+    let event = SwapEvent {
+        pool: h.pool.config,
+        user,
+        is_x: true,
+        amount_in: 5_000_000,
+        amount_out: quote.amount_out,
+        fee: 15_000,
+        fee_bps: 30,
+        reserve_x,
+        reserve_y,
+    };
+    assert_eq!(events, vec![AmmEvent::Swap(event)]);
+}
+
+#[test]
+fn swap_exact_out_matches_quote() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+
+    let quote = quote_swap_exact_out(&h.state(), false, 3_000_000).unwrap();
+    let (x, y, _) = h.balances();
+
+    h.send(instructions::swap_exact_out(
+        &user,
+        &h.pool,
+        false,
+        3_000_000,
+        quote.amount_in,
+    ));
+
+    let (x_after, y_after, _) = h.balances();
+    assert_eq!(y - y_after, quote.amount_in);
+    assert_eq!(x_after - x, quote.amount_out);
+    let config = decode_config(&h.data(&h.pool.config)).unwrap();
+    assert_eq!(config.protocol_fees_y, quote.protocol_fee);
+}
+
+#[test]
+fn donation_does_not_move_the_twap() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+
+    h.warp_to(1_000);
+    h.send(instructions::swap(&user, &h.pool, true, 5_000_000, 1));
+    let config = h.state().config;
+
+    // Doubles vault X, the price of X would halve if the vaults were read
+    h.send(
+        spl_token::instruction::transfer(
+            &spl_token::ID,
+            &h.pool.user_x(&user),
+            &h.pool.vault_x,
+            &user,
+            &[],
+            100_000_000,
+        )
+        .unwrap(),
+    );
+    h.warp_to(1_100);
+
+    let twap = Twap::try_from_slice(&h.simulate(instructions::observe(&h.pool, 50))).unwrap();
+    assert_eq!(
+        twap.price_x,
+        ((config.reserve_y as u128) << 64) / config.reserve_x as u128
+    );
+}
+
+#[test]
+fn flash_loan_is_repaid_with_fee() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    let borrow = instructions::flash_borrow(&user, &h.pool, true, 10_000_000);
+    let repay = instructions::flash_repay(&user, &h.pool);
+    let swap = instructions::swap(&user, &h.pool, false, 1_000_000, 0);
+
+    // Unpaid, and with the pool traded before the repay
+    assert!(!h.try_send(&[borrow.clone()]));
+    assert!(!h.try_send(&[borrow.clone(), swap, repay.clone()]));
+
+    let before = h.state();
+    let (x, _, _) = h.balances();
+    h.warp_to(1_000);
+    assert!(h.try_send(&[borrow, repay]));
+
+    // 0.3% of the loan stays with the LPs
+    let after = h.state();
+    assert_eq!(h.balances().0, x - 30_000);
+    assert_eq!(after.vault_x, before.vault_x + 30_000);
+    assert_eq!(after.config.flash_loan, 0);
+
+    // The time since the last update is credited at the reserves before the fee
+    let config = before.config;
+    let elapsed = (1_000 - config.last_update_timestamp) as u128;
+    let price_x = config
+        .spot_price(config.reserve_x, config.reserve_y)
+        .unwrap();
+    assert_eq!(
+        after.config.price_x_cumulative,
+        config.price_x_cumulative.wrapping_add(price_x * elapsed)
+    );
+    assert_eq!(after.config.reserve_x, config.reserve_x + 30_000);
+}
+
+#[test]
+fn donation_before_the_first_deposit_is_absorbed() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+
+    // A second pool of the pair, at the other fee tier
+    h.pool = Pool::new(7, h.pool.mint_x, h.pool.mint_y);
+    h.send(instructions::initialize(
+        &user,
+        &h.pool,
+        5,
+        1,
+        Some(user),
+        CurveType::ConstantProduct,
+        0,
+    ));
+    h.send(
+        spl_token::instruction::transfer(
+            &spl_token::ID,
+            &h.pool.user_x(&user),
+            &h.pool.vault_x,
+            &user,
+            &[],
+            1,
+        )
+        .unwrap(),
+    );
+
+    let quote = quote_deposit(&h.state(), 1, 10_000_000, 10_000_000).unwrap();
+    h.send(instructions::deposit(
+        &user, &h.pool, 1, 10_000_000, 10_000_000,
+    ));
+
+    let state = h.state();
+    assert_eq!(h.balances().2, quote.lp);
+    assert_eq!((state.vault_x, state.vault_y), (10_000_001, 10_000_000));
+}
+
+#[test]
+fn deposit_and_withdraw_match_quotes() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+
+    // Skew the pool first so the amounts aren't trivially equal
+    h.send(instructions::swap(&user, &h.pool, true, 7_000_000, 1));
+
+    let quote = quote_deposit(&h.state(), 10_000_000, u64::MAX, u64::MAX).unwrap();
+    let (x, y, lp) = h.balances();
+
+    h.send(instructions::deposit(
+        &user, &h.pool, 10_000_000, quote.x, quote.y,
+    ));
+
+    let (x_after, y_after, lp_after) = h.balances();
+    assert_eq!((x - x_after, y - y_after), (quote.x, quote.y));
+    assert_eq!(lp_after - lp, quote.lp);
+
+    let quote = quote_withdraw(&h.state(), 25_000_000).unwrap();
+
+    h.send(instructions::withdraw(
+        &user, &h.pool, 25_000_000, quote.x, quote.y,
+    ));
+
+    let (x_final, y_final, lp_final) = h.balances();
+    assert_eq!((x_final - x_after, y_final - y_after), (quote.x, quote.y));
+    assert_eq!(lp_after - lp_final, 25_000_000);
+}
+
+#[test]
+fn single_sided_liquidity_matches_quotes() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+
+    let quote = quote_deposit_single(&h.state(), false, 4_000_000).unwrap();
+    let (x, y, lp) = h.balances();
+
+    h.send(instructions::deposit_single(
+        &user, &h.pool, false, 4_000_000, quote.lp,
+    ));
+
+    let (x_after, y_after, lp_after) = h.balances();
+    assert_eq!((x, y - y_after), (x_after, quote.y));
+    assert_eq!(lp_after - lp, quote.lp);
+
+    let quote = quote_withdraw_single(&h.state(), true, quote.lp).unwrap();
+
+    h.send(instructions::withdraw_single(
+        &user,
+        &h.pool,
+        true,
+        lp_after - lp,
+        quote.x,
+    ));
+
+    let (x_final, y_final, _) = h.balances();
+    assert_eq!((x_final - x_after, y_final), (quote.x, y_after));
+}
+
+#[test]
+fn stable_swap_matches_quote() {
+    let mut h = Harness::with_curve(CurveType::StableSwap, 100);
+    let user = h.user.pubkey();
+
+    let quote = quote_swap(&h.state(), false, 20_000_000).unwrap();
+    let (x, y, _) = h.balances();
+
+    h.send(instructions::swap(
+        &user,
+        &h.pool,
+        false,
+        20_000_000,
+        quote.amount_out,
+    ));
+
+    let (x_after, y_after, _) = h.balances();
+    assert_eq!(y - y_after, quote.amount_in);
+    assert_eq!(x_after - x, quote.amount_out);
+
+    // A constant product pool of the same size pays out noticeably less
+    let constant_product = Harness::new();
+    let cp_quote = quote_swap(&constant_product.state(), false, 20_000_000).unwrap();
+    assert!(quote.amount_out > cp_quote.amount_out);
+}
+
+#[test]
+fn stable_twap_follows_the_marginal_price() {
+    let mut h = Harness::with_curve(CurveType::StableSwap, 100);
+    let user = h.user.pubkey();
+
+    // Leaves the pool well off balance, the curve still prices X close to Y
+    h.warp_to(1_000);
+    h.send(instructions::swap(&user, &h.pool, true, 60_000_000, 1));
+    let config = h.state().config;
+    h.warp_to(1_100);
+
+    let twap = Twap::try_from_slice(&h.simulate(instructions::observe(&h.pool, 50))).unwrap();
+    assert_eq!(
+        twap.price_x,
+        config
+            .spot_price(config.reserve_x, config.reserve_y)
+            .unwrap()
+    );
+
+    // What a small swap is quoted at once the fee is taken off
+    let quote = quote_swap(&h.state(), true, 100_000).unwrap();
+    let after_fee = quote.amount_in as f64 * (10_000 - quote.fee_bps) as f64 / 10_000.0;
+    let quoted = quote.amount_out as f64 / after_fee;
+    let twap_price = twap.price_x as f64 / 2f64.powi(64);
+    assert!((twap_price - quoted).abs() < quoted * 1e-3);
+
+    // The reserve ratio would have reported a fraction of it
+    let ratio = config.reserve_y as f64 / config.reserve_x as f64;
+    assert!(twap_price > 2.0 * ratio);
+}
+
+#[test]
+fn concentrated_liquidity_matches_quotes() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    let pool = ConcentratedPool::new(7, h.pool.mint_x, h.pool.mint_y);
+    let (spacing, range) = (8, (-128, 128));
+
+    // Starts at a price of 1, the arrays cover ticks [-256, 256). The pair's
+    // constant product pool holds the 30 bps tier.
+    h.send(instructions::initialize_cl_pool(
+        &user,
+        &pool,
+        5,
+        1,
+        spacing,
+        1 << 64,
+    ));
+    h.send(instructions::initialize_tick_array(&user, &pool, -256));
+    h.send(instructions::initialize_tick_array(&user, &pool, 0));
+    h.send(instructions::open_position(&user, &pool, range.0, range.1));
+
+    let cl_state = |h: &Harness, is_x: bool| {
+        let state = decode_cl_pool(&h.data(&pool.pool)).unwrap();
+        let tick_arrays = pool.swap_tick_arrays(&state, is_x, 2);
+        let data: Vec<Vec<u8>> = tick_arrays.iter().map(|a| h.data(a)).collect();
+        let data: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+        let state = ClPoolState::from_account_data(
+            &h.data(&pool.pool),
+            &data,
+            &h.data(&pool.mint_x),
+            &h.data(&pool.mint_y),
+            &h.svm.get_sysvar::<Clock>(),
+        )
+        .unwrap();
+        (state, tick_arrays)
+    };
+
+    let (state, _) = cl_state(&h, true);
+    let liquidity =
+        liquidity_for_amounts(&state.pool, range.0, range.1, 50_000_000, 50_000_000).unwrap();
+    let quote = quote_increase_liquidity(&state, range.0, range.1, liquidity).unwrap();
+    let (x, y, _) = h.balances();
+
+    let events = h.send(instructions::increase_liquidity(
+        &user, &pool, spacing, range, liquidity, quote.x, quote.y,
+    ));
+
+    let (x_after, y_after, _) = h.balances();
+    assert_eq!((x - x_after, y - y_after), (quote.x, quote.y));
+    assert!(matches!(
+        &events[..],
+        [AmmEvent::Deposit(e)] if e.pool == pool.pool
+            && (e.amount_x, e.amount_y) == (quote.x, quote.y)
+            && e.lp_amount as u128 == liquidity
+    ));
+
+    // Far enough to leave the position's range, the swap stops at its edge
+    let (state, tick_arrays) = cl_state(&h, true);
+    let limit = sqrt_price_from_tick(-200).unwrap();
+    let quote = quote_cl_swap(&state, true, 80_000_000, limit).unwrap();
+
+    let events = h.send(instructions::cl_swap(
+        &user,
+        &pool,
+        &tick_arrays,
+        true,
+        80_000_000,
+        quote.amount_out,
+        limit,
+    ));
+
+    let (x_swap, y_swap, _) = h.balances();
+    assert!(quote.amount_in < 80_000_000);
+    assert_eq!(x_after - x_swap, quote.amount_in);
+    assert_eq!(y_swap - y_after, quote.amount_out);
+    let [AmmEvent::Swap(swap)] = &events[..] else {
+        panic!("expected a swap event, got {events:?}");
+    };
+    assert_eq!(
+        (swap.pool, swap.amount_in, swap.amount_out, swap.fee_bps),
+        (pool.pool, quote.amount_in, quote.amount_out, 5)
+    );
+    assert!(swap.fee > 0 && swap.fee < quote.amount_in);
+
+    let (state, _) = cl_state(&h, true);
+    let quote = quote_decrease_liquidity(&state, range.0, range.1, liquidity).unwrap();
+
+    let decreased = h.send(instructions::decrease_liquidity(
+        &user, &pool, spacing, range, liquidity, quote.x, quote.y,
+    ));
+    let collected = h.send(instructions::collect_fees(&user, &pool, spacing, range));
+
+    // Principal and the swap fee come back, the vaults keep the rounding
+    let (x_final, y_final, _) = h.balances();
+    assert!(x_final - x_swap > quote.x && y_final - y_swap == quote.y);
+    assert!(x_final <= x && y_final <= y);
+    assert!(matches!(
+        &decreased[..],
+        [AmmEvent::Withdraw(e)] if (e.amount_x, e.amount_y) == (quote.x, quote.y)
+            && e.lp_amount as u128 == liquidity
+    ));
+    // Collecting moves no liquidity, only the fees
+    assert!(matches!(
+        &collected[..],
+        [AmmEvent::Withdraw(e)] if e.amount_x == x_final - x_swap - quote.x
+            && e.amount_y == 0
+            && e.lp_amount == 0
+    ));
+}
+
+#[test]
+fn registry_keeps_one_pool_per_pair_and_fee_tier() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    let (mint_x, mint_y) = (h.pool.mint_x, h.pool.mint_y);
+
+    let entry = decode_pool_entry(&h.data(&pool_entry_address(&mint_x, &mint_y, 30))).unwrap();
+    assert_eq!((entry.config, entry.index), (h.pool.config, 0));
+
+    // Same pair and tier under another seed, reversed, and at a tier not allowed
+    let duplicate = Pool::new(43, mint_x, mint_y);
+    let reversed = Pool::new(44, mint_y, mint_x);
+    let init = |pool: &Pool, fee, index| {
+        instructions::initialize(&user, pool, fee, index, None, CurveType::ConstantProduct, 0)
+    };
+    assert!(!h.try_send(&[init(&duplicate, 30, 1)]));
+    assert!(!h.try_send(&[init(&reversed, 5, 1)]));
+    assert!(!h.try_send(&[init(&duplicate, 100, 1)]));
+
+    // Another tier of the same pair is a new pool
+    h.send(init(&duplicate, 5, 1));
+    h.send(instructions::add_fee_tier(&user, 100));
+    h.send(init(&Pool::new(45, mint_x, mint_y), 100, 2));
+
+    // Concentrated pools go through the same entries
+    let cl_pool = |seed, mint_x, mint_y, fee| {
+        let pool = ConcentratedPool::new(seed, mint_x, mint_y);
+        instructions::initialize_cl_pool(&user, &pool, fee, 3, 8, 1 << 64)
+    };
+    assert!(!h.try_send(&[cl_pool(46, mint_x, mint_y, 30)]));
+    assert!(!h.try_send(&[cl_pool(46, mint_y, mint_x, 30)]));
+    assert!(!h.try_send(&[cl_pool(46, mint_x, mint_y, 1)]));
+    h.send(instructions::add_fee_tier(&user, 1));
+    h.send(cl_pool(46, mint_x, mint_y, 1));
+
+    let registry = decode_registry(&h.data(&registry_address())).unwrap();
+    assert_eq!(registry.fee_tiers, vec![5, 30, 100, 1]);
+    assert_eq!(registry.pool_count, 4);
+    let entry = decode_pool_entry(&h.data(&pool_entry_address(&mint_x, &mint_y, 100))).unwrap();
+    assert_eq!(entry.index, 2);
+    let entry = decode_pool_entry(&h.data(&pool_entry_address(&mint_x, &mint_y, 1))).unwrap();
+    assert_eq!((entry.config, entry.index), (cl_pool_address(46), 3));
+
+    // Every pool can be listed from its index
+    let pools: Vec<Pubkey> = (0..registry.next_index)
+        .map(|index| {
+            decode_pool_index(&h.data(&pool_index_address(index)))
+                .unwrap()
+                .config
+        })
+        .collect();
+    assert_eq!(
+        pools,
+        vec![
+            h.pool.config,
+            duplicate.config,
+            Pool::new(45, mint_x, mint_y).config,
+            cl_pool_address(46),
+        ]
+    );
+}
+
+#[test]
+fn fee_update_moves_the_pool_entry() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    let (mint_x, mint_y) = (h.pool.mint_x, h.pool.mint_y);
+
+    // Only to another tier of the registry
+    assert!(!h.try_send(&[instructions::update_fee(&user, &h.pool, 30, 50)]));
+    h.send(instructions::update_fee(&user, &h.pool, 30, 5));
+
+    assert_eq!(h.state().config.fee, 5);
+    assert!(h
+        .svm
+        .get_account(&pool_entry_address(&mint_x, &mint_y, 30))
+        .is_none_or(|account| account.lamports == 0));
+    let entry = decode_pool_entry(&h.data(&pool_entry_address(&mint_x, &mint_y, 5))).unwrap();
+    assert_eq!((entry.config, entry.index), (h.pool.config, 0));
+
+    // The tier it left is free for another pool of the pair
+    h.send(instructions::initialize(
+        &user,
+        &Pool::new(43, mint_x, mint_y),
+        30,
+        1,
+        None,
+        CurveType::ConstantProduct,
+        0,
+    ));
+    let registry = decode_registry(&h.data(&registry_address())).unwrap();
+    assert_eq!((registry.pool_count, registry.next_index), (2, 2));
+}
+
+#[test]
+fn farm_pays_rewards_to_stakers() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    let reward_mint = Pubkey::new_unique();
+    let user_reward =
+        get_associated_token_address_with_program_id(&user, &reward_mint, &spl_token::ID);
+    h.set_mint(reward_mint);
+    h.set_token_account(user_reward, reward_mint, 1_000_000_000);
+
+    let mut clock = h.svm.get_sysvar::<Clock>();
+    let start = clock.unix_timestamp;
+    h.send(instructions::initialize_farm(&user, &h.pool));
+    h.send(instructions::fund_reward(
+        &user,
+        &h.pool,
+        &reward_mint,
+        &spl_token::ID,
+        1_000,
+        start,
+        start + 1_000,
+    ));
+
+    let (_, _, lp) = h.balances();
+    h.send(instructions::stake_lp(&user, &h.pool, lp / 2));
+
+    clock.unix_timestamp += 100;
+    h.svm.set_sysvar(&clock);
+
+    // Alone in the farm, the user earns every emission, minus rounding
+    let farm = decode_farm(&h.data(&h.pool.farm)).unwrap();
+    let stake = decode_stake(&h.data(&h.pool.stake(&user))).unwrap();
+    let pending = pending_rewards(&farm, &stake, clock.unix_timestamp).unwrap();
+    assert!((99_999..=100_000).contains(&pending[0]));
+    assert_eq!(pending[1..], [0, 0]);
+
+    let reward_balance = |h: &Harness| TokenAccount::unpack(&h.data(&user_reward)).unwrap().amount;
+    let before = reward_balance(&h);
+    h.send(instructions::harvest(
+        &user,
+        &h.pool,
+        &reward_mint,
+        &spl_token::ID,
+    ));
+    assert_eq!(reward_balance(&h) - before, pending[0]);
+
+    h.send(instructions::unstake_lp(&user, &h.pool, lp / 2));
+    assert_eq!(h.balances().2, lp);
+
+    // Nothing is staked for the rest of the schedule, the authority can take
+    // those emissions back once it's over
+    let reclaim = instructions::reclaim_rewards(&user, &h.pool, &reward_mint, &spl_token::ID);
+    assert!(!h.try_send(&[reclaim.clone()]));
+
+    clock.unix_timestamp = start + 1_000;
+    h.svm.set_sysvar(&clock);
+    let before = reward_balance(&h);
+    h.send(reclaim);
+    assert_eq!(reward_balance(&h) - before, 900_000);
+}
+
+#[test]
+fn limit_order_fills_once_the_price_crosses() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    let pool = h.pool;
+
+    // Sell 1 X for at least 1.05 Y
+    h.send(instructions::place_limit_order(
+        &user, &pool, true, 7, 1_000_000, 1_050_000, 5_000,
+    ));
+    let address = pool.order(&user, 7);
+    let escrow = pool.escrow(&address, true);
+    let order = decode_order(&h.data(&address)).unwrap();
+    let escrowed = TokenAccount::unpack(&h.data(&escrow)).unwrap().amount;
+    assert_eq!(escrowed, 1_000_000);
+
+    // The pool only pays ~1 Y per X
+    assert!(!order_fillable(&h.state(), &order, escrowed));
+    let crank = instructions::crank_orders(&user, &pool, &[order.clone()]);
+    assert!(!h.try_send(&[crank.clone()]));
+
+    // Buying X pushes its price past the limit
+    h.send(instructions::swap(&user, &pool, false, 10_000_000, 1));
+    assert!(order_fillable(&h.state(), &order, escrowed));
+    let quote = quote_swap(&h.state(), true, escrowed).unwrap();
+
+    let (_, y, _) = h.balances();
+    let events = h.send(crank);
+
+    assert_eq!(h.balances().1 - y, quote.amount_out);
+    assert!(quote.amount_out >= 1_050_000);
+    assert!(matches!(
+        events.as_slice(),
+        [AmmEvent::Swap(SwapEvent { user: owner, is_x: true, .. })] if *owner == user
+    ));
+    // Order and escrow are closed
+    let closed = |address| {
+        h.svm
+            .get_account(address)
+            .is_none_or(|account| account.lamports == 0)
+    };
+    assert!(closed(&address) && closed(&escrow));
+}
+
+#[test]
+fn gated_pool_requires_an_allowlist_entry() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    h.send(instructions::set_gate(&user, &h.pool, true));
+
+    // No entry passed
+    let swap = |pool| instructions::swap(&user, pool, true, 1_000_000, 1);
+    let flash_loan = |pool| {
+        [
+            instructions::flash_borrow(&user, pool, true, 1_000_000),
+            instructions::flash_repay(&user, pool),
+        ]
+    };
+    assert!(!h.try_send(&[swap(&h.pool)]));
+    assert!(!h.try_send(&flash_loan(&h.pool)));
+
+    let mut clock = h.svm.get_sysvar::<Clock>();
+    h.pool.gate = true;
+    h.send(instructions::add_to_allowlist(
+        &user,
+        &h.pool,
+        user,
+        clock.unix_timestamp + 100,
+    ));
+    let entry = h.pool.allow_entry(&user).unwrap();
+    let expires_at = decode_allow_entry(&h.data(&entry)).unwrap().expires_at;
+    assert_eq!(expires_at, clock.unix_timestamp + 100);
+    h.send(swap(&h.pool));
+    assert!(h.try_send(&flash_loan(&h.pool)));
+
+    // Route swaps can't carry entries
+    let route = instructions::route_swap(&user, &[(h.pool, true)], 1_000_000, 1);
+    assert!(!h.try_send(&[route]));
+
+    clock.unix_timestamp += 100;
+    h.svm.set_sysvar(&clock);
+    assert!(!h.try_send(&[swap(&h.pool)]));
+
+    h.send(instructions::remove_from_allowlist(&user, &h.pool, &user));
+    assert!(h
+        .svm
+        .get_account(&entry)
+        .is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn drained_pool_can_be_closed() {
+    let mut h = Harness::new();
+    let user = h.user.pubkey();
+    let receiver = Pubkey::new_unique();
+    // The user is the registry admin, the dust can't go anywhere else
+    let close = instructions::close_pool(&user, &h.pool, 30, 0, &receiver, &user);
+    let elsewhere = instructions::close_pool(&user, &h.pool, 30, 0, &receiver, &receiver);
+
+    // A farm with a schedule running
+    let reward_mint = Pubkey::new_unique();
+    h.set_mint(reward_mint);
+    h.set_token_account(
+        get_associated_token_address_with_program_id(&user, &reward_mint, &spl_token::ID),
+        reward_mint,
+        1_000_000,
+    );
+    let start = h.svm.get_sysvar::<Clock>().unix_timestamp;
+    h.send(instructions::initialize_farm(&user, &h.pool));
+    h.send(instructions::fund_reward(
+        &user,
+        &h.pool,
+        &reward_mint,
+        &spl_token::ID,
+        1_000,
+        start,
+        start + 100,
+    ));
+
+    // Still has liquidity
+    assert!(!h.try_send(&[close.clone()]));
+
+    let (_, _, lp) = h.balances();
+    h.send(instructions::withdraw(&user, &h.pool, lp, 1, 1));
+    let (x, y, _) = h.balances();
+
+    // Until the schedule is over and the rewards nobody earned are reclaimed
+    assert!(!h.try_send(&[close.clone()]));
+    h.warp_to(start + 100);
+    assert!(!h.try_send(&[close.clone()]));
+    h.send(instructions::reclaim_rewards(
+        &user,
+        &h.pool,
+        &reward_mint,
+        &spl_token::ID,
+    ));
+
+    assert!(!h.try_send(&[elsewhere]));
+    let events = h.send(close);
+    assert!(matches!(
+        &events[..],
+        [AmmEvent::PoolClosed(event)] if event.receiver == receiver
+    ));
+
+    // The locked liquidity's share goes to the admin, the rent to the receiver
+    let (x_after, y_after, _) = h.balances();
+    assert!(x_after > x && y_after > y);
+    assert!(h.svm.get_account(&receiver).unwrap().lamports > 0);
+    for address in [
+        h.pool.config,
+        h.pool.oracle,
+        h.pool.vault_x,
+        h.pool.vault_y,
+        h.pool.config_lp(),
+        pool_entry_address(&h.pool.mint_x, &h.pool.mint_y, 30),
+        pool_index_address(0),
+    ] {
+        assert!(h
+            .svm
+            .get_account(&address)
+            .is_none_or(|account| account.lamports == 0));
+    }
+
+    let registry = decode_registry(&h.data(&registry_address())).unwrap();
+    assert_eq!((registry.pool_count, registry.next_index), (0, 1));
+}
@@ -0,0 +1,169 @@
+// Fuzzes the program's integer StableSwap math against a plain floating point
+// solution of the same invariant: ann * (x + y) + D = ann * D + D^3 / (4 * x * y)
+use anchor_amm_q4_25::stable_swap::{
+    amount_in_for_exact_out, compute_d, compute_y, deposit_single, marginal_price, swap, MAX_AMP,
+    MIN_AMP,
+};
+use proptest::prelude::*;
+
+fn invariant(x: f64, y: f64, d: f64, amp: u64) -> f64 {
+    let ann = amp as f64 * 2.0;
+    ann * (x + y) + d - ann * d - d * d * d / (4.0 * x * y)
+}
+
+// Decreasing in D, positive at 0 and not positive at x + y
+fn reference_d(x: u64, y: u64, amp: u64) -> f64 {
+    let (x, y) = (x as f64, y as f64);
+    let (mut lo, mut hi) = (0.0, x + y);
+    for _ in 0..200 {
+        let mid = (lo + hi) / 2.0;
+        match invariant(x, y, mid, amp) > 0.0 {
+            true => lo = mid,
+            false => hi = mid,
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+// Increasing in y and negative towards 0
+fn reference_y(x: f64, d: f64, amp: u64) -> f64 {
+    let mut hi = d;
+    while invariant(x, hi, d, amp) < 0.0 {
+        hi *= 2.0;
+    }
+    let mut lo = 0.0;
+    for _ in 0..200 {
+        let mid = (lo + hi) / 2.0;
+        match invariant(x, mid, d, amp) < 0.0 {
+            true => lo = mid,
+            false => hi = mid,
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    let tolerance = 4.0 + expected.abs() * 1e-9;
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{actual} is not within {tolerance} of {expected}"
+    );
+}
+
+fn reserve() -> impl Strategy<Value = u64> {
+    1_000u64..10_000_000_000_000
+}
+
+fn amp() -> impl Strategy<Value = u64> {
+    MIN_AMP..=MAX_AMP
+}
+
+proptest! {
+    #[test]
+    fn d_matches_reference(x in reserve(), y in reserve(), amp in amp()) {
+        let d = compute_d(x, y, amp).unwrap();
+        assert_close(d as f64, reference_d(x, y, amp));
+    }
+
+    #[test]
+    fn y_matches_reference(x in reserve(), y in reserve(), amp in amp()) {
+        let d = compute_d(x, y, amp).unwrap();
+        let y_out = compute_y(x as u128, d, amp).unwrap();
+        assert_close(y_out as f64, reference_y(x as f64, d as f64, amp));
+    }
+
+    #[test]
+    fn swap_matches_reference_and_rounds_for_the_pool(
+        x in reserve(),
+        y in reserve(),
+        amp in amp(),
+        fee in 0u16..1_000,
+        share in 1u64..1_000,
+        is_x: bool,
+    ) {
+        let (reserve_in, reserve_out) = if is_x { (x, y) } else { (y, x) };
+        let amount = (reserve_in as u128 * share as u128 / 1_000) as u64 + 1;
+
+        let res = swap(x, y, amp, fee, is_x, amount, 0).unwrap();
+
+        let d = compute_d(x, y, amp).unwrap() as f64;
+        let new_out = reference_y((reserve_in + amount - res.fee) as f64, d, amp);
+        assert_close(res.withdraw as f64, reserve_out as f64 - new_out);
+
+        // The whole input, fee included, stays in the pool
+        let (x1, y1) = if is_x {
+            (x + amount, y - res.withdraw)
+        } else {
+            (x - res.withdraw, y + amount)
+        };
+        prop_assert!(compute_d(x1, y1, amp).unwrap() >= compute_d(x, y, amp).unwrap());
+    }
+
+    #[test]
+    fn exact_out_buys_at_least_the_output(
+        x in reserve(),
+        y in reserve(),
+        amp in amp(),
+        fee in 0u16..1_000,
+        share in 1u64..900,
+    ) {
+        let amount_out = (y as u128 * share as u128 / 1_000) as u64 + 1;
+
+        let amount_in = amount_in_for_exact_out(x, y, amp, amount_out, fee).unwrap();
+
+        let res = swap(x, y, amp, fee, true, amount_in, 0).unwrap();
+        prop_assert!(res.withdraw >= amount_out);
+    }
+
+    #[test]
+    fn marginal_price_matches_a_small_trade(
+        x in 1_000_000_000u64..10_000_000_000_000,
+        y in 1_000_000_000u64..10_000_000_000_000,
+        amp in amp(),
+    ) {
+        let price = marginal_price(x, y, amp).unwrap() as f64 / 2f64.powi(64);
+
+        // A millionth of the pool moves the price by about as much
+        let amount = x / 1_000_000;
+        let res = swap(x, y, amp, 0, true, amount, 0).unwrap();
+        let traded = res.withdraw as f64 / amount as f64;
+        prop_assert!((traded - price).abs() <= price * 1e-4 + 4.0 / amount as f64);
+
+        // The price of Y in X is its inverse
+        let inverse = marginal_price(y, x, amp).unwrap() as f64 / 2f64.powi(64);
+        prop_assert!((price * inverse - 1.0).abs() <= 1e-9);
+    }
+
+    #[test]
+    fn single_sided_deposit_never_beats_its_share_of_d(
+        x in reserve(),
+        y in reserve(),
+        amp in amp(),
+        fee in 0u16..1_000,
+        share in 1u64..1_000,
+    ) {
+        let supply = compute_d(x, y, amp).unwrap() as u64;
+        let amount = (x as u128 * share as u128 / 1_000) as u64 + 1;
+
+        let (lp, _, _) = deposit_single(x, y, amp, fee, supply, true, amount).unwrap();
+        let (lp_without_fee, _, _) = deposit_single(x, y, amp, 0, supply, true, amount).unwrap();
+
+        let d0 = compute_d(x, y, amp).unwrap();
+        let d1 = compute_d(x + amount, y, amp).unwrap();
+        prop_assert!(lp as u128 <= supply as u128 * (d1 - d0) / d0);
+        prop_assert!(lp <= lp_without_fee);
+    }
+}
+
+#[test]
+fn trades_close_to_one_to_one_near_the_peg() {
+    let (x, y) = (1_000_000_000_000, 1_000_000_000_000);
+
+    // 10% of the pool at amp 1000 and no fee barely moves the price
+    let stable = swap(x, y, 1_000, 0, true, 100_000_000_000, 0).unwrap();
+    assert!(stable.withdraw > 99_900_000_000);
+
+    // The same trade at amp 1 already pays a lot more slippage
+    let flat = swap(x, y, 1, 0, true, 100_000_000_000, 0).unwrap();
+    assert!(flat.withdraw < stable.withdraw);
+}
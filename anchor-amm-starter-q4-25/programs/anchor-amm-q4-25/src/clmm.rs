@@ -0,0 +1,423 @@
+use std::ops::DerefMut;
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    state::{ClPool, Tick, TickArray, TICK_ARRAY_SIZE},
+};
+
+#[allow(clippy::manual_div_ceil, clippy::assign_op_pattern)]
+mod u256 {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+pub use u256::U256;
+
+// Prices are Q64.64 square roots of the price of X in Y, tick i sits at
+// sqrt(1.0001)^i. These bounds keep every sqrt price representable.
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_016;
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_521_066_979_257_578_248_091;
+pub const MAX_TICK_SPACING: u16 = 16_384;
+
+const Q64: u128 = 1 << 64;
+
+// 2^64 / sqrt(1.0001)^(2^i), multiplied together for the bits of |tick|
+const TICK_FACTORS: [u128; 19] = [
+    0xfffcb933bd6fad37,
+    0xfff97272373d4132,
+    0xfff2e50f5f656932,
+    0xffe5caca7e10e4e6,
+    0xffcb9843d60f6159,
+    0xff973b41fa98c081,
+    0xff2ea16466c96a38,
+    0xfe5dee046a99a2a8,
+    0xfcbe86c7900a88ae,
+    0xf987a7253ac41317,
+    0xf3392b0822b70005,
+    0xe7159475a2c29b74,
+    0xd097f3bdfd2022b8,
+    0xa9f746462d870fdf,
+    0x70d869a156d2a1b8,
+    0x31be135f97d08fd9,
+    0x9aa508b5b7a84e1,
+    0x5d6af8dedb8119,
+    0x2216e584f5fa,
+];
+
+pub fn sqrt_price_from_tick(tick: i32) -> Result<u128> {
+    require!(
+        (MIN_TICK..=MAX_TICK).contains(&tick),
+        AmmError::InvalidTickRange
+    );
+
+    // Every factor is below 1, so the running product never leaves u128
+    let abs = tick.unsigned_abs();
+    let mut ratio = Q64;
+    for (bit, factor) in TICK_FACTORS.iter().enumerate() {
+        if abs & (1 << bit) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+
+    match tick > 0 {
+        true => Ok(u128::MAX / ratio),
+        false => Ok(ratio),
+    }
+}
+
+// Greatest tick whose sqrt price is at or below `sqrt_price`
+pub fn tick_from_sqrt_price(sqrt_price: u128) -> Result<i32> {
+    require!(
+        (MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price),
+        AmmError::InvalidSqrtPrice
+    );
+
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        match sqrt_price_from_tick(mid)? <= sqrt_price {
+            true => low = mid,
+            false => high = mid - 1,
+        }
+    }
+
+    Ok(low)
+}
+
+// a * b / c with a 256 bit intermediate
+pub fn mul_div(a: u128, b: u128, c: u128, round_up: bool) -> Result<u128> {
+    require!(c != 0, AmmError::Overflow);
+
+    let (a, b, c) = (U256::from(a), U256::from(b), U256::from(c));
+    let product = a * b;
+    let mut result = product / c;
+    if round_up && !(product % c).is_zero() {
+        result += U256::one();
+    }
+
+    to_u128(result)
+}
+
+fn to_u128(value: U256) -> Result<u128> {
+    require!(value.bits() <= 128, AmmError::Overflow);
+    Ok(value.as_u128())
+}
+
+// Token X held by `liquidity` between two sqrt prices:
+// L * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b)
+pub fn amount_x_delta(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (low, high) = (sqrt_a.min(sqrt_b), sqrt_a.max(sqrt_b));
+    let amount = mul_div(liquidity, high - low, high, round_up)?;
+    mul_div(amount, Q64, low, round_up)
+}
+
+// Token Y held by `liquidity` between two sqrt prices: L * (sqrt_b - sqrt_a)
+pub fn amount_y_delta(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (low, high) = (sqrt_a.min(sqrt_b), sqrt_a.max(sqrt_b));
+    mul_div(liquidity, high - low, Q64, round_up)
+}
+
+// Tokens backing `liquidity` over [tick_lower, tick_upper) at the pool's price.
+// Deposits round up and withdrawals round down, in the pool's favour.
+pub fn amounts_for_liquidity(
+    pool: &ClPool,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u64, u64)> {
+    let sqrt_lower = sqrt_price_from_tick(tick_lower)?;
+    let sqrt_upper = sqrt_price_from_tick(tick_upper)?;
+
+    // On the price like `liquidity_for_amounts`, the current tick can sit one
+    // below a boundary the price rests exactly on
+    let (x, y) = match pool.sqrt_price {
+        p if p <= sqrt_lower => (
+            amount_x_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?,
+            0,
+        ),
+        p if p < sqrt_upper => (
+            amount_x_delta(p, sqrt_upper, liquidity, round_up)?,
+            amount_y_delta(sqrt_lower, p, liquidity, round_up)?,
+        ),
+        _ => (
+            0,
+            amount_y_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?,
+        ),
+    };
+
+    Ok((to_u64(x)?, to_u64(y)?))
+}
+
+// Most liquidity over [tick_lower, tick_upper) that `max_x` and `max_y` can back
+pub fn liquidity_for_amounts(
+    pool: &ClPool,
+    tick_lower: i32,
+    tick_upper: i32,
+    max_x: u64,
+    max_y: u64,
+) -> Result<u128> {
+    let sqrt_lower = sqrt_price_from_tick(tick_lower)?;
+    let sqrt_upper = sqrt_price_from_tick(tick_upper)?;
+
+    // Inverse of `amount_x_delta` and `amount_y_delta`, rounded down
+    let from_x = |low: u128, high: u128| -> Result<u128> {
+        let liquidity = mul_div(max_x as u128, low, Q64, false)?;
+        mul_div(liquidity, high, high - low, false)
+    };
+    let from_y = |low: u128, high: u128| mul_div(max_y as u128, Q64, high - low, false);
+
+    // Compared on the price, at exactly the lower tick the range is all X
+    match pool.sqrt_price {
+        p if p <= sqrt_lower => from_x(sqrt_lower, sqrt_upper),
+        p if p < sqrt_upper => Ok(from_x(p, sqrt_upper)?.min(from_y(sqrt_lower, p)?)),
+        _ => from_y(sqrt_lower, sqrt_upper),
+    }
+}
+
+fn to_u64(amount: u128) -> Result<u64> {
+    u64::try_from(amount).map_err(|_| AmmError::Overflow.into())
+}
+
+// Sqrt price after `amount` of the input token is added to `liquidity`.
+// Rounded so the pool never moves further than the input pays for.
+pub fn next_sqrt_price(sqrt_price: u128, liquidity: u128, amount: u64, is_x: bool) -> Result<u128> {
+    match is_x {
+        // L * sqrt_p / (L + amount * sqrt_p)
+        true => {
+            let product = (U256::from(amount) * U256::from(sqrt_price)) >> 64;
+            let denominator = U256::from(liquidity) + product;
+            let numerator = U256::from(liquidity) * U256::from(sqrt_price);
+            let mut next = numerator / denominator;
+            if !(numerator % denominator).is_zero() {
+                next += U256::one();
+            }
+            to_u128(next)
+        }
+        // sqrt_p + amount / L
+        false => {
+            let delta = mul_div(amount as u128, Q64, liquidity, false)?;
+            sqrt_price
+                .checked_add(delta)
+                .ok_or(AmmError::Overflow.into())
+        }
+    }
+}
+
+pub struct SwapStep {
+    pub sqrt_price: u128, // Price the step ends at
+    pub amount_in: u64,   // Input without the fee
+    pub amount_out: u64,
+    pub fee: u64, // Swap fee, paid in the input token
+}
+
+// Exact-in trade within a single liquidity range, from `sqrt_price` towards
+// `sqrt_target`, spending at most `amount_remaining` fee included
+pub fn swap_step(
+    sqrt_price: u128,
+    sqrt_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee: u16,
+    is_x: bool,
+) -> Result<SwapStep> {
+    let fee = fee as u128;
+    let amount_less_fee = amount_remaining as u128 * (10_000 - fee) / 10_000;
+
+    let amount_to_target = match is_x {
+        true => amount_x_delta(sqrt_target, sqrt_price, liquidity, true)?,
+        false => amount_y_delta(sqrt_price, sqrt_target, liquidity, true)?,
+    };
+
+    let reached = amount_less_fee >= amount_to_target;
+    let (next, amount_in) = match reached {
+        true => (sqrt_target, amount_to_target),
+        false => {
+            let next = next_sqrt_price(sqrt_price, liquidity, amount_less_fee as u64, is_x)?;
+            let amount_in = match is_x {
+                true => amount_x_delta(next, sqrt_price, liquidity, true)?,
+                false => amount_y_delta(sqrt_price, next, liquidity, true)?,
+            };
+            (next, amount_in)
+        }
+    };
+
+    let amount_out = match is_x {
+        true => amount_y_delta(next, sqrt_price, liquidity, false)?,
+        false => amount_x_delta(sqrt_price, next, liquidity, false)?,
+    };
+
+    let amount_in = to_u64(amount_in)?;
+    let left = amount_remaining
+        .checked_sub(amount_in)
+        .ok_or(AmmError::Underflow)?;
+
+    // A partial step ends the swap, so whatever it didn't use is the fee
+    let fee = match reached {
+        true => (amount_in as u128 * fee)
+            .div_ceil(10_000 - fee)
+            .min(left as u128) as u64,
+        false => left,
+    };
+
+    Ok(SwapStep {
+        sqrt_price: next,
+        amount_in,
+        amount_out: to_u64(amount_out)?,
+        fee,
+    })
+}
+
+// Fees earned per unit of liquidity inside [tick_lower, tick_upper), for X and Y
+pub fn fee_growth_inside(
+    pool: &ClPool,
+    (tick_lower, lower): (i32, &Tick),
+    (tick_upper, upper): (i32, &Tick),
+) -> (u128, u128) {
+    let (global_x, global_y) = (pool.fee_growth_global_x, pool.fee_growth_global_y);
+
+    let (below_x, below_y) = match pool.tick_current >= tick_lower {
+        true => (lower.fee_growth_outside_x, lower.fee_growth_outside_y),
+        false => (
+            global_x.wrapping_sub(lower.fee_growth_outside_x),
+            global_y.wrapping_sub(lower.fee_growth_outside_y),
+        ),
+    };
+    let (above_x, above_y) = match pool.tick_current < tick_upper {
+        true => (upper.fee_growth_outside_x, upper.fee_growth_outside_y),
+        false => (
+            global_x.wrapping_sub(upper.fee_growth_outside_x),
+            global_y.wrapping_sub(upper.fee_growth_outside_y),
+        ),
+    };
+
+    // Growth counters are allowed to wrap, only their differences matter
+    (
+        global_x.wrapping_sub(below_x).wrapping_sub(above_x),
+        global_y.wrapping_sub(below_y).wrapping_sub(above_y),
+    )
+}
+
+// Exact-in swap of `amount` across as many ticks as it takes, stopping early
+// at `sqrt_price_limit`. `tick_arrays` have to be consecutive in the direction
+// of the trade, starting with the one holding the current tick.
+// Returns (amount_in, amount_out, fee), the fee is part of amount_in.
+pub fn swap<A: DerefMut<Target = TickArray>>(
+    pool: &mut ClPool,
+    tick_arrays: &mut [A],
+    is_x: bool,
+    amount: u64,
+    sqrt_price_limit: u128,
+) -> Result<(u64, u64, u64)> {
+    require!(amount != 0, AmmError::InvalidAmount);
+    match is_x {
+        true => require!(
+            sqrt_price_limit >= MIN_SQRT_PRICE && sqrt_price_limit < pool.sqrt_price,
+            AmmError::InvalidSqrtPrice
+        ),
+        false => require!(
+            sqrt_price_limit <= MAX_SQRT_PRICE && sqrt_price_limit > pool.sqrt_price,
+            AmmError::InvalidSqrtPrice
+        ),
+    }
+
+    let span = pool.tick_spacing as i32 * TICK_ARRAY_SIZE as i32;
+    let first = TickArray::start_index(pool.tick_current, pool.tick_spacing);
+    for (i, array) in tick_arrays.iter().enumerate() {
+        let offset = span * i as i32;
+        let expected = match is_x {
+            true => first - offset,
+            false => first + offset,
+        };
+        require!(
+            array.start_tick_index == expected,
+            AmmError::InvalidTickArray
+        );
+    }
+
+    let (mut remaining, mut amount_out, mut fee) = (amount, 0u64, 0u64);
+    let mut index = 0;
+
+    while remaining != 0 && pool.sqrt_price != sqrt_price_limit {
+        let array = tick_arrays
+            .get_mut(index)
+            .ok_or(AmmError::InvalidTickArray)?;
+
+        // Past this array's last tick in the direction of the trade
+        let Some((tick, initialized)) =
+            array.next_initialized_tick(pool.tick_current, pool.tick_spacing, is_x)
+        else {
+            index += 1;
+            continue;
+        };
+
+        let tick = tick.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_tick = sqrt_price_from_tick(tick)?;
+        let sqrt_target = match is_x {
+            true => sqrt_tick.max(sqrt_price_limit),
+            false => sqrt_tick.min(sqrt_price_limit),
+        };
+
+        let step = swap_step(
+            pool.sqrt_price,
+            sqrt_target,
+            pool.liquidity,
+            remaining,
+            pool.fee,
+            is_x,
+        )?;
+
+        remaining -= step.amount_in + step.fee;
+        amount_out = amount_out
+            .checked_add(step.amount_out)
+            .ok_or(AmmError::Overflow)?;
+        fee += step.fee;
+
+        // The fee stays in the input vault and is owed to the liquidity in range
+        if pool.liquidity != 0 {
+            let growth = mul_div(step.fee as u128, Q64, pool.liquidity, false)?;
+            let global = match is_x {
+                true => &mut pool.fee_growth_global_x,
+                false => &mut pool.fee_growth_global_y,
+            };
+            *global = global.wrapping_add(growth);
+        }
+
+        pool.sqrt_price = step.sqrt_price;
+
+        if step.sqrt_price == sqrt_tick {
+            if initialized {
+                let net = array.cross(tick, pool)?;
+                // Moving down crosses ticks from above, so their net flips sign
+                let net = match is_x {
+                    true => net.checked_neg().ok_or(AmmError::Overflow)?,
+                    false => net,
+                };
+                pool.liquidity = add_delta(pool.liquidity, net)?;
+            }
+
+            pool.tick_current = match is_x {
+                true => tick - 1,
+                false => tick,
+            };
+        } else {
+            pool.tick_current = tick_from_sqrt_price(pool.sqrt_price)?;
+        }
+    }
+
+    Ok((amount - remaining, amount_out, fee))
+}
+
+pub fn add_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    let result = match delta >= 0 {
+        true => liquidity.checked_add(delta as u128),
+        false => liquidity.checked_sub(delta.unsigned_abs()),
+    };
+
+    result.ok_or(AmmError::Overflow.into())
+}
@@ -39,6 +39,52 @@ pub enum AmmError {
     InsufficientBalance,
     #[msg("Zero balance.")]
     ZeroBalance,
+    #[msg("No price observation old enough for the requested window.")]
+    ObservationNotFound,
+    #[msg("Invalid swap route.")]
+    InvalidRoute,
+    #[msg("Invalid amplification coefficient.")]
+    InvalidAmp,
+    #[msg("Invalid amplification ramp.")]
+    InvalidRamp,
+    #[msg("Not supported by this pool's curve.")]
+    InvalidCurveType,
+    #[msg("Invalid tick spacing.")]
+    InvalidTickSpacing,
+    #[msg("Invalid tick range.")]
+    InvalidTickRange,
+    #[msg("Invalid or missing tick array.")]
+    InvalidTickArray,
+    #[msg("Square root price out of range.")]
+    InvalidSqrtPrice,
+    #[msg("Mints must be distinct and sorted.")]
+    InvalidMintPair,
+    #[msg("Fee tier not allowed by the registry.")]
+    InvalidFeeTier,
+    #[msg("A flash loan is in progress on this pool.")]
+    FlashLoanInProgress,
+    #[msg("Flash loan must be repaid later in the same transaction.")]
+    InvalidFlashLoan,
+    #[msg("Invalid dynamic fee parameters.")]
+    InvalidDynamicFee,
+    #[msg("Invalid reward schedule.")]
+    InvalidRewardSchedule,
+    #[msg("Farm already emits the maximum number of reward tokens.")]
+    RewardSlotsFull,
+    #[msg("Invalid limit order.")]
+    InvalidOrder,
+    #[msg("None of the orders could be filled.")]
+    NoOrderFilled,
+    #[msg("User is not on the pool's allowlist.")]
+    NotAllowlisted,
+    #[msg("Allowlist entry expired.")]
+    AllowlistExpired,
+    #[msg("Expiry must be in the future.")]
+    InvalidExpiry,
+    #[msg("Not supported on a gated pool.")]
+    PoolGated,
+    #[msg("Pool still holds liquidity or uncollected protocol fees.")]
+    PoolNotEmpty,
 }
 
 impl From<CurveError> for AmmError {
@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+use crate::state::CurveType;
+
+// Emitted with `emit_cpi!`, as the data of a CPI into the program itself, so
+// they can't be lost to log truncation. Reserves are the vault balances left
+// to the LPs once the instruction's transfers have landed. Concentrated pools
+// emit the same events, with the position's liquidity as the LP amount.
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapEvent {
+    pub pool: Pubkey,    // Config of the pool
+    pub user: Pubkey,    // Trader
+    pub is_x: bool,      // True when X was sold for Y
+    pub amount_in: u64,  // Sent by the user, before the input mint's transfer fee
+    pub amount_out: u64, // Sent from the vault, before the output mint's transfer fee
+    pub fee: u64,        // Swap fee on the input, the protocol's share included
+    pub fee_bps: u16,    // Fee rate the swap paid, moves with volatility on a dynamic fee
+    pub reserve_x: u64,  // Token X reserve after the swap
+    pub reserve_y: u64,  // Token Y reserve after the swap
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DepositEvent {
+    pub pool: Pubkey,   // Config of the pool
+    pub user: Pubkey,   // Liquidity provider
+    pub amount_x: u64,  // Token X sent by the user, 0 for a single-sided Y deposit
+    pub amount_y: u64,  // Token Y sent by the user, 0 for a single-sided X deposit
+    pub lp_amount: u64, // LP tokens minted to the user
+    pub reserve_x: u64, // Token X reserve after the deposit
+    pub reserve_y: u64, // Token Y reserve after the deposit
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawEvent {
+    pub pool: Pubkey,   // Config of the pool
+    pub user: Pubkey,   // Liquidity provider
+    pub amount_x: u64,  // Token X sent from the vault, before transfer fees
+    pub amount_y: u64,  // Token Y sent from the vault, before transfer fees
+    pub lp_amount: u64, // LP tokens burned
+    pub reserve_x: u64, // Token X reserve after the withdrawal
+    pub reserve_y: u64, // Token Y reserve after the withdrawal
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolInitialized {
+    pub pool: Pubkey,              // Config of the new pool
+    pub initializer: Pubkey,       // Paid for the pool's accounts
+    pub mint_x: Pubkey,            // Token X
+    pub mint_y: Pubkey,            // Token Y
+    pub fee: u16,                  // Fee tier the pool is registered under
+    pub curve_type: CurveType,     // Invariant the pool trades on
+    pub amp: u64,                  // StableSwap amplification, 0 for constant product
+    pub authority: Option<Pubkey>, // Admin of the pool, if any
+    pub index: u64,                // Position of the pool in the registry
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolClosed {
+    pub pool: Pubkey,     // Config of the closed pool
+    pub receiver: Pubkey, // Got the rent of the pool's accounts
+    pub amount_x: u64,    // Dust of token X left in the vault, sent to the registry admin
+    pub amount_y: u64,    // Dust of token Y left in the vault, sent to the registry admin
+}
+
+// Settings of a pool after any of the admin instructions
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigUpdated {
+    pub pool: Pubkey,              // Config of the pool
+    pub authority: Option<Pubkey>, // Admin after the update, None once renounced
+    pub locked: bool,              // If the pool is locked
+    pub gate: bool,                // If swaps and liquidity need an allowlist entry
+    pub fee: u16,                  // Swap fee in basis points, the base fee of a dynamic fee
+    pub max_fee: u16,              // Cap on the dynamic fee in basis points
+    pub volatility_factor: u16,    // Dynamic fee per 100% price move, 0 for a static fee
+    pub protocol_fee_bps: u16,     // Share of the swap fee kept by the protocol
+    pub target_amp: u64,           // StableSwap amplification the pool is ramping to
+    pub ramp_stop_ts: i64,         // Unix timestamp the amp ramp ends at
+}
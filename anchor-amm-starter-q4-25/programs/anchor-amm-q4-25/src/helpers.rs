@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{
+            transfer_fee::{TransferFee, TransferFeeConfig},
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint as MintState,
+    },
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{errors::AmmError, state::Config};
+
+// Current transfer fee of a Token-2022 mint, None for classic SPL mints and
+// Token-2022 mints without the transfer-fee extension
+fn epoch_transfer_fee(mint: &InterfaceAccount<Mint>) -> Result<Option<TransferFee>> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == anchor_spl::token::ID {
+        return Ok(None);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => Some(*config.get_epoch_fee(Clock::get()?.epoch)),
+        Err(_) => None,
+    };
+
+    Ok(fee)
+}
+
+// Amount withheld by the mint when `amount` is transferred
+pub fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    match epoch_transfer_fee(mint)? {
+        Some(fee) => Ok(fee.calculate_fee(amount).ok_or(AmmError::Overflow)?),
+        None => Ok(0),
+    }
+}
+
+// Amount that has to be sent so that exactly `post_fee_amount` arrives
+pub fn pre_fee_amount(mint: &InterfaceAccount<Mint>, post_fee_amount: u64) -> Result<u64> {
+    match epoch_transfer_fee(mint)? {
+        Some(fee) => Ok(fee
+            .calculate_pre_fee_amount(post_fee_amount)
+            .ok_or(AmmError::Overflow)?),
+        None => Ok(post_fee_amount),
+    }
+}
+
+// Moves the transfer fees withheld in `account` to its mint, Token-2022 won't
+// close an account that still holds some. The mint has to be writable.
+pub fn harvest_withheld_fees<'info>(
+    account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    if epoch_transfer_fee(mint)?.is_none() {
+        return Ok(());
+    }
+
+    harvest_withheld_tokens_to_mint(
+        CpiContext::new(
+            token_program.to_account_info(),
+            HarvestWithheldTokensToMint {
+                token_program_id: token_program.to_account_info(),
+                mint: mint.to_account_info(),
+            },
+        ),
+        vec![account.to_account_info()],
+    )
+}
+
+// LP reserves once the instruction's transfers have landed, for its event.
+// Stored on the config as what the next price accumulator update runs on.
+pub fn reserves_after(
+    config: &mut Config,
+    vault_x: &mut InterfaceAccount<TokenAccount>,
+    vault_y: &mut InterfaceAccount<TokenAccount>,
+) -> Result<(u64, u64)> {
+    vault_x.reload()?;
+    vault_y.reload()?;
+    let (x, y) = config.reserves(vault_x.amount, vault_y.amount)?;
+    config.reserve_x = x;
+    config.reserve_y = y;
+    Ok((x, y))
+}
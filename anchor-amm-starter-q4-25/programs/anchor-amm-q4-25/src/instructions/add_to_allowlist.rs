@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    state::{AllowEntry, Config},
+};
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        constraint = config.authority.is_some() @ AmmError::NoAuthoritySet,
+        constraint = config.authority == Some(authority.key()) @ AmmError::InvalidAuthority,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"allow", config.key().as_ref(), user.as_ref()],
+        bump,
+        space = AllowEntry::DISCRIMINATOR.len() + AllowEntry::INIT_SPACE,
+    )]
+    pub allow_entry: Account<'info, AllowEntry>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddToAllowlist<'info> {
+    // Also renews or shortens the entry of a user already on the allowlist
+    pub fn add(&mut self, user: Pubkey, expires_at: i64, bumps: AddToAllowlistBumps) -> Result<()> {
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            AmmError::InvalidExpiry
+        );
+
+        self.allow_entry.set_inner(AllowEntry {
+            config: self.config.key(),
+            user,
+            expires_at,
+            bump: bumps.allow_entry,
+        });
+
+        Ok(())
+    }
+}
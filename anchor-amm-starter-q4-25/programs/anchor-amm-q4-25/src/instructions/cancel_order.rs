@@ -0,0 +1,117 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{helpers::harvest_withheld_fees, state::Order};
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    // Closing it also hands the keeper reward back to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            b"order",
+            order.config.as_ref(),
+            owner.key().as_ref(),
+            order.id.to_le_bytes().as_ref(),
+        ],
+        bump = order.bump,
+        has_one = owner,
+        has_one = mint_in,
+    )]
+    pub order: Box<Account<'info, Order>>,
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = order,
+        associated_token::token_program = token_program,
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_in: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the mint sold
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelOrder<'info> {
+    pub fn cancel(&mut self) -> Result<()> {
+        Self::release_escrow(
+            &self.escrow,
+            &self.owner_in,
+            &self.mint_in,
+            &self.token_program,
+            &self.order,
+            &self.owner.to_account_info(),
+        )
+    }
+
+    // Sends the whole escrow to `to` and closes it, its rent going to the
+    // order's owner. `mint` has to be writable so the transfer fees withheld
+    // in the escrow can be harvested. Doesn't borrow `self` so `crank_orders`
+    // can fill orders it only gets as remaining accounts.
+    pub fn release_escrow(
+        escrow: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        token_program: &Interface<'info, TokenInterface>,
+        order: &Account<'info, Order>,
+        owner: &AccountInfo<'info>,
+    ) -> Result<()> {
+        let id = order.id.to_le_bytes();
+        let seeds: &[&[u8]] = &[
+            b"order",
+            order.config.as_ref(),
+            order.owner.as_ref(),
+            &id,
+            &[order.bump],
+        ];
+
+        if escrow.amount != 0 {
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: escrow.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: order.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                escrow.amount,
+                mint.decimals,
+            )?;
+        }
+
+        harvest_withheld_fees(escrow, mint, token_program)?;
+        close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: escrow.to_account_info(),
+                destination: owner.clone(),
+                authority: order.to_account_info(),
+            },
+            &[seeds],
+        ))
+    }
+}
@@ -0,0 +1,207 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    clmm,
+    errors::AmmError,
+    events::SwapEvent,
+    helpers::{pre_fee_amount, transfer_fee},
+    instructions::Swap,
+    state::{ClPool, TickArray},
+};
+
+// The tick arrays the trade can cross go in the remaining accounts, writable,
+// starting with the one holding the current tick and in the trade's direction
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClSwap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"cl_pool", pool.seed.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_x,
+    )]
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_y,
+    )]
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> ClSwap<'info> {
+    // Exact-in swap that stops early at `sqrt_price_limit`, in which case the
+    // user only pays for the part that was filled
+    pub fn swap(
+        &mut self,
+        tick_arrays: &'info [AccountInfo<'info>],
+        is_x: bool,
+        amount: u64,
+        min: u64,
+        sqrt_price_limit: u128,
+    ) -> Result<SwapEvent> {
+        require!(amount != 0, AmmError::InvalidAmount);
+
+        let mut tick_arrays = tick_arrays
+            .iter()
+            .map(|info| {
+                let tick_array = Account::<TickArray>::try_from(info)?;
+                require_keys_eq!(tick_array.pool, self.pool.key(), AmmError::InvalidTickArray);
+                Ok(tick_array)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (mint_in, mint_out) = match is_x {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x),
+        };
+
+        let received = amount - transfer_fee(mint_in, amount)?;
+        let min_withdraw = pre_fee_amount(mint_out, min)?;
+
+        let (used, withdraw, fee) = clmm::swap(
+            &mut self.pool,
+            &mut tick_arrays,
+            is_x,
+            received,
+            sqrt_price_limit,
+        )?;
+
+        require!(withdraw != 0, AmmError::InvalidAmount);
+        require!(withdraw >= min_withdraw, AmmError::SlippageExceeded);
+
+        let amount_in = match used == received {
+            true => amount,
+            false => pre_fee_amount(mint_in, used)?,
+        };
+
+        for tick_array in &tick_arrays {
+            tick_array.exit(&crate::ID)?;
+        }
+
+        self.deposit_tokens(is_x, amount_in)?;
+        self.withdraw_tokens(is_x, withdraw)?;
+
+        // A concentrated pool has no protocol fee, all of it goes to the LPs
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        Ok(SwapEvent {
+            pool: self.pool.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in,
+            amount_out: withdraw,
+            fee,
+            fee_bps: self.pool.fee,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+        })
+    }
+
+    fn deposit_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                &self.user_x,
+                &self.vault_x,
+                &self.mint_x,
+                &self.token_program_x,
+            ),
+            false => (
+                &self.user_y,
+                &self.vault_y,
+                &self.mint_y,
+                &self.token_program_y,
+            ),
+        };
+
+        Swap::transfer_in(from, to, mint, token_program, &self.user, amount)
+    }
+
+    fn withdraw_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
+        // Withdraw from the opposite vault (other kind of token)
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                &self.vault_y,
+                &self.user_y,
+                &self.mint_y,
+                &self.token_program_y,
+            ),
+            false => (
+                &self.vault_x,
+                &self.user_x,
+                &self.mint_x,
+                &self.token_program_x,
+            ),
+        };
+
+        Self::transfer_out(from, to, mint, token_program, &self.pool, amount)
+    }
+
+    // Signed by the pool, shared with the liquidity instructions
+    pub fn transfer_out(
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        token_program: &Interface<'info, TokenInterface>,
+        pool: &Account<'info, ClPool>,
+        amount: u64,
+    ) -> Result<()> {
+        transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: from.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[b"cl_pool".as_ref(), &pool.seed.to_le_bytes(), &[pool.bump]]],
+            ),
+            amount,
+            mint.decimals,
+        )
+    }
+}
@@ -0,0 +1,283 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        burn, close_account, transfer_checked, Burn, CloseAccount, Mint, TokenAccount,
+        TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    errors::AmmError,
+    events::PoolClosed,
+    helpers::harvest_withheld_fees,
+    instructions::MINIMUM_LIQUIDITY,
+    state::{Config, Farm, Oracle, PoolEntry, PoolIndex, Registry},
+};
+
+// Fees and donations grow what the locked liquidity is worth, up to this much
+// of the invariant it's still dust
+pub const MAX_DUST_LIQUIDITY: u128 = 10 * MINIMUM_LIQUIDITY as u128;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub receiver: SystemAccount<'info>,
+    // Writable for the transfer fees withheld in the vaults to be harvested
+    #[account(
+        mut,
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Box<Account<'info, Registry>>,
+    #[account(
+        address = registry.admin @ AmmError::InvalidAuthority,
+    )]
+    pub admin: SystemAccount<'info>, // Gets the dust left in the vaults
+    // Frees the pair and fee tier for a new pool
+    #[account(
+        mut,
+        close = receiver,
+        has_one = config,
+    )]
+    pub pool_entry: Box<Account<'info, PoolEntry>>,
+    // Leaves a gap at the pool's index, indexes aren't reused
+    #[account(
+        mut,
+        close = receiver,
+        seeds = [b"pool_index", pool_entry.index.to_le_bytes().as_ref()],
+        bump = pool_index.bump,
+        has_one = config,
+    )]
+    pub pool_index: Box<Account<'info, PoolIndex>>,
+    #[account(
+        mut,
+        close = receiver,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+        constraint = config.authority.unwrap_or(config.initializer) == authority.key()
+            @ AmmError::InvalidAuthority,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = receiver,
+        seeds = [b"oracle", config.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+    /// CHECK: the pool's farm, which may not exist
+    #[account(
+        seeds = [b"farm", config.key().as_ref()],
+        bump,
+    )]
+    pub farm: UncheckedAccount<'info>,
+    // SPL mints can't be closed, the LP mint outlives the pool
+    #[account(
+        mut,
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+        mint::token_program = token_program,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_lp,
+        associated_token::authority = config,
+        associated_token::token_program = token_program,
+    )]
+    pub config_lp: Box<InterfaceAccount<'info, TokenAccount>>, // Holds the permanently locked MINIMUM_LIQUIDITY
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Get the dust the locked liquidity was backed by
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_x,
+        associated_token::authority = admin,
+        associated_token::token_program = token_program_x,
+    )]
+    pub treasury_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_y,
+        associated_token::authority = admin,
+        associated_token::token_program = token_program_y,
+    )]
+    pub treasury_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the LP mint
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClosePool<'info> {
+    // Only a pool whose LP supply is down to the locked MINIMUM_LIQUIDITY, with
+    // no more than dust behind it, and whose protocol fees were collected can
+    // be closed. The dust goes to the registry admin. The config, oracle, pool
+    // entry and pool index are closed by their constraints, the registry stops
+    // counting the pool.
+    pub fn close(&mut self) -> Result<PoolClosed> {
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        require!(
+            self.config.protocol_fees_x == 0 && self.config.protocol_fees_y == 0,
+            AmmError::PoolNotEmpty
+        );
+        require!(
+            self.mint_lp.supply == self.config_lp.amount,
+            AmmError::PoolNotEmpty
+        );
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        require!(
+            self.config.invariant(reserve_x, reserve_y)? <= MAX_DUST_LIQUIDITY,
+            AmmError::PoolNotEmpty
+        );
+        self.check_farm()?;
+
+        let seed = self.config.seed.to_le_bytes();
+        let signer_seeds: &[&[&[u8]]] = &[&[b"config", &seed, &[self.config.config_bump]]];
+
+        if self.config_lp.amount != 0 {
+            burn(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    Burn {
+                        mint: self.mint_lp.to_account_info(),
+                        from: self.config_lp.to_account_info(),
+                        authority: self.config.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                self.config_lp.amount,
+            )?;
+        }
+        close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: self.config_lp.to_account_info(),
+                destination: self.receiver.to_account_info(),
+                authority: self.config.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let amount_x = self.close_vault(true, signer_seeds)?;
+        let amount_y = self.close_vault(false, signer_seeds)?;
+
+        self.registry.pool_count = self
+            .registry
+            .pool_count
+            .checked_sub(1)
+            .ok_or(AmmError::Underflow)?;
+
+        Ok(PoolClosed {
+            pool: self.config.key(),
+            receiver: self.receiver.key(),
+            amount_x,
+            amount_y,
+        })
+    }
+
+    // The farm outlives the pool so stakers can still harvest what they're
+    // owed, but nothing can fund it or reclaim from it without the config.
+    // Every schedule has to be over and what's unallocated reclaimed first.
+    fn check_farm(&self) -> Result<()> {
+        if self.farm.owner != &crate::ID {
+            return Ok(());
+        }
+
+        let mut farm = Farm::try_deserialize(&mut &self.farm.try_borrow_data()?[..])?;
+        let now = Clock::get()?.unix_timestamp;
+        farm.accrue(now)?;
+        require!(
+            farm.rewards
+                .iter()
+                .all(|reward| reward.end_ts <= now && reward.unallocated == 0),
+            AmmError::PoolNotEmpty
+        );
+
+        Ok(())
+    }
+
+    // Empties the vault into the treasury and closes it, returns the amount
+    // that was left in it
+    fn close_vault(&self, is_x: bool, signer_seeds: &[&[&[u8]]]) -> Result<u64> {
+        let (vault, treasury, mint, token_program) = match is_x {
+            true => (
+                &self.vault_x,
+                &self.treasury_x,
+                &self.mint_x,
+                &self.token_program_x,
+            ),
+            false => (
+                &self.vault_y,
+                &self.treasury_y,
+                &self.mint_y,
+                &self.token_program_y,
+            ),
+        };
+
+        let amount = vault.amount;
+        if amount != 0 {
+            transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: vault.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: treasury.to_account_info(),
+                        authority: self.config.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                mint.decimals,
+            )?;
+        }
+
+        harvest_withheld_fees(vault, mint, token_program)?;
+        close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: vault.to_account_info(),
+                destination: self.receiver.to_account_info(),
+                authority: self.config.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        Ok(amount)
+    }
+}
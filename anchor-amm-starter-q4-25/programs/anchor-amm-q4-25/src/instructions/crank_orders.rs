@@ -0,0 +1,245 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    events::SwapEvent,
+    helpers::{pre_fee_amount, reserves_after, transfer_fee},
+    instructions::{swap_on_curve, CancelOrder, Swap},
+    state::{Config, Oracle, Order},
+};
+
+// Remaining accounts per order, in this order:
+// order, escrow, owner_out, owner
+pub const ORDER_ACCOUNTS: usize = 4;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CrankOrders<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    // Writable for the transfer fees withheld in the escrows to be harvested
+    #[account(
+        mut,
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"oracle", config.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+struct LimitOrder<'info> {
+    order: Box<Account<'info, Order>>,
+    escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    owner_out: Box<InterfaceAccount<'info, TokenAccount>>,
+    owner: &'info AccountInfo<'info>,
+}
+
+impl<'info> CrankOrders<'info> {
+    // Fills every order the pool can pay its limit price, the others are
+    // skipped and stay open. Fails if none could be filled so keepers don't
+    // pay for empty cranks. Returns the event of every fill.
+    pub fn crank(&mut self, orders: &'info [AccountInfo<'info>]) -> Result<Vec<SwapEvent>> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        // Order owners aren't checked against the allowlist
+        require!(!self.config.gate, AmmError::PoolGated);
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        require!(
+            !orders.is_empty() && orders.len() % ORDER_ACCOUNTS == 0,
+            AmmError::InvalidOrder
+        );
+
+        let mut events = Vec::new();
+
+        for accounts in orders.chunks(ORDER_ACCOUNTS) {
+            let order = self.load_order(accounts)?;
+            if let Some(event) = self.fill(order)? {
+                events.push(event);
+            }
+        }
+
+        require!(!events.is_empty(), AmmError::NoOrderFilled);
+
+        Ok(events)
+    }
+
+    // Same checks the `CancelOrder` account constraints do, on accounts we
+    // only get as `remaining_accounts`
+    fn load_order(&self, accounts: &'info [AccountInfo<'info>]) -> Result<LimitOrder<'info>> {
+        let [order, escrow, owner_out, owner] = accounts else {
+            return err!(AmmError::InvalidOrder);
+        };
+
+        let limit_order = LimitOrder {
+            order: Box::new(Account::try_from(order)?),
+            escrow: Box::new(InterfaceAccount::try_from(escrow)?),
+            owner_out: Box::new(InterfaceAccount::try_from(owner_out)?),
+            owner,
+        };
+        let order = &limit_order.order;
+
+        require_keys_eq!(order.config, self.config.key(), AmmError::InvalidOrder);
+        require_keys_eq!(owner.key(), order.owner, AmmError::InvalidOrder);
+
+        let (mint_in, mint_out) = match order.mint_in == self.mint_x.key() {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x),
+        };
+        require_keys_eq!(
+            escrow.key(),
+            get_associated_token_address_with_program_id(
+                &order.key(),
+                &mint_in.key(),
+                &self.token_program_for(mint_in).key(),
+            ),
+            AmmError::InvalidOrder
+        );
+        require_keys_eq!(
+            limit_order.owner_out.owner,
+            order.owner,
+            AmmError::InvalidOrder
+        );
+        require_keys_eq!(
+            limit_order.owner_out.mint,
+            mint_out.key(),
+            AmmError::InvalidToken
+        );
+
+        Ok(limit_order)
+    }
+
+    // Sells the whole escrow, priced and booked exactly like `Swap::swap`,
+    // then closes the order and pays its keeper reward. None if the pool
+    // can't meet the limit price yet.
+    fn fill(&mut self, limit_order: LimitOrder<'info>) -> Result<Option<SwapEvent>> {
+        let LimitOrder {
+            order,
+            escrow,
+            owner_out,
+            owner,
+        } = limit_order;
+        let is_x = order.mint_in == self.mint_x.key();
+
+        let (x, y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.config.update_price_cumulatives()?;
+        self.oracle.record(self.config.observation());
+        let fee_bps = self.config.trade_fee(x, y)?;
+
+        let (mint_in, mint_out, vault_in, vault_out) = match is_x {
+            true => (&self.mint_x, &self.mint_y, &self.vault_x, &self.vault_y),
+            false => (&self.mint_y, &self.mint_x, &self.vault_y, &self.vault_x),
+        };
+
+        let amount = escrow.amount;
+        let received = amount - transfer_fee(mint_in, amount)?;
+        let min_withdraw = pre_fee_amount(mint_out, order.min_amount_out)?;
+
+        // Quoted on a copy so an order below its limit leaves the pool as it
+        // was. Any error, the limit included, keeps the order for a later crank.
+        let mut quote = Config::clone(&self.config);
+        let Ok((withdraw, fee)) = swap_on_curve(
+            &mut quote,
+            fee_bps,
+            (x, y),
+            self.mint_lp.supply,
+            is_x,
+            received,
+            min_withdraw,
+        ) else {
+            return Ok(None);
+        };
+        self.config.set_inner(quote);
+
+        CancelOrder::release_escrow(
+            &escrow,
+            vault_in,
+            mint_in,
+            self.token_program_for(mint_in),
+            &order,
+            owner,
+        )?;
+        Swap::transfer_out(
+            vault_out,
+            &owner_out,
+            mint_out,
+            self.token_program_for(mint_out),
+            &self.config,
+            withdraw,
+        )?;
+
+        // The order's rent goes back to its owner with whatever is left once
+        // the keeper is paid
+        order.sub_lamports(order.keeper_reward)?;
+        self.keeper.add_lamports(order.keeper_reward)?;
+        order.close(owner.clone())?;
+
+        let (reserve_x, reserve_y) =
+            reserves_after(&mut self.config, &mut self.vault_x, &mut self.vault_y)?;
+
+        Ok(Some(SwapEvent {
+            pool: self.config.key(),
+            user: owner.key(),
+            is_x,
+            amount_in: amount,
+            amount_out: withdraw,
+            fee,
+            fee_bps,
+            reserve_x,
+            reserve_y,
+        }))
+    }
+
+    fn token_program_for(
+        &self,
+        mint: &InterfaceAccount<'info, Mint>,
+    ) -> &Interface<'info, TokenInterface> {
+        match mint.key() == self.mint_x.key() {
+            true => &self.token_program_x,
+            false => &self.token_program_y,
+        }
+    }
+}
@@ -1,63 +1,110 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer},
+    token_interface::{
+        mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 use constant_product_curve::ConstantProduct;
 
-use crate::{errors::AmmError, state::Config};
+use crate::{
+    errors::AmmError,
+    events::DepositEvent,
+    helpers::{pre_fee_amount, reserves_after, transfer_fee},
+    instructions::swap_on_curve,
+    stable_swap,
+    state::{AllowEntry, Config, CurveType, Oracle},
+};
+
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    pub mint_x: Account<'info, Mint>,
-    pub mint_y: Account<'info, Mint>,
     #[account(
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
         has_one = mint_x,
         has_one = mint_y,
         seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump,
     )]
     pub config: Account<'info, Config>,
+    // Only checked on gated pools, None otherwise
+    #[account(
+        seeds = [b"allow", config.key().as_ref(), user.key().as_ref()],
+        bump = allow_entry.bump,
+    )]
+    pub allow_entry: Option<Box<Account<'info, AllowEntry>>>,
+    #[account(
+        mut,
+        seeds = [b"oracle", config.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
     #[account(
         mut,
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump,
+        mint::token_program = token_program,
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = user,
+        associated_token::token_program = token_program_x,
     )]
-    pub user_x: Account<'info, TokenAccount>,
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = user,
+        associated_token::token_program = token_program_y,
     )]
-    pub user_y: Account<'info, TokenAccount>,
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_lp,
         associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub user_lp: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub user_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = config,
+        associated_token::token_program = token_program,
+    )]
+    pub config_lp: Box<InterfaceAccount<'info, TokenAccount>>, // Holds the permanently locked MINIMUM_LIQUIDITY
+    pub token_program: Interface<'info, TokenInterface>, // Owns the LP mint
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -65,64 +112,226 @@ pub struct Deposit<'info> {
 impl<'info> Deposit<'info> {
     pub fn deposit(
         &mut self,
-        amount: u64, // Amount of LP tokens that the user wants to "claim"
+        amount: u64, // Amount of LP tokens that the user wants to "claim" (the minimum on the first deposit)
         max_x: u64,  // Maximum amount of token X that the user is willing to deposit
         max_y: u64,  // Maximum amount of token Y that the user is willing to deposit
-    ) -> Result<()> {
-        require!(self.config.locked == false, AmmError::PoolLocked);
+    ) -> Result<DepositEvent> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        self.config
+            .check_gate(self.allow_entry.as_ref().map(|entry| entry.expires_at))?;
         require!(amount != 0, AmmError::InvalidAmount);
 
-        let (x, y) = match self.mint_lp.supply == 0
-            && self.vault_x.amount == 0
-            && self.vault_y.amount == 0
-        {
-            true => (max_x, max_y),
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.config.update_price_cumulatives()?;
+        self.oracle.record(self.config.observation());
+
+        // Anything sent to the vaults before the pool had LPs goes to the first
+        // depositor, so a donation can't stop the pool from ever being funded
+        let first_deposit = self.mint_lp.supply == 0;
+
+        // (x, y) is what the user sends, LP is priced on what the vaults actually
+        // receive once Token-2022 transfer fees are withheld
+        let (x, y, lp) = match first_deposit {
+            // The first depositor sets the price and gets the invariant's D in LP tokens,
+            // sqrt(x * y) for constant product, minus MINIMUM_LIQUIDITY which is locked
+            // forever so the share price can't be inflated
+            true => {
+                let received_x = max_x - transfer_fee(&self.mint_x, max_x)?;
+                let received_y = max_y - transfer_fee(&self.mint_y, max_y)?;
+                let lp = u64::try_from(self.config.invariant(received_x, received_y)?)
+                    .map_err(|_| AmmError::Overflow)?;
+                require!(lp > MINIMUM_LIQUIDITY, AmmError::LiquidityLessThanMinimum);
+                (max_x, max_y, lp - MINIMUM_LIQUIDITY)
+            }
+            // Proportional to the reserves, which keeps the price on either curve
             false => {
                 let amounts = ConstantProduct::xy_deposit_amounts_from_l(
-                    self.vault_x.amount,
-                    self.vault_y.amount,
+                    reserve_x,
+                    reserve_y,
                     self.mint_lp.supply,
                     amount,
-                    6,
+                    self.mint_lp.decimals.into(),
                 )
                 .map_err(|_| AmmError::CurveError)?;
-                (amounts.x, amounts.y)
+                (
+                    pre_fee_amount(&self.mint_x, amounts.x)?,
+                    pre_fee_amount(&self.mint_y, amounts.y)?,
+                    amount,
+                )
             }
         };
 
         require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+        require!(lp >= amount, AmmError::SlippageExceeded);
 
         self.deposit_tokens(true, x)?;
         self.deposit_tokens(false, y)?;
-        self.mint_lp_tokens(amount)
+        if first_deposit {
+            self.mint_lp_tokens(self.config_lp.to_account_info(), MINIMUM_LIQUIDITY)?;
+        }
+        self.mint_lp_tokens(self.user_lp.to_account_info(), lp)?;
+
+        self.event(x, y, lp)
+    }
+
+    pub fn deposit_single(
+        &mut self,
+        is_x: bool,  // Side the user deposits
+        amount: u64, // Amount of that token the user sends
+        min_lp: u64, // Minimum amount of LP tokens the user wants to receive
+    ) -> Result<DepositEvent> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        self.config
+            .check_gate(self.allow_entry.as_ref().map(|entry| entry.expires_at))?;
+        require!(amount != 0, AmmError::InvalidAmount);
+        require!(self.mint_lp.supply != 0, AmmError::NoLiquidityInPool);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.config.update_price_cumulatives()?;
+        self.oracle.record(self.config.observation());
+        let fee = self.config.trade_fee(reserve_x, reserve_y)?;
+
+        let received = match is_x {
+            true => amount - transfer_fee(&self.mint_x, amount)?,
+            false => amount - transfer_fee(&self.mint_y, amount)?,
+        };
+
+        let lp = match self.config.curve_type {
+            CurveType::ConstantProduct => {
+                self.zap_lp(is_x, received, fee, (reserve_x, reserve_y))?
+            }
+            CurveType::StableSwap => {
+                let (lp, fee_in, fee_out) = stable_swap::deposit_single(
+                    reserve_x,
+                    reserve_y,
+                    self.config.current_amp(),
+                    fee,
+                    self.mint_lp.supply,
+                    is_x,
+                    received,
+                )?;
+                self.config.accrue_protocol_fee(is_x, fee_in)?;
+                self.config.accrue_protocol_fee(!is_x, fee_out)?;
+                lp
+            }
+        };
+
+        require!(lp != 0, AmmError::InvalidAmount);
+        require!(lp >= min_lp, AmmError::SlippageExceeded);
+
+        self.deposit_tokens(is_x, amount)?;
+        self.mint_lp_tokens(self.user_lp.to_account_info(), lp)?;
+
+        match is_x {
+            true => self.event(amount, 0, lp),
+            false => self.event(0, amount, lp),
+        }
+    }
+
+    fn event(&mut self, amount_x: u64, amount_y: u64, lp_amount: u64) -> Result<DepositEvent> {
+        let (reserve_x, reserve_y) =
+            reserves_after(&mut self.config, &mut self.vault_x, &mut self.vault_y)?;
+
+        Ok(DepositEvent {
+            pool: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount,
+            reserve_x,
+            reserve_y,
+        })
+    }
+
+    // LP tokens for a single-sided deposit into a constant product pool
+    fn zap_lp(
+        &mut self,
+        is_x: bool,
+        received: u64,
+        fee: u16,
+        (reserve_x, reserve_y): (u64, u64),
+    ) -> Result<u64> {
+        let reserve_in = match is_x {
+            true => reserve_x,
+            false => reserve_y,
+        };
+
+        // Part of the deposit is swapped through the curve, paying the pool fee.
+        // Its output never leaves the vault, it is deposited along with the rest.
+        let swapped = zap_swap_amount(reserve_in, received, fee)?;
+        let (out, _) = swap_on_curve(
+            &mut self.config,
+            fee,
+            (reserve_x, reserve_y),
+            self.mint_lp.supply,
+            is_x,
+            swapped,
+            0,
+        )?;
+
+        let (reserve_in, reserve_out) = match is_x {
+            true => self
+                .config
+                .reserves(self.vault_x.amount + swapped, self.vault_y.amount - out)?,
+            false => {
+                let (x, y) = self
+                    .config
+                    .reserves(self.vault_x.amount - out, self.vault_y.amount + swapped)?;
+                (y, x)
+            }
+        };
+
+        let supply = self.mint_lp.supply as u128;
+        let lp = std::cmp::min(
+            (received - swapped) as u128 * supply / reserve_in as u128,
+            out as u128 * supply / reserve_out as u128,
+        );
+
+        Ok(lp as u64)
     }
 
     pub fn deposit_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
-        let (from, to) = match is_x {
+        let (from, to, mint, decimals, token_program) = match is_x {
             true => (
                 self.user_x.to_account_info(),
                 self.vault_x.to_account_info(),
+                self.mint_x.to_account_info(),
+                self.mint_x.decimals,
+                self.token_program_x.to_account_info(),
             ),
             false => (
                 self.user_y.to_account_info(),
                 self.vault_y.to_account_info(),
+                self.mint_y.to_account_info(),
+                self.mint_y.decimals,
+                self.token_program_y.to_account_info(),
             ),
         };
 
-        transfer(
+        transfer_checked(
             CpiContext::new(
-                self.token_program.to_account_info(),
-                Transfer {
+                token_program,
+                TransferChecked {
                     from,
+                    mint,
                     to,
                     authority: self.user.to_account_info(),
                 },
             ),
             amount,
+            decimals,
         )
     }
 
-    pub fn mint_lp_tokens(&self, amount: u64) -> Result<()> {
+    pub fn mint_lp_tokens(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
         let cpi_program = self.token_program.to_account_info();
 
         let signer_seeds: &[&[&[u8]]] = &[&[
@@ -136,7 +345,7 @@ impl<'info> Deposit<'info> {
                 cpi_program,
                 MintTo {
                     mint: self.mint_lp.to_account_info(),
-                    to: self.user_lp.to_account_info(),
+                    to,
                     authority: self.config.to_account_info(),
                 },
                 signer_seeds,
@@ -145,3 +354,26 @@ impl<'info> Deposit<'info> {
         )
     }
 }
+
+// Part of `amount` to swap so that what is left and the swap output match the
+// pool's ratio after the swap. Positive root of
+// g * s^2 + r * (10_000 + g) * s - 10_000 * a * r = 0 with g = 10_000 - fee,
+// written as 20_000 * a / (10_000 + g + sqrt(e / r)) to avoid cancellation.
+pub fn zap_swap_amount(reserve_in: u64, amount: u64, fee: u16) -> Result<u64> {
+    require!(reserve_in != 0, AmmError::NoLiquidityInPool);
+
+    let r = reserve_in as u128;
+    let a = amount as u128;
+    let g = 10_000 - fee as u128;
+
+    let e = r
+        .checked_mul((10_000 + g).pow(2))
+        .and_then(|e| e.checked_add(40_000 * g * a))
+        .ok_or(AmmError::Overflow)?;
+
+    // sqrt(e / r) in Q16 so the truncation doesn't bias the split
+    let root = ((e << 32) / r).isqrt();
+    let swapped = ((20_000 * a) << 16) / (((10_000 + g) << 16) + root);
+
+    Ok(swapped as u64)
+}
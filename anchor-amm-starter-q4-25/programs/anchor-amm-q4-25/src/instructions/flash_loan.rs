@@ -0,0 +1,204 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
+    Discriminator,
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    helpers::{pre_fee_amount, reserves_after},
+    instruction::FlashRepay,
+    instructions::Swap,
+    state::{AllowEntry, Config, Oracle},
+};
+
+// Position of `config` in `FlashLoan`, where the repay has to name the pool
+pub const FLASH_LOAN_CONFIG_INDEX: usize = 3;
+
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+    // Only checked on gated pools, None otherwise
+    #[account(
+        seeds = [b"allow", config.key().as_ref(), user.key().as_ref()],
+        bump = allow_entry.bump,
+    )]
+    pub allow_entry: Option<Box<Account<'info, AllowEntry>>>,
+    #[account(
+        mut,
+        seeds = [b"oracle", config.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_x,
+    )]
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_y,
+    )]
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: instructions sysvar, to find the repay
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instruction_sysvar: UncheckedAccount<'info>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> FlashLoan<'info> {
+    // Lends `amount` out of the vault. The loan plus the pool fee is owed
+    // back to the vault by a `flash_repay` later in the same transaction, and
+    // until then the pool can't be traded or its liquidity moved. Gated
+    // pools only lend to users on their allowlist, like they only trade with them.
+    pub fn borrow(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        self.config
+            .check_gate(self.allow_entry.as_ref().map(|entry| entry.expires_at))?;
+        require!(amount != 0, AmmError::InvalidAmount);
+
+        let (x, y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let reserve = match is_x {
+            true => x,
+            false => y,
+        };
+        require!(amount <= reserve, AmmError::InsufficientBalance);
+
+        self.find_repay()?;
+
+        // Rounded up, the fee stays in the vault and accrues to the LPs
+        let fee = (amount as u128)
+            .checked_mul(self.config.fee as u128)
+            .ok_or(AmmError::Overflow)?
+            .div_ceil(10_000) as u64;
+        self.config.flash_loan = amount.checked_add(fee).ok_or(AmmError::Overflow)?;
+        self.config.flash_loan_is_x = is_x;
+
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                &self.vault_x,
+                &self.user_x,
+                &self.mint_x,
+                &self.token_program_x,
+            ),
+            false => (
+                &self.vault_y,
+                &self.user_y,
+                &self.mint_y,
+                &self.token_program_y,
+            ),
+        };
+
+        Swap::transfer_out(from, to, mint, token_program, &self.config, amount)
+    }
+
+    pub fn repay(&mut self) -> Result<()> {
+        let owed = self.config.flash_loan;
+        require!(owed != 0, AmmError::InvalidFlashLoan);
+
+        let (from, to, mint, token_program) = match self.config.flash_loan_is_x {
+            true => (
+                &self.user_x,
+                &self.vault_x,
+                &self.mint_x,
+                &self.token_program_x,
+            ),
+            false => (
+                &self.user_y,
+                &self.vault_y,
+                &self.mint_y,
+                &self.token_program_y,
+            ),
+        };
+
+        // The vault has to receive the full amount owed, Token-2022 fees on top
+        let amount = pre_fee_amount(mint, owed)?;
+        Swap::transfer_in(from, to, mint, token_program, &self.user, amount)?;
+
+        self.config.flash_loan = 0;
+        // The fee grows the reserves, the accumulators have to see them before
+        self.config.update_price_cumulatives()?;
+        self.oracle.record(self.config.observation());
+        reserves_after(&mut self.config, &mut self.vault_x, &mut self.vault_y)?;
+        Ok(())
+    }
+
+    // A top-level borrow needs a top-level `flash_repay` on the same pool
+    // further down the transaction. That repay can only succeed by paying
+    // the loan back, so the transaction can't end with it outstanding.
+    fn find_repay(&self) -> Result<()> {
+        require!(
+            get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+            AmmError::InvalidFlashLoan
+        );
+
+        let sysvar = self.instruction_sysvar.to_account_info();
+        let current = load_current_index_checked(&sysvar)? as usize;
+
+        let mut index = current + 1;
+        while let Ok(ix) = load_instruction_at_checked(index, &sysvar) {
+            if ix.program_id == crate::ID
+                && ix.data.starts_with(FlashRepay::DISCRIMINATOR)
+                && ix
+                    .accounts
+                    .get(FLASH_LOAN_CONFIG_INDEX)
+                    .is_some_and(|account| account.pubkey == self.config.key())
+            {
+                return Ok(());
+            }
+            index += 1;
+        }
+
+        err!(AmmError::InvalidFlashLoan)
+    }
+}
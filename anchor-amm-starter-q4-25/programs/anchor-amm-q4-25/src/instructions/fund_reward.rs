@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    helpers::pre_fee_amount,
+    instructions::Swap,
+    state::{Config, Farm},
+};
+
+#[derive(Accounts)]
+pub struct FundReward<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        constraint = config.authority.is_some() @ AmmError::NoAuthoritySet,
+        constraint = config.authority == Some(authority.key()) @ AmmError::InvalidAuthority,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"farm", config.key().as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(
+        mint::token_program = token_program,
+        // The farm's LP vault would double as the reward vault
+        constraint = reward_mint.key() != farm.mint_lp @ AmmError::InvalidToken,
+    )]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::authority = authority,
+        token::token_program = token_program,
+    )]
+    pub authority_reward: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the reward mint
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FundReward<'info> {
+    // Funds `emissions_per_second` of the reward mint from `start_ts` to
+    // `end_ts` up front. A mint keeps its slot for good, so it can only be
+    // funded again once its previous schedule has ended.
+    pub fn fund(&mut self, emissions_per_second: u64, start_ts: i64, end_ts: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            emissions_per_second != 0 && start_ts >= now && end_ts > start_ts,
+            AmmError::InvalidRewardSchedule
+        );
+
+        self.farm.accrue(now)?;
+
+        let mint = self.reward_mint.key();
+        let index = match self.farm.reward_index(&mint) {
+            Some(index) => {
+                require!(
+                    now >= self.farm.rewards[index].end_ts,
+                    AmmError::InvalidRewardSchedule
+                );
+                index
+            }
+            None => self
+                .farm
+                .reward_index(&Pubkey::default())
+                .ok_or(AmmError::RewardSlotsFull)?,
+        };
+
+        let total = (end_ts - start_ts)
+            .try_into()
+            .ok()
+            .and_then(|seconds: u64| seconds.checked_mul(emissions_per_second))
+            .ok_or(AmmError::Overflow)?;
+
+        let reward = &mut self.farm.rewards[index];
+        reward.mint = mint;
+        reward.emissions_per_second = emissions_per_second;
+        reward.start_ts = start_ts;
+        reward.end_ts = end_ts;
+
+        // The vault has to receive every token it will emit
+        let amount = pre_fee_amount(&self.reward_mint, total)?;
+        Swap::transfer_in(
+            &self.authority_reward,
+            &self.reward_vault,
+            &self.reward_mint,
+            &self.token_program,
+            &self.authority,
+            amount,
+        )
+    }
+}
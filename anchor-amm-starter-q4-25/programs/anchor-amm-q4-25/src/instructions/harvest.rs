@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    instructions::StakeLp,
+    state::{Farm, Stake},
+};
+
+#[derive(Accounts)]
+pub struct Harvest<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"farm", farm.config.as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(
+        mut,
+        seeds = [b"stake", farm.key().as_ref(), user.key().as_ref()],
+        bump = stake.bump,
+    )]
+    pub stake: Box<Account<'info, Stake>>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = reward_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_reward: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the reward mint
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Harvest<'info> {
+    // Pays out everything owed in one reward token, one instruction per token
+    pub fn harvest(&mut self) -> Result<()> {
+        let index = self
+            .farm
+            .reward_index(&self.reward_mint.key())
+            .ok_or(AmmError::InvalidToken)?;
+
+        self.farm.accrue(Clock::get()?.unix_timestamp)?;
+        self.stake.settle(&self.farm)?;
+
+        let owed = self.stake.rewards_owed[index];
+        require!(owed != 0, AmmError::ZeroBalance);
+        self.stake.rewards_owed[index] = 0;
+
+        StakeLp::transfer_out(
+            &self.reward_vault,
+            &self.user_reward,
+            &self.reward_mint,
+            &self.token_program,
+            &self.farm,
+            owed,
+        )
+    }
+}
@@ -1,41 +1,85 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::state::Config;
+use crate::{
+    errors::AmmError,
+    events::PoolInitialized,
+    stable_swap::{MAX_AMP, MIN_AMP},
+    state::{Config, CurveType, Observation, Oracle, PoolEntry, PoolIndex, Registry, OBSERVATIONS},
+};
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(seed: u64)]
+#[instruction(seed: u64, fee: u16)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub initializer: Signer<'info>,
-    pub mint_x: Account<'info, Mint>,
-    pub mint_y: Account<'info, Mint>,
+    #[account(
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Box<Account<'info, Registry>>,
+    // Fails to init if the pair already has a pool at this fee tier
+    #[account(
+        init,
+        payer = initializer,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+            fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space = PoolEntry::DISCRIMINATOR.len() + PoolEntry::INIT_SPACE,
+    )]
+    pub pool_entry: Box<Account<'info, PoolEntry>>,
+    #[account(
+        init,
+        payer = initializer,
+        seeds = [b"pool_index", registry.next_index.to_le_bytes().as_ref()],
+        bump,
+        space = PoolIndex::DISCRIMINATOR.len() + PoolIndex::INIT_SPACE,
+    )]
+    pub pool_index: Box<Account<'info, PoolIndex>>,
     #[account(
         init,
         payer = initializer,
         seeds = [b"lp", config.key.as_ref()],
         bump,
-        mint::decimals = 6,
+        // As precise as the finer of the pair
+        mint::decimals = mint_x.decimals.max(mint_y.decimals),
         mint::authority = config,
+        mint::token_program = token_program,
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         init,
         payer = initializer,
         associated_token::mint = mint_x,
         associated_token::authority = config,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
         payer = initializer,
         associated_token::mint = mint_y,
         associated_token::authority = config,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
         payer = initializer,
@@ -44,7 +88,17 @@ pub struct Initialize<'info> {
         space = Config::DISCRIMINATOR.len() + Config::INIT_SPACE,
     )]
     pub config: Account<'info, Config>,
-    pub token_program: Program<'info, Token>,
+    #[account(
+        init,
+        payer = initializer,
+        seeds = [b"oracle", config.key().as_ref()],
+        bump,
+        space = Oracle::DISCRIMINATOR.len() + Oracle::INIT_SPACE,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the LP mint
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
@@ -55,19 +109,95 @@ impl<'info> Initialize<'info> {
         seed: u64,
         fee: u16,
         authority: Option<Pubkey>,
-        bumps: InitializeBumps,
-    ) -> Result<()> {
+        curve_type: CurveType,
+        amp: u64, // StableSwap amplification, 0 for constant product pools
+        bumps: &InitializeBumps,
+    ) -> Result<PoolInitialized> {
+        // Sorted mints make the pool entry canonical, reversed pairs can't get their own
+        require!(
+            self.mint_x.key() < self.mint_y.key(),
+            AmmError::InvalidMintPair
+        );
+        require!(fee < 10_000, AmmError::InvalidFee);
+        require!(
+            self.registry.fee_tiers.contains(&fee),
+            AmmError::InvalidFeeTier
+        );
+
+        match curve_type {
+            CurveType::ConstantProduct => require!(amp == 0, AmmError::InvalidAmp),
+            CurveType::StableSwap => {
+                require!((MIN_AMP..=MAX_AMP).contains(&amp), AmmError::InvalidAmp)
+            }
+        }
+
+        let clock = Clock::get()?;
+
         self.config.set_inner(Config {
             seed,
             authority,
+            initializer: self.initializer.key(),
             mint_x: self.mint_x.key(),
             mint_y: self.mint_y.key(),
             fee,
+            max_fee: fee,
+            volatility_factor: 0,
+            decay_period: 0,
+            reference_price: 0,
+            reference_timestamp: 0,
+            protocol_fee_bps: 0,
+            protocol_fees_x: 0,
+            protocol_fees_y: 0,
+            curve_type,
+            initial_amp: amp,
+            target_amp: amp,
+            ramp_start_ts: 0,
+            ramp_stop_ts: 0,
+            reserve_x: 0,
+            reserve_y: 0,
+            price_x_cumulative: 0,
+            price_y_cumulative: 0,
+            last_update_slot: clock.slot,
+            last_update_timestamp: clock.unix_timestamp,
             locked: false,
+            gate: false,
+            flash_loan: 0,
+            flash_loan_is_x: false,
             config_bump: bumps.config,
             lp_bump: bumps.mint_lp,
         });
 
-        Ok(())
+        let mut observations = [Observation::default(); OBSERVATIONS];
+        observations[0] = self.config.observation();
+
+        self.oracle.set_inner(Oracle {
+            config: self.config.key(),
+            index: 0,
+            observations,
+            bump: bumps.oracle,
+        });
+
+        let index = self.registry.register()?;
+        self.pool_entry.set_inner(PoolEntry {
+            config: self.config.key(),
+            index,
+            bump: bumps.pool_entry,
+        });
+        self.pool_index.set_inner(PoolIndex {
+            config: self.config.key(),
+            bump: bumps.pool_index,
+        });
+
+        Ok(PoolInitialized {
+            pool: self.config.key(),
+            initializer: self.initializer.key(),
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee,
+            curve_type,
+            amp,
+            authority,
+            index,
+        })
     }
 }
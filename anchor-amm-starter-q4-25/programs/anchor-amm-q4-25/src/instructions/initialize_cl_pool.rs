@@ -0,0 +1,139 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    clmm::{tick_from_sqrt_price, MAX_SQRT_PRICE, MAX_TICK_SPACING, MIN_SQRT_PRICE},
+    errors::AmmError,
+    state::{ClPool, PoolEntry, PoolIndex, Registry},
+};
+
+#[derive(Accounts)]
+#[instruction(seed: u64, fee: u16)]
+pub struct InitializeClPool<'info> {
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    #[account(
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Box<Account<'info, Registry>>,
+    // Shared with the other curves, a pair has one pool per fee tier
+    #[account(
+        init,
+        payer = initializer,
+        seeds = [
+            b"pool",
+            mint_x.key().as_ref(),
+            mint_y.key().as_ref(),
+            fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space = PoolEntry::DISCRIMINATOR.len() + PoolEntry::INIT_SPACE,
+    )]
+    pub pool_entry: Box<Account<'info, PoolEntry>>,
+    #[account(
+        init,
+        payer = initializer,
+        seeds = [b"pool_index", registry.next_index.to_le_bytes().as_ref()],
+        bump,
+        space = PoolIndex::DISCRIMINATOR.len() + PoolIndex::INIT_SPACE,
+    )]
+    pub pool_index: Box<Account<'info, PoolIndex>>,
+    #[account(
+        init,
+        payer = initializer,
+        seeds = [b"cl_pool", seed.to_le_bytes().as_ref()],
+        bump,
+        space = ClPool::DISCRIMINATOR.len() + ClPool::INIT_SPACE,
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = initializer,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeClPool<'info> {
+    pub fn init(
+        &mut self,
+        seed: u64,
+        fee: u16,
+        tick_spacing: u16,
+        sqrt_price: u128, // Q64.64 square root of the starting price of X in Y
+        bumps: InitializeClPoolBumps,
+    ) -> Result<()> {
+        // Registered like `initialize` registers the other curves
+        require!(
+            self.mint_x.key() < self.mint_y.key(),
+            AmmError::InvalidMintPair
+        );
+        require!(fee < 10_000, AmmError::InvalidFee);
+        require!(
+            self.registry.fee_tiers.contains(&fee),
+            AmmError::InvalidFeeTier
+        );
+        require!(
+            (1..=MAX_TICK_SPACING).contains(&tick_spacing),
+            AmmError::InvalidTickSpacing
+        );
+        require!(
+            (MIN_SQRT_PRICE..MAX_SQRT_PRICE).contains(&sqrt_price),
+            AmmError::InvalidSqrtPrice
+        );
+
+        self.pool.set_inner(ClPool {
+            seed,
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            fee,
+            tick_spacing,
+            sqrt_price,
+            tick_current: tick_from_sqrt_price(sqrt_price)?,
+            liquidity: 0,
+            fee_growth_global_x: 0,
+            fee_growth_global_y: 0,
+            bump: bumps.pool,
+        });
+
+        let index = self.registry.register()?;
+        self.pool_entry.set_inner(PoolEntry {
+            config: self.pool.key(),
+            index,
+            bump: bumps.pool_entry,
+        });
+        self.pool_index.set_inner(PoolIndex {
+            config: self.pool.key(),
+            bump: bumps.pool_index,
+        });
+
+        Ok(())
+    }
+}
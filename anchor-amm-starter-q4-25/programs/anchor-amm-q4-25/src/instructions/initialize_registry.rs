@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    program::AnchorAmmQ425,
+    state::{Registry, MAX_FEE_TIERS},
+};
+
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    // Only the program's upgrade authority picks the registry's admin, anyone
+    // else could create it first with themselves in charge
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key())
+            @ AmmError::InvalidAuthority,
+    )]
+    pub program: Program<'info, AnchorAmmQ425>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ AmmError::InvalidAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"registry"],
+        bump,
+        space = Registry::DISCRIMINATOR.len() + Registry::INIT_SPACE,
+    )]
+    pub registry: Account<'info, Registry>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeRegistry<'info> {
+    // There is a single registry, created once right after deployment
+    pub fn init(
+        &mut self,
+        admin: Pubkey,
+        fee_tiers: Vec<u16>,
+        bumps: InitializeRegistryBumps,
+    ) -> Result<()> {
+        require!(fee_tiers.len() <= MAX_FEE_TIERS, AmmError::InvalidFeeTier);
+        for (i, fee) in fee_tiers.iter().enumerate() {
+            require!(*fee < 10_000, AmmError::InvalidFee);
+            require!(!fee_tiers[..i].contains(fee), AmmError::InvalidFeeTier);
+        }
+
+        self.registry.set_inner(Registry {
+            admin,
+            fee_tiers,
+            pool_count: 0,
+            next_index: 0,
+            bump: bumps.registry,
+        });
+
+        Ok(())
+    }
+}
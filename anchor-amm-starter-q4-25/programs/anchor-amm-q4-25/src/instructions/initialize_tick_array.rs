@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    clmm::{MAX_TICK, MIN_TICK},
+    errors::AmmError,
+    state::{ClPool, Tick, TickArray},
+};
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitializeTickArray<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"cl_pool", pool.seed.to_le_bytes().as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"tick_array", pool.key().as_ref(), start_tick_index.to_le_bytes().as_ref()],
+        bump,
+        space = TickArray::DISCRIMINATOR.len() + TickArray::INIT_SPACE,
+    )]
+    pub tick_array: Box<Account<'info, TickArray>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeTickArray<'info> {
+    // Permissionless, whoever needs the ticks first pays for the account
+    pub fn init(&mut self, start_tick_index: i32, bumps: InitializeTickArrayBumps) -> Result<()> {
+        let spacing = self.pool.tick_spacing;
+        require!(
+            start_tick_index == TickArray::start_index(start_tick_index, spacing),
+            AmmError::InvalidTickArray
+        );
+        require!(
+            start_tick_index >= TickArray::start_index(MIN_TICK, spacing)
+                && start_tick_index <= MAX_TICK,
+            AmmError::InvalidTickArray
+        );
+
+        // Written field by field on the boxed account, the ticks alone would
+        // take half the SBF stack frame as a TickArray value
+        let tick_array = &mut self.tick_array;
+        tick_array.pool = self.pool.key();
+        tick_array.start_tick_index = start_tick_index;
+        tick_array.ticks.fill(Tick::default());
+        tick_array.bump = bumps.tick_array;
+
+        Ok(())
+    }
+}
@@ -1,9 +1,57 @@
+pub mod add_to_allowlist;
+pub mod cancel_order;
+pub mod cl_swap;
+pub mod close_pool;
+pub mod collect_protocol_fees;
+pub mod crank_orders;
 pub mod deposit;
+pub mod flash_loan;
+pub mod fund_reward;
+pub mod harvest;
 pub mod initialize;
+pub mod initialize_cl_pool;
+pub mod initialize_farm;
+pub mod initialize_registry;
+pub mod initialize_tick_array;
+pub mod modify_liquidity;
+pub mod observe;
+pub mod open_position;
+pub mod place_limit_order;
+pub mod reclaim_rewards;
+pub mod remove_from_allowlist;
+pub mod route_swap;
+pub mod stake_lp;
 pub mod swap;
+pub mod update;
+pub mod update_fee;
+pub mod update_registry;
 pub mod withdraw;
 
+pub use add_to_allowlist::*;
+pub use cancel_order::*;
+pub use cl_swap::*;
+pub use close_pool::*;
+pub use collect_protocol_fees::*;
+pub use crank_orders::*;
 pub use deposit::*;
+pub use flash_loan::*;
+pub use fund_reward::*;
+pub use harvest::*;
 pub use initialize::*;
+pub use initialize_cl_pool::*;
+pub use initialize_farm::*;
+pub use initialize_registry::*;
+pub use initialize_tick_array::*;
+pub use modify_liquidity::*;
+pub use observe::*;
+pub use open_position::*;
+pub use place_limit_order::*;
+pub use reclaim_rewards::*;
+pub use remove_from_allowlist::*;
+pub use route_swap::*;
+pub use stake_lp::*;
 pub use swap::*;
+pub use update::*;
+pub use update_fee::*;
+pub use update_registry::*;
 pub use withdraw::*;
@@ -0,0 +1,312 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    clmm::{add_delta, amounts_for_liquidity, fee_growth_inside},
+    errors::AmmError,
+    events::{DepositEvent, WithdrawEvent},
+    helpers::{pre_fee_amount, transfer_fee},
+    instructions::{ClSwap, Swap},
+    state::{ClPool, Position, TickArray},
+};
+
+// Shared by `increase_liquidity`, `decrease_liquidity` and `collect_fees`
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"cl_pool", pool.seed.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+    #[account(
+        mut,
+        seeds = [
+            b"position",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            position.tick_lower.to_le_bytes().as_ref(),
+            position.tick_upper.to_le_bytes().as_ref(),
+        ],
+        bump = position.bump,
+        has_one = owner,
+    )]
+    pub position: Box<Account<'info, Position>>,
+    // Both bounds can be in the same array, then the same account is passed twice
+    #[account(
+        mut,
+        seeds = [
+            b"tick_array",
+            pool.key().as_ref(),
+            TickArray::start_index(position.tick_lower, pool.tick_spacing).to_le_bytes().as_ref(),
+        ],
+        bump = tick_array_lower.bump,
+    )]
+    pub tick_array_lower: Box<Account<'info, TickArray>>,
+    #[account(
+        mut,
+        seeds = [
+            b"tick_array",
+            pool.key().as_ref(),
+            TickArray::start_index(position.tick_upper, pool.tick_spacing).to_le_bytes().as_ref(),
+        ],
+        bump = tick_array_upper.bump,
+    )]
+    pub tick_array_upper: Box<Account<'info, TickArray>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_x,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_x,
+    )]
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_y,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_y,
+    )]
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ModifyLiquidity<'info> {
+    pub fn increase_liquidity(
+        &mut self,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+    ) -> Result<DepositEvent> {
+        require!(liquidity != 0, AmmError::InvalidAmount);
+
+        let delta = i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+        let (x, y) = self.modify(delta)?;
+
+        // The vaults have to receive the full amounts, so gross them up for
+        // Token-2022 transfer fees before checking the user's limits
+        let amount_x = pre_fee_amount(&self.mint_x, x)?;
+        let amount_y = pre_fee_amount(&self.mint_y, y)?;
+        require!(
+            amount_x <= max_x && amount_y <= max_y,
+            AmmError::SlippageExceeded
+        );
+
+        if amount_x != 0 {
+            self.deposit_tokens(true, amount_x)?;
+        }
+        if amount_y != 0 {
+            self.deposit_tokens(false, amount_y)?;
+        }
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        Ok(DepositEvent {
+            pool: self.pool.key(),
+            user: self.owner.key(),
+            amount_x,
+            amount_y,
+            lp_amount: lp_amount(liquidity),
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+        })
+    }
+
+    pub fn decrease_liquidity(
+        &mut self,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+    ) -> Result<WithdrawEvent> {
+        require!(
+            liquidity != 0 && liquidity <= self.position.liquidity,
+            AmmError::InvalidAmount
+        );
+
+        let delta = i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+        let (x, y) = self.modify(-delta)?;
+
+        require!(
+            x - transfer_fee(&self.mint_x, x)? >= min_x
+                && y - transfer_fee(&self.mint_y, y)? >= min_y,
+            AmmError::SlippageExceeded
+        );
+
+        if x != 0 {
+            self.withdraw_tokens(true, x)?;
+        }
+        if y != 0 {
+            self.withdraw_tokens(false, y)?;
+        }
+
+        self.withdrawn(x, y, lp_amount(liquidity))
+    }
+
+    // A withdrawal of the fees alone, no liquidity leaves the position
+    pub fn collect_fees(&mut self) -> Result<WithdrawEvent> {
+        // Settles whatever the position earned since it last changed
+        self.modify(0)?;
+
+        let (fees_x, fees_y) = (self.position.fees_owed_x, self.position.fees_owed_y);
+        require!(fees_x != 0 || fees_y != 0, AmmError::ZeroBalance);
+
+        self.position.fees_owed_x = 0;
+        self.position.fees_owed_y = 0;
+
+        if fees_x != 0 {
+            self.withdraw_tokens(true, fees_x)?;
+        }
+        if fees_y != 0 {
+            self.withdraw_tokens(false, fees_y)?;
+        }
+
+        self.withdrawn(fees_x, fees_y, 0)
+    }
+
+    fn withdrawn(&mut self, amount_x: u64, amount_y: u64, lp_amount: u64) -> Result<WithdrawEvent> {
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+
+        Ok(WithdrawEvent {
+            pool: self.pool.key(),
+            user: self.owner.key(),
+            amount_x,
+            amount_y,
+            lp_amount,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+        })
+    }
+
+    // Moves `liquidity_delta` in or out of the position and its ticks, and
+    // returns the tokens backing it
+    fn modify(&mut self, liquidity_delta: i128) -> Result<(u64, u64)> {
+        let (lower, upper) = (self.position.tick_lower, self.position.tick_upper);
+
+        // The fee growth inside the range is read off the bounds, so new ticks
+        // are initialized before and emptied ticks cleared after settling fees
+        if liquidity_delta > 0 {
+            self.update_ticks(liquidity_delta)?;
+        }
+
+        let spacing = self.pool.tick_spacing;
+        let inside = fee_growth_inside(
+            &self.pool,
+            (lower, self.tick_array_lower.tick(lower, spacing)?),
+            (upper, self.tick_array_upper.tick(upper, spacing)?),
+        );
+        self.position.update(liquidity_delta, inside)?;
+
+        if liquidity_delta < 0 {
+            self.update_ticks(liquidity_delta)?;
+        }
+
+        if (lower..upper).contains(&self.pool.tick_current) {
+            self.pool.liquidity = add_delta(self.pool.liquidity, liquidity_delta)?;
+        }
+
+        amounts_for_liquidity(
+            &self.pool,
+            lower,
+            upper,
+            liquidity_delta.unsigned_abs(),
+            liquidity_delta > 0,
+        )
+    }
+
+    fn update_ticks(&mut self, liquidity_delta: i128) -> Result<()> {
+        let (lower, upper) = (self.position.tick_lower, self.position.tick_upper);
+
+        self.tick_array_lower
+            .update_tick(lower, &self.pool, liquidity_delta, false)?;
+
+        // Both accounts are written back on exit, keep them identical
+        if self.tick_array_lower.key() == self.tick_array_upper.key() {
+            self.tick_array_lower
+                .update_tick(upper, &self.pool, liquidity_delta, true)?;
+            self.tick_array_upper.ticks = self.tick_array_lower.ticks;
+        } else {
+            self.tick_array_upper
+                .update_tick(upper, &self.pool, liquidity_delta, true)?;
+        }
+
+        Ok(())
+    }
+
+    fn deposit_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                &self.user_x,
+                &self.vault_x,
+                &self.mint_x,
+                &self.token_program_x,
+            ),
+            false => (
+                &self.user_y,
+                &self.vault_y,
+                &self.mint_y,
+                &self.token_program_y,
+            ),
+        };
+
+        Swap::transfer_in(from, to, mint, token_program, &self.owner, amount)
+    }
+
+    fn withdraw_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                &self.vault_x,
+                &self.user_x,
+                &self.mint_x,
+                &self.token_program_x,
+            ),
+            false => (
+                &self.vault_y,
+                &self.user_y,
+                &self.mint_y,
+                &self.token_program_y,
+            ),
+        };
+
+        ClSwap::transfer_out(from, to, mint, token_program, &self.pool, amount)
+    }
+}
+
+// Liquidity as the events' LP amount, u64::MAX when it doesn't fit
+fn lp_amount(liquidity: u128) -> u64 {
+    u64::try_from(liquidity).unwrap_or(u64::MAX)
+}
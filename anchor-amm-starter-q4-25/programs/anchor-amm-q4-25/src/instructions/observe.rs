@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Config, Oracle, Twap};
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [b"oracle", config.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+}
+
+impl<'info> Observe<'info> {
+    pub fn observe(&self, window: i64) -> Result<Twap> {
+        // Bring a copy of the accumulators up to now, the pool itself is not written
+        let mut config = (*self.config).clone();
+        config.update_price_cumulatives()?;
+
+        self.oracle.twap(config.observation(), window)
+    }
+}
@@ -0,0 +1,110 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    instructions::Swap,
+    state::{Config, Order},
+};
+
+#[derive(Accounts)]
+#[instruction(id: u64)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        constraint = mint_in.key() == config.mint_x || mint_in.key() == config.mint_y
+            @ AmmError::InvalidToken,
+        mint::token_program = token_program,
+    )]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_in: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"order", config.key().as_ref(), owner.key().as_ref(), id.to_le_bytes().as_ref()],
+        bump,
+        space = Order::DISCRIMINATOR.len() + Order::INIT_SPACE,
+    )]
+    pub order: Box<Account<'info, Order>>,
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = order,
+        associated_token::token_program = token_program,
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the mint sold
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceLimitOrder<'info> {
+    // Escrows `amount` to be sold for at least `min_amount_out`, the keeper
+    // reward is kept on the order account until it's filled or cancelled
+    pub fn place(
+        &mut self,
+        id: u64,
+        amount: u64,
+        min_amount_out: u64,
+        keeper_reward: u64,
+        bumps: PlaceLimitOrderBumps,
+    ) -> Result<()> {
+        // It could never be cranked
+        require!(!self.config.gate, AmmError::PoolGated);
+        require!(amount != 0, AmmError::InvalidAmount);
+        require!(min_amount_out != 0, AmmError::InvalidAmount);
+
+        self.order.set_inner(Order {
+            config: self.config.key(),
+            owner: self.owner.key(),
+            id,
+            mint_in: self.mint_in.key(),
+            min_amount_out,
+            keeper_reward,
+            bump: bumps.order,
+        });
+
+        Swap::transfer_in(
+            &self.owner_in,
+            &self.escrow,
+            &self.mint_in,
+            &self.token_program,
+            &self.owner,
+            amount,
+        )?;
+
+        if keeper_reward != 0 {
+            transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.owner.to_account_info(),
+                        to: self.order.to_account_info(),
+                    },
+                ),
+                keeper_reward,
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    errors::AmmError,
+    instructions::StakeLp,
+    state::{Config, Farm},
+};
+
+#[derive(Accounts)]
+pub struct ReclaimRewards<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        constraint = config.authority.is_some() @ AmmError::NoAuthoritySet,
+        constraint = config.authority == Some(authority.key()) @ AmmError::InvalidAuthority,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"farm", config.key().as_ref()],
+        bump = farm.bump,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = token_program,
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::authority = authority,
+        token::token_program = token_program,
+    )]
+    pub authority_reward: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the reward mint
+}
+
+impl<'info> ReclaimRewards<'info> {
+    // Withdraws what the reward emitted while nothing was staked, once its
+    // schedule has ended. What the stakers earned stays in the vault for them.
+    pub fn reclaim(&mut self) -> Result<()> {
+        let index = self
+            .farm
+            .reward_index(&self.reward_mint.key())
+            .ok_or(AmmError::InvalidToken)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= self.farm.rewards[index].end_ts,
+            AmmError::InvalidRewardSchedule
+        );
+        self.farm.accrue(now)?;
+
+        let amount = self.farm.rewards[index].unallocated;
+        require!(amount != 0, AmmError::ZeroBalance);
+        self.farm.rewards[index].unallocated = 0;
+
+        StakeLp::transfer_out(
+            &self.reward_vault,
+            &self.authority_reward,
+            &self.reward_mint,
+            &self.token_program,
+            &self.farm,
+            amount,
+        )
+    }
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    state::{AllowEntry, Config},
+};
+
+#[derive(Accounts)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        constraint = config.authority.is_some() @ AmmError::NoAuthoritySet,
+        constraint = config.authority == Some(authority.key()) @ AmmError::InvalidAuthority,
+    )]
+    pub config: Account<'info, Config>,
+    // The rent goes back to the authority that paid for the entry
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"allow", config.key().as_ref(), allow_entry.user.as_ref()],
+        bump = allow_entry.bump,
+    )]
+    pub allow_entry: Account<'info, AllowEntry>,
+}
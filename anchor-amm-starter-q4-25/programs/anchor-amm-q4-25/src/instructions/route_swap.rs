@@ -0,0 +1,245 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    errors::AmmError,
+    events::SwapEvent,
+    helpers::{reserves_after, transfer_fee},
+    instructions::{swap_on_curve, Swap},
+    state::{Config, Oracle},
+};
+
+// Remaining accounts per hop, in this order:
+// config, oracle, mint_x, mint_y, mint_lp, vault_x, vault_y, user_x, user_y
+pub const HOP_ACCOUNTS: usize = 9;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RouteSwap<'info> {
+    pub user: Signer<'info>,
+    #[account(address = anchor_spl::token::ID)]
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(address = anchor_spl::token_2022::ID)]
+    pub token_program_2022: Interface<'info, TokenInterface>,
+}
+
+struct Hop<'info> {
+    config: Box<Account<'info, Config>>,
+    oracle: Box<Account<'info, Oracle>>,
+    mint_x: Box<InterfaceAccount<'info, Mint>>,
+    mint_y: Box<InterfaceAccount<'info, Mint>>,
+    mint_lp: Box<InterfaceAccount<'info, Mint>>,
+    vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    user_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    user_y: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+impl<'info> RouteSwap<'info> {
+    // `path` holds the `is_x` of every hop, the output of one hop is the
+    // input of the next and only the last one is checked against `min`.
+    // Returns the event of every hop.
+    pub fn route_swap(
+        &self,
+        hops: &'info [AccountInfo<'info>],
+        path: Vec<bool>,
+        amount: u64,
+        min: u64,
+    ) -> Result<Vec<SwapEvent>> {
+        require!(!path.is_empty(), AmmError::InvalidRoute);
+        require!(
+            hops.len() == path.len() * HOP_ACCOUNTS,
+            AmmError::InvalidRoute
+        );
+        require!(amount != 0, AmmError::InvalidAmount);
+
+        let mut amount = amount;
+        let mut mint_out: Option<Pubkey> = None;
+        let mut events = Vec::with_capacity(path.len());
+
+        for (accounts, is_x) in hops.chunks(HOP_ACCOUNTS).zip(path) {
+            // Pools are loaded one hop at a time so a pool visited twice sees
+            // the vaults and accumulators left by the previous hop
+            let mut hop = self.load_hop(accounts)?;
+
+            let (mint_in, next_mint) = match is_x {
+                true => (hop.mint_x.key(), hop.mint_y.key()),
+                false => (hop.mint_y.key(), hop.mint_x.key()),
+            };
+            if let Some(mint_out) = mint_out {
+                require_keys_eq!(mint_in, mint_out, AmmError::InvalidRoute);
+            }
+
+            let (out, event) = self.swap_hop(&mut hop, is_x, amount)?;
+            amount = out;
+            mint_out = Some(next_mint);
+            events.push(event);
+        }
+
+        require!(amount >= min, AmmError::SlippageExceeded);
+
+        Ok(events)
+    }
+
+    // Same checks the `Swap` account constraints do, on accounts we only get
+    // as `remaining_accounts`
+    fn load_hop(&self, accounts: &'info [AccountInfo<'info>]) -> Result<Hop<'info>> {
+        let [config, oracle, mint_x, mint_y, mint_lp, vault_x, vault_y, user_x, user_y] = accounts
+        else {
+            return err!(AmmError::InvalidRoute);
+        };
+
+        let hop = Hop {
+            config: Box::new(Account::try_from(config)?),
+            oracle: Box::new(Account::try_from(oracle)?),
+            mint_x: Box::new(InterfaceAccount::try_from(mint_x)?),
+            mint_y: Box::new(InterfaceAccount::try_from(mint_y)?),
+            mint_lp: Box::new(InterfaceAccount::try_from(mint_lp)?),
+            vault_x: Box::new(InterfaceAccount::try_from(vault_x)?),
+            vault_y: Box::new(InterfaceAccount::try_from(vault_y)?),
+            user_x: Box::new(InterfaceAccount::try_from(user_x)?),
+            user_y: Box::new(InterfaceAccount::try_from(user_y)?),
+        };
+
+        let config_key = Pubkey::create_program_address(
+            &[
+                b"config",
+                &hop.config.seed.to_le_bytes(),
+                &[hop.config.config_bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| AmmError::BumpError)?;
+        require_keys_eq!(config.key(), config_key, AmmError::InvalidRoute);
+
+        let oracle_key = Pubkey::create_program_address(
+            &[b"oracle", config_key.as_ref(), &[hop.oracle.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| AmmError::BumpError)?;
+        require_keys_eq!(oracle.key(), oracle_key, AmmError::InvalidRoute);
+
+        let lp_key = Pubkey::create_program_address(
+            &[b"lp", config_key.as_ref(), &[hop.config.lp_bump]],
+            &crate::ID,
+        )
+        .map_err(|_| AmmError::BumpError)?;
+        require_keys_eq!(mint_lp.key(), lp_key, AmmError::InvalidRoute);
+
+        require_keys_eq!(mint_x.key(), hop.config.mint_x, AmmError::InvalidToken);
+        require_keys_eq!(mint_y.key(), hop.config.mint_y, AmmError::InvalidToken);
+
+        for (vault, mint) in [(vault_x, mint_x), (vault_y, mint_y)] {
+            require_keys_eq!(
+                vault.key(),
+                get_associated_token_address_with_program_id(&config_key, mint.key, mint.owner),
+                AmmError::InvalidToken
+            );
+        }
+
+        for (user, mint) in [(&hop.user_x, mint_x), (&hop.user_y, mint_y)] {
+            require_keys_eq!(user.owner, self.user.key(), AmmError::InvalidRoute);
+            require_keys_eq!(user.mint, mint.key(), AmmError::InvalidToken);
+        }
+
+        Ok(hop)
+    }
+
+    // One leg of the route, priced and booked exactly like `Swap::swap`.
+    // Returns what the user holds after the output mint's transfer fee, and
+    // the hop's event.
+    fn swap_hop(&self, hop: &mut Hop<'info>, is_x: bool, amount: u64) -> Result<(u64, SwapEvent)> {
+        require!(!hop.config.locked, AmmError::PoolLocked);
+        // Hops carry no allowlist entries
+        require!(!hop.config.gate, AmmError::PoolGated);
+        require!(hop.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+
+        let (x, y) = hop
+            .config
+            .reserves(hop.vault_x.amount, hop.vault_y.amount)?;
+
+        hop.config.update_price_cumulatives()?;
+        hop.oracle.record(hop.config.observation());
+        let fee_bps = hop.config.trade_fee(x, y)?;
+
+        let (mint_in, mint_out, vault_in, vault_out, user_in, user_out) = match is_x {
+            true => (
+                &hop.mint_x,
+                &hop.mint_y,
+                &hop.vault_x,
+                &hop.vault_y,
+                &hop.user_x,
+                &hop.user_y,
+            ),
+            false => (
+                &hop.mint_y,
+                &hop.mint_x,
+                &hop.vault_y,
+                &hop.vault_x,
+                &hop.user_y,
+                &hop.user_x,
+            ),
+        };
+
+        let received = amount - transfer_fee(mint_in, amount)?;
+
+        let (withdraw, fee) = swap_on_curve(
+            &mut hop.config,
+            fee_bps,
+            (x, y),
+            hop.mint_lp.supply,
+            is_x,
+            received,
+            0,
+        )?;
+
+        Swap::transfer_in(
+            user_in,
+            vault_in,
+            mint_in,
+            self.token_program_for(mint_in),
+            &self.user,
+            amount,
+        )?;
+        Swap::transfer_out(
+            vault_out,
+            user_out,
+            mint_out,
+            self.token_program_for(mint_out),
+            &hop.config,
+            withdraw,
+        )?;
+
+        let (reserve_x, reserve_y) =
+            reserves_after(&mut hop.config, &mut hop.vault_x, &mut hop.vault_y)?;
+        hop.config.exit(&crate::ID)?;
+        hop.oracle.exit(&crate::ID)?;
+
+        let event = SwapEvent {
+            pool: hop.config.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in: amount,
+            amount_out: withdraw,
+            fee,
+            fee_bps,
+            reserve_x,
+            reserve_y,
+        };
+
+        Ok((withdraw - transfer_fee(mint_out, withdraw)?, event))
+    }
+
+    fn token_program_for(
+        &self,
+        mint: &InterfaceAccount<'info, Mint>,
+    ) -> &Interface<'info, TokenInterface> {
+        match *mint.to_account_info().owner == self.token_program_2022.key() {
+            true => &self.token_program_2022,
+            false => &self.token_program,
+        }
+    }
+}
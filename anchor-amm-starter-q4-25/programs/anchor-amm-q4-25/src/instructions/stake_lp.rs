@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    errors::AmmError,
+    state::{Farm, Stake, MAX_REWARDS},
+};
+
+#[derive(Accounts)]
+pub struct StakeLp<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"farm", farm.config.as_ref()],
+        bump = farm.bump,
+        has_one = mint_lp,
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm,
+        associated_token::token_program = token_program,
+    )]
+    pub farm_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [b"stake", farm.key().as_ref(), user.key().as_ref()],
+        bump,
+        space = Stake::DISCRIMINATOR.len() + Stake::INIT_SPACE,
+    )]
+    pub stake: Box<Account<'info, Stake>>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the LP mint
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StakeLp<'info> {
+    pub fn stake(&mut self, amount: u64, bumps: StakeLpBumps) -> Result<()> {
+        require!(amount != 0, AmmError::InvalidAmount);
+
+        if self.stake.farm == Pubkey::default() {
+            self.stake.set_inner(Stake {
+                farm: self.farm.key(),
+                owner: self.user.key(),
+                amount: 0,
+                reward_per_share: [0; MAX_REWARDS],
+                rewards_owed: [0; MAX_REWARDS],
+                bump: bumps.stake,
+            });
+        }
+
+        self.update()?;
+        self.stake.amount = self
+            .stake
+            .amount
+            .checked_add(amount)
+            .ok_or(AmmError::Overflow)?;
+        self.farm.total_staked = self
+            .farm
+            .total_staked
+            .checked_add(amount)
+            .ok_or(AmmError::Overflow)?;
+
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.user_lp.to_account_info(),
+                    mint: self.mint_lp.to_account_info(),
+                    to: self.farm_lp.to_account_info(),
+                    authority: self.user.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_lp.decimals,
+        )
+    }
+
+    // Rewards settled on the way out stay owed until harvested
+    pub fn unstake(&mut self, amount: u64) -> Result<()> {
+        require!(amount != 0, AmmError::InvalidAmount);
+        require!(amount <= self.stake.amount, AmmError::InsufficientBalance);
+
+        self.update()?;
+        self.stake.amount -= amount;
+        self.farm.total_staked -= amount;
+
+        Self::transfer_out(
+            &self.farm_lp,
+            &self.user_lp,
+            &self.mint_lp,
+            &self.token_program,
+            &self.farm,
+            amount,
+        )
+    }
+
+    fn update(&mut self) -> Result<()> {
+        self.farm.accrue(Clock::get()?.unix_timestamp)?;
+        self.stake.settle(&self.farm)
+    }
+
+    pub fn transfer_out(
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        token_program: &Interface<'info, TokenInterface>,
+        farm: &Account<'info, Farm>,
+        amount: u64,
+    ) -> Result<()> {
+        transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: from.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: farm.to_account_info(),
+                },
+                &[&[b"farm".as_ref(), farm.config.as_ref(), &[farm.bump]]],
+            ),
+            amount,
+            mint.decimals,
+        )
+    }
+}
@@ -1,18 +1,31 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{transfer, Mint, Token, TokenAccount, Transfer},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
-// use constant_product_curve::{ConstantProduct, LiquidityPair};
+use constant_product_curve::{ConstantProduct, LiquidityPair};
 
-use crate::{errors::AmmError, state::Config};
+use crate::{
+    errors::AmmError,
+    events::SwapEvent,
+    helpers::{pre_fee_amount, reserves_after, transfer_fee},
+    stable_swap,
+    state::{AllowEntry, Config, CurveType, Oracle},
+};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Swap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    pub mint_x: Account<'info, Mint>,
-    pub mint_y: Account<'info, Mint>,
+    #[account(
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
@@ -22,148 +35,366 @@ pub struct Swap<'info> {
         has_one = mint_y
     )]
     pub config: Account<'info, Config>,
+    // Only checked on gated pools, None otherwise
+    #[account(
+        seeds = [b"allow", config.key().as_ref(), user.key().as_ref()],
+        bump = allow_entry.bump,
+    )]
+    pub allow_entry: Option<Box<Account<'info, AllowEntry>>>,
+
+    #[account(
+        mut,
+        seeds = [b"oracle", config.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
 
     #[account(
         mut,
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump,
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
+        associated_token::token_program = token_program_x,
     )]
-    pub user_x: Account<'info, TokenAccount>,
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
+        associated_token::token_program = token_program_y,
     )]
-    pub user_y: Account<'info, TokenAccount>,
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 impl<'info> Swap<'info> {
-    pub fn swap(&mut self, is_x: bool, amount: u64, min: u64) -> Result<()> {
-        require!(self.config.locked == false, AmmError::PoolLocked);
+    pub fn swap(&mut self, is_x: bool, amount: u64, min: u64) -> Result<SwapEvent> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        self.config
+            .check_gate(self.allow_entry.as_ref().map(|entry| entry.expires_at))?;
         require!(amount != 0, AmmError::InvalidAmount);
 
-        let fee = amount
-            .checked_mul(self.config.fee as u64)
-            .ok_or(AmmError::Overflow)?
-            .checked_div(10000)
-            .ok_or(AmmError::Overflow)?;
+        let (x, y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
 
-        let amount_after_fee = amount.checked_sub(fee).ok_or(AmmError::Overflow)?;
+        // Price accumulators have to see the reserves before this instruction moves them
+        self.config.update_price_cumulatives()?;
+        self.oracle.record(self.config.observation());
+        let fee_bps = self.config.trade_fee(x, y)?;
 
-        // balance before swap
-        let x_before = self.vault_x.amount;
-        let y_before = self.vault_y.amount;
-        let product = x_before.checked_mul(y_before).ok_or(AmmError::Overflow)?;
-
-        // focus_token_before => token user depositing
-        let (focus_token_before, other_token_before) = match is_x {
-            true => (x_before, y_before),
-            false => (y_before, x_before),
+        let (mint_in, mint_out) = match is_x {
+            true => (&self.mint_x, &self.mint_y),
+            false => (&self.mint_y, &self.mint_x),
         };
 
-        let focus_token_after = focus_token_before
-            .checked_add(amount_after_fee)
-            .ok_or(AmmError::Overflow)?;
+        // Quote on what the vault actually receives, and make sure the user
+        // still gets `min` after the output mint's transfer fee
+        let received = amount - transfer_fee(mint_in, amount)?;
+        let min_withdraw = pre_fee_amount(mint_out, min)?;
+
+        let (withdraw, fee) = swap_on_curve(
+            &mut self.config,
+            fee_bps,
+            (x, y),
+            self.mint_lp.supply,
+            is_x,
+            received,
+            min_withdraw,
+        )?;
 
-        let other_token_after = product.checked_div(focus_token_after).ok_or(AmmError::Overflow)?;
+        self.deposit_tokens(is_x, amount)?;
+        self.withdraw_tokens(is_x, withdraw)?;
 
-        // other token user will receive for the focus token
-        let amount_out = other_token_before
-            .checked_sub(other_token_after)
-            .ok_or(AmmError::Overflow)?;
+        self.event(is_x, amount, withdraw, fee, fee_bps)
+    }
 
-        require!(amount_out >= min, AmmError::SlippageExceeded);
-        require!(amount != 0, AmmError::InvalidAmount);
+    pub fn swap_exact_out(
+        &mut self,
+        is_x: bool,
+        amount_out: u64,
+        max_amount_in: u64,
+    ) -> Result<SwapEvent> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        self.config
+            .check_gate(self.allow_entry.as_ref().map(|entry| entry.expires_at))?;
         require!(amount_out != 0, AmmError::InvalidAmount);
 
-        self.deposit_tokens(is_x, amount)?;
-        self.withdraw_tokens(is_x, amount_out)?;
+        let (x, y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.config.update_price_cumulatives()?;
+        self.oracle.record(self.config.observation());
+        let fee_bps = self.config.trade_fee(x, y)?;
+
+        let (reserve_in, reserve_out, mint_in, mint_out) = match is_x {
+            true => (x, y, &self.mint_x, &self.mint_y),
+            false => (y, x, &self.mint_y, &self.mint_x),
+        };
+
+        // Gross up both legs for Token-2022 transfer fees so the user receives
+        // exactly `amount_out` and the vault receives the full required input
+        let withdraw = pre_fee_amount(mint_out, amount_out)?;
+        let received =
+            exact_out_on_curve(&self.config, fee_bps, reserve_in, reserve_out, withdraw)?;
+        let amount_in = pre_fee_amount(mint_in, received)?;
+
+        require!(amount_in <= max_amount_in, AmmError::SlippageExceeded);
+
+        let fee = (received as u128)
+            .checked_mul(fee_bps as u128)
+            .ok_or(AmmError::Overflow)?
+            .checked_div(10_000)
+            .ok_or(AmmError::Overflow)? as u64;
+        self.config.accrue_protocol_fee(is_x, fee)?;
+
+        self.deposit_tokens(is_x, amount_in)?;
+        self.withdraw_tokens(is_x, withdraw)?;
+
+        self.event(is_x, amount_in, withdraw, fee, fee_bps)
+    }
+
+    fn event(
+        &mut self,
+        is_x: bool,
+        amount_in: u64,
+        amount_out: u64,
+        fee: u64,
+        fee_bps: u16,
+    ) -> Result<SwapEvent> {
+        let (reserve_x, reserve_y) =
+            reserves_after(&mut self.config, &mut self.vault_x, &mut self.vault_y)?;
 
-        Ok(())
+        Ok(SwapEvent {
+            pool: self.config.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in,
+            amount_out,
+            fee,
+            fee_bps,
+            reserve_x,
+            reserve_y,
+        })
     }
 
     pub fn deposit_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
-        let (from, to) = match is_x {
+        let (from, to, mint, token_program) = match is_x {
             true => (
-                self.user_x.to_account_info(),
-                self.vault_x.to_account_info(),
+                &self.user_x,
+                &self.vault_x,
+                &self.mint_x,
+                &self.token_program_x,
             ),
             false => (
-                self.user_y.to_account_info(),
-                self.vault_y.to_account_info(),
+                &self.user_y,
+                &self.vault_y,
+                &self.mint_y,
+                &self.token_program_y,
             ),
         };
 
-        transfer(
-            CpiContext::new(
-                self.token_program.to_account_info(),
-                Transfer {
-                    from,
-                    to,
-                    authority: self.user.to_account_info(),
-                },
-            ),
-            amount,
-        )?;
-
-        Ok(())
+        Self::transfer_in(from, to, mint, token_program, &self.user, amount)
     }
 
     pub fn withdraw_tokens(&mut self, is_x: bool, amount: u64) -> Result<()> {
         // Withdraw from the opposite vault (other kind of token)
-        let (from, to) = match is_x {
+        let (from, to, mint, token_program) = match is_x {
             true => (
-                self.vault_y.to_account_info(),
-                self.user_y.to_account_info(),
+                &self.vault_y,
+                &self.user_y,
+                &self.mint_y,
+                &self.token_program_y,
             ),
             false => (
-                self.vault_x.to_account_info(),
-                self.user_x.to_account_info(),
+                &self.vault_x,
+                &self.user_x,
+                &self.mint_x,
+                &self.token_program_x,
             ),
         };
 
-        transfer(
+        Self::transfer_out(from, to, mint, token_program, &self.config, amount)
+    }
+
+    // The transfers below don't borrow `self` so `route_swap` can run them on
+    // accounts it loaded for each hop
+    pub fn transfer_in(
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        token_program: &Interface<'info, TokenInterface>,
+        user: &Signer<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        transfer_checked(
+            CpiContext::new(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: from.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: user.to_account_info(),
+                },
+            ),
+            amount,
+            mint.decimals,
+        )
+    }
+
+    pub fn transfer_out(
+        from: &InterfaceAccount<'info, TokenAccount>,
+        to: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        token_program: &Interface<'info, TokenInterface>,
+        config: &Account<'info, Config>,
+        amount: u64,
+    ) -> Result<()> {
+        transfer_checked(
             CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            Transfer {
-                from,
-                to,
-                authority: self.config.to_account_info(),
-            },
-            &[&[
-                b"config".as_ref(),
-                &self.config.seed.to_le_bytes(),
-                &[self.config.config_bump],
-            ]],
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: from.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: config.to_account_info(),
+                },
+                &[&[
+                    b"config".as_ref(),
+                    &config.seed.to_le_bytes(),
+                    &[config.config_bump],
+                ]],
             ),
             amount,
-        )?;
+            mint.decimals,
+        )
+    }
+}
+
+// Exact-in trade of `received` tokens against the reserves, shared by `swap`
+// and every hop of `route_swap`. Charges `fee` basis points, as returned by
+// `Config::trade_fee`. Returns the amount leaving the output vault and the fee
+// charged on the input.
+pub fn swap_on_curve(
+    config: &mut Config,
+    fee: u16,
+    (x, y): (u64, u64),
+    lp_supply: u64,
+    is_x: bool,
+    received: u64,
+    min_withdraw: u64,
+) -> Result<(u64, u64)> {
+    // The fee stays in the input vault, so it accrues to the LPs through the
+    // invariant minus the protocol's share, which is kept out of the reserves
+    let res = match config.curve_type {
+        CurveType::ConstantProduct => {
+            let mut curve =
+                ConstantProduct::init(x, y, lp_supply, fee, None).map_err(AmmError::from)?;
+
+            let pair = match is_x {
+                true => LiquidityPair::X,
+                false => LiquidityPair::Y,
+            };
+
+            curve
+                .swap(pair, received, min_withdraw)
+                .map_err(AmmError::from)?
+        }
+        CurveType::StableSwap => stable_swap::swap(
+            x,
+            y,
+            config.current_amp(),
+            fee,
+            is_x,
+            received,
+            min_withdraw,
+        )?,
+    };
+
+    require!(res.deposit != 0, AmmError::InvalidAmount);
+    require!(res.withdraw != 0, AmmError::InvalidAmount);
+
+    config.accrue_protocol_fee(is_x, res.fee)?;
 
-        Ok(())
+    Ok((res.withdraw, res.fee))
+}
+
+// Input the vault has to receive so that `withdraw` can leave it, on the pool's
+// curve at `fee` basis points
+pub fn exact_out_on_curve(
+    config: &Config,
+    fee: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    withdraw: u64,
+) -> Result<u64> {
+    match config.curve_type {
+        CurveType::ConstantProduct => {
+            amount_in_for_exact_out(reserve_in, reserve_out, withdraw, fee)
+        }
+        CurveType::StableSwap => stable_swap::amount_in_for_exact_out(
+            reserve_in,
+            reserve_out,
+            config.current_amp(),
+            withdraw,
+            fee,
+        ),
     }
 }
+
+// Input needed to receive exactly `amount_out`, fee included. Both divisions
+// round up so the pool never gives away more than x * y = k allows.
+pub fn amount_in_for_exact_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    fee: u16,
+) -> Result<u64> {
+    require!(
+        reserve_in != 0 && reserve_out != 0,
+        AmmError::NoLiquidityInPool
+    );
+    require!(amount_out < reserve_out, AmmError::InsufficientBalance);
+    require!(fee < 10_000, AmmError::InvalidFee);
+
+    let amount_in_after_fee = (reserve_in as u128)
+        .checked_mul(amount_out as u128)
+        .ok_or(AmmError::Overflow)?
+        .div_ceil((reserve_out - amount_out) as u128);
+
+    let amount_in = amount_in_after_fee
+        .checked_mul(10_000)
+        .ok_or(AmmError::Overflow)?
+        .div_ceil((10_000 - fee) as u128);
+
+    u64::try_from(amount_in).map_err(|_| AmmError::Overflow.into())
+}
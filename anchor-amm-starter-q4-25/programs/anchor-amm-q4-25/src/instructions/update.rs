@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::ConfigUpdated,
+    stable_swap::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP, MIN_RAMP_DURATION},
+    state::{Config, CurveType, MAX_PROTOCOL_FEE_BPS},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Update<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        constraint = config.authority.is_some() @ AmmError::NoAuthoritySet,
+        constraint = config.authority == Some(authority.key()) @ AmmError::InvalidAuthority,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> Update<'info> {
+    pub fn lock(&mut self) -> Result<ConfigUpdated> {
+        self.config.locked = true;
+        Ok(self.event())
+    }
+
+    pub fn unlock(&mut self) -> Result<ConfigUpdated> {
+        self.config.locked = false;
+        Ok(self.event())
+    }
+
+    // Existing allowlist entries are kept when the gate is lifted
+    pub fn set_gate(&mut self, gate: bool) -> Result<ConfigUpdated> {
+        self.config.gate = gate;
+        Ok(self.event())
+    }
+
+    // Turns the dynamic fee on, or off with a `volatility_factor` of 0. The
+    // reference price restarts from the spot price at the next trade.
+    pub fn set_dynamic_fee(
+        &mut self,
+        max_fee: u16,
+        volatility_factor: u16,
+        decay_period: i64,
+    ) -> Result<ConfigUpdated> {
+        require!(
+            max_fee < 10_000 && max_fee >= self.config.fee,
+            AmmError::InvalidFee
+        );
+        require!(
+            volatility_factor == 0 || decay_period > 0,
+            AmmError::InvalidDynamicFee
+        );
+
+        self.config.max_fee = max_fee;
+        self.config.volatility_factor = volatility_factor;
+        self.config.decay_period = decay_period;
+        self.config.reference_price = 0;
+        Ok(self.event())
+    }
+
+    // The protocol's share is capped so LPs always keep part of the swap fee
+    pub fn update_protocol_fee(&mut self, protocol_fee_bps: u16) -> Result<ConfigUpdated> {
+        require!(
+            protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            AmmError::InvalidFee
+        );
+        self.config.protocol_fee_bps = protocol_fee_bps;
+        Ok(self.event())
+    }
+
+    // Moves amp linearly to `target_amp` by `ramp_stop_ts` instead of all at
+    // once, so the curve can't be reshaped under the LPs in a single slot
+    pub fn ramp_amp(&mut self, target_amp: u64, ramp_stop_ts: i64) -> Result<ConfigUpdated> {
+        require!(
+            self.config.curve_type == CurveType::StableSwap,
+            AmmError::InvalidCurveType
+        );
+        require!(
+            (MIN_AMP..=MAX_AMP).contains(&target_amp),
+            AmmError::InvalidAmp
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= self.config.ramp_start_ts + MIN_RAMP_DURATION,
+            AmmError::InvalidRamp
+        );
+        require!(
+            ramp_stop_ts >= now + MIN_RAMP_DURATION,
+            AmmError::InvalidRamp
+        );
+
+        let amp = self.config.amp(now);
+        require!(
+            target_amp <= amp * MAX_AMP_CHANGE && target_amp * MAX_AMP_CHANGE >= amp,
+            AmmError::InvalidAmp
+        );
+
+        self.config.initial_amp = amp;
+        self.config.target_amp = target_amp;
+        self.config.ramp_start_ts = now;
+        self.config.ramp_stop_ts = ramp_stop_ts;
+        Ok(self.event())
+    }
+
+    pub fn transfer_authority(&mut self, new_authority: Pubkey) -> Result<ConfigUpdated> {
+        self.config.authority = Some(new_authority);
+        Ok(self.event())
+    }
+
+    // Once renounced the pool can never be locked or updated again, so it
+    // has to be open when it happens or it would stay shut for good
+    pub fn renounce_authority(&mut self) -> Result<ConfigUpdated> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(!self.config.gate, AmmError::PoolGated);
+        self.config.authority = None;
+        Ok(self.event())
+    }
+
+    fn event(&self) -> ConfigUpdated {
+        config_updated(&self.config)
+    }
+}
+
+// Settings of the pool once an authority instruction changed them
+pub(crate) fn config_updated(config: &Account<Config>) -> ConfigUpdated {
+    ConfigUpdated {
+        pool: config.key(),
+        authority: config.authority,
+        locked: config.locked,
+        gate: config.gate,
+        fee: config.fee,
+        max_fee: config.max_fee,
+        volatility_factor: config.volatility_factor,
+        protocol_fee_bps: config.protocol_fee_bps,
+        target_amp: config.target_amp,
+        ramp_stop_ts: config.ramp_stop_ts,
+    }
+}
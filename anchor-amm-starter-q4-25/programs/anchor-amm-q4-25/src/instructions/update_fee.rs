@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::ConfigUpdated,
+    instructions::config_updated,
+    state::{Config, PoolEntry, Registry},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(fee: u16)]
+pub struct UpdateFee<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        constraint = config.authority.is_some() @ AmmError::NoAuthoritySet,
+        constraint = config.authority == Some(authority.key()) @ AmmError::InvalidAuthority,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [b"registry"],
+        bump = registry.bump,
+    )]
+    pub registry: Box<Account<'info, Registry>>,
+    // The pair's entry at the current fee tier, moved to the new one
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            b"pool",
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            config.fee.to_le_bytes().as_ref(),
+        ],
+        bump = pool_entry.bump,
+        has_one = config,
+    )]
+    pub pool_entry: Box<Account<'info, PoolEntry>>,
+    // Fails to init if the pair already has a pool at the new fee tier
+    #[account(
+        init,
+        payer = authority,
+        seeds = [
+            b"pool",
+            config.mint_x.as_ref(),
+            config.mint_y.as_ref(),
+            fee.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space = PoolEntry::DISCRIMINATOR.len() + PoolEntry::INIT_SPACE,
+    )]
+    pub new_pool_entry: Box<Account<'info, PoolEntry>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateFee<'info> {
+    // The fee can only move between the registry's tiers, keeping the pool
+    // canonical for the pair at its fee. The pool keeps its index.
+    pub fn update_fee(&mut self, fee: u16, bumps: &UpdateFeeBumps) -> Result<ConfigUpdated> {
+        require!(fee < 10_000, AmmError::InvalidFee);
+        require!(
+            self.registry.fee_tiers.contains(&fee),
+            AmmError::InvalidFeeTier
+        );
+        require!(
+            self.config.volatility_factor == 0 || fee <= self.config.max_fee,
+            AmmError::InvalidFee
+        );
+
+        self.new_pool_entry.set_inner(PoolEntry {
+            config: self.config.key(),
+            index: self.pool_entry.index,
+            bump: bumps.new_pool_entry,
+        });
+        self.config.fee = fee;
+        Ok(config_updated(&self.config))
+    }
+}
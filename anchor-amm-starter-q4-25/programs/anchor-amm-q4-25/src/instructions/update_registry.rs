@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    state::{Registry, MAX_FEE_TIERS},
+};
+
+#[derive(Accounts)]
+pub struct UpdateRegistry<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"registry"],
+        bump = registry.bump,
+        has_one = admin @ AmmError::InvalidAuthority,
+    )]
+    pub registry: Account<'info, Registry>,
+}
+
+impl<'info> UpdateRegistry<'info> {
+    pub fn add_fee_tier(&mut self, fee: u16) -> Result<()> {
+        require!(fee < 10_000, AmmError::InvalidFee);
+        let fee_tiers = &mut self.registry.fee_tiers;
+        require!(
+            !fee_tiers.contains(&fee) && fee_tiers.len() < MAX_FEE_TIERS,
+            AmmError::InvalidFeeTier
+        );
+        fee_tiers.push(fee);
+        Ok(())
+    }
+
+    // Only stops new pools from using the tier, existing pools keep it
+    pub fn remove_fee_tier(&mut self, fee: u16) -> Result<()> {
+        let fee_tiers = &mut self.registry.fee_tiers;
+        let index = fee_tiers
+            .iter()
+            .position(|tier| *tier == fee)
+            .ok_or(AmmError::InvalidFeeTier)?;
+        fee_tiers.remove(index);
+        Ok(())
+    }
+
+    pub fn set_registry_admin(&mut self, new_admin: Pubkey) -> Result<()> {
+        self.registry.admin = new_admin;
+        Ok(())
+    }
+}
@@ -1,18 +1,33 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer},
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 use constant_product_curve::ConstantProduct;
 
-use crate::{errors::AmmError, state::Config};
+use crate::{
+    errors::AmmError,
+    events::WithdrawEvent,
+    helpers::{reserves_after, transfer_fee},
+    instructions::swap_on_curve,
+    state::{AllowEntry, Config, Oracle},
+};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
-    pub mint_x: Account<'info, Mint>,
-    pub mint_y: Account<'info, Mint>,
+    #[account(
+        mint::token_program = token_program_x,
+    )]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_y,
+    )]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
@@ -22,48 +37,69 @@ pub struct Withdraw<'info> {
         has_one = mint_y
     )]
     pub config: Account<'info, Config>,
+    // Only checked on gated pools, None otherwise
+    #[account(
+        seeds = [b"allow", config.key().as_ref(), user.key().as_ref()],
+        bump = allow_entry.bump,
+    )]
+    pub allow_entry: Option<Box<Account<'info, AllowEntry>>>,
+
+    #[account(
+        mut,
+        seeds = [b"oracle", config.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
 
     #[account(
         mut,
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump,
+        mint::token_program = token_program,
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
+        associated_token::token_program = token_program_x,
     )]
-    pub user_x: Account<'info, TokenAccount>,
+    pub user_x: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
+        associated_token::token_program = token_program_y,
     )]
-    pub user_y: Account<'info, TokenAccount>,
+    pub user_y: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         associated_token::mint = mint_lp,
-        associated_token::authority = user
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub user_lp: Account<'info, TokenAccount>,
+    pub user_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns the LP mint
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -74,8 +110,11 @@ impl<'info> Withdraw<'info> {
         amount: u64, // Amount of LP tokens that the user wants to "burn"
         min_x: u64,  // Minimum amount of token X that the user wants to receive
         min_y: u64,  // Minimum amount of token Y that the user wants to receive
-    ) -> Result<()> {
-        require!(self.config.locked == false, AmmError::PoolLocked);
+    ) -> Result<WithdrawEvent> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        self.config
+            .check_gate(self.allow_entry.as_ref().map(|entry| entry.expires_at))?;
         require!(amount != 0, AmmError::InvalidAmount);
         require!(self.mint_lp.supply != 0, AmmError::NoLiquidityInPool);
         require!(
@@ -88,21 +127,31 @@ impl<'info> Withdraw<'info> {
         );
         require!(min_x != 0 || min_y != 0, AmmError::InvalidAmount);
 
+        // Accrued protocol fees are not part of the LPs' share of the pool
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.config.update_price_cumulatives()?;
+        self.oracle.record(self.config.observation());
+
+        // Proportional to the reserves, which holds on either curve
         let withdraw_amount = ConstantProduct::xy_withdraw_amounts_from_l(
-            self.vault_x.amount,
-            self.vault_y.amount,
+            reserve_x,
+            reserve_y,
             self.mint_lp.supply,
             amount,
-            6,
+            self.mint_lp.decimals.into(),
         )
         .map_err(|_| AmmError::CurveError)?;
 
+        // Slippage is checked on what the user receives after Token-2022 transfer fees
         require!(
-            withdraw_amount.x >= min_x,
+            withdraw_amount.x - transfer_fee(&self.mint_x, withdraw_amount.x)? >= min_x,
             AmmError::SlippageExceeded
         );
         require!(
-            withdraw_amount.y >= min_y,
+            withdraw_amount.y - transfer_fee(&self.mint_y, withdraw_amount.y)? >= min_y,
             AmmError::SlippageExceeded
         );
 
@@ -110,18 +159,106 @@ impl<'info> Withdraw<'info> {
         self.withdraw_tokens(false, withdraw_amount.y)?;
         self.burn_lp_tokens(amount)?;
         
-        Ok(())
+        self.event(withdraw_amount.x, withdraw_amount.y, amount)
+    }
+
+    pub fn withdraw_single(
+        &mut self,
+        is_x: bool,  // Side the user wants to receive
+        amount: u64, // Amount of LP tokens that the user wants to "burn"
+        min: u64,    // Minimum amount of that token the user wants to receive
+    ) -> Result<WithdrawEvent> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(self.config.flash_loan == 0, AmmError::FlashLoanInProgress);
+        self.config
+            .check_gate(self.allow_entry.as_ref().map(|entry| entry.expires_at))?;
+        require!(amount != 0, AmmError::InvalidAmount);
+        require!(self.mint_lp.supply != 0, AmmError::NoLiquidityInPool);
+        require!(
+            self.mint_lp.supply >= amount,
+            AmmError::LiquidityLessThanMinimum
+        );
+        require!(self.user_lp.amount >= amount, AmmError::InsufficientBalance);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.config.update_price_cumulatives()?;
+        self.oracle.record(self.config.observation());
+        let fee_bps = self.config.trade_fee(reserve_x, reserve_y)?;
+
+        let withdraw_amount = ConstantProduct::xy_withdraw_amounts_from_l(
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            amount,
+            self.mint_lp.decimals.into(),
+        )
+        .map_err(|_| AmmError::CurveError)?;
+
+        // The other side stays in the vault and is swapped back, paying the pool
+        // fee, against the reserves left once the LP tokens are burned
+        let (kept, swapped) = match is_x {
+            true => (withdraw_amount.x, withdraw_amount.y),
+            false => (withdraw_amount.y, withdraw_amount.x),
+        };
+        let (out, _) = swap_on_curve(
+            &mut self.config,
+            fee_bps,
+            (reserve_x - withdraw_amount.x, reserve_y - withdraw_amount.y),
+            self.mint_lp.supply - amount,
+            !is_x,
+            swapped,
+            0,
+        )?;
+
+        let total = kept + out;
+        let fee = match is_x {
+            true => transfer_fee(&self.mint_x, total)?,
+            false => transfer_fee(&self.mint_y, total)?,
+        };
+        require!(total - fee >= min, AmmError::SlippageExceeded);
+
+        self.withdraw_tokens(is_x, total)?;
+        self.burn_lp_tokens(amount)?;
+
+        match is_x {
+            true => self.event(total, 0, amount),
+            false => self.event(0, total, amount),
+        }
+    }
+
+    fn event(&mut self, amount_x: u64, amount_y: u64, lp_amount: u64) -> Result<WithdrawEvent> {
+        let (reserve_x, reserve_y) =
+            reserves_after(&mut self.config, &mut self.vault_x, &mut self.vault_y)?;
+
+        Ok(WithdrawEvent {
+            pool: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount,
+            reserve_x,
+            reserve_y,
+        })
     }
 
     pub fn withdraw_tokens(&self, is_x: bool, amount: u64) -> Result<()> {
-        let (user_ata, vault_ata) = match is_x {
+        let (user_ata, vault_ata, mint, decimals, token_program) = match is_x {
             true => (
                 self.user_x.to_account_info(),
                 self.vault_x.to_account_info(),
+                self.mint_x.to_account_info(),
+                self.mint_x.decimals,
+                self.token_program_x.to_account_info(),
             ),
             false => (
                 self.user_y.to_account_info(),
                 self.vault_y.to_account_info(),
+                self.mint_y.to_account_info(),
+                self.mint_y.decimals,
+                self.token_program_y.to_account_info(),
             ),
         };
 
@@ -132,17 +269,19 @@ impl<'info> Withdraw<'info> {
         let seeds = &[b"config".as_ref(), config_key.as_ref(), &[config_bump]];
         let signer_seeds = &[&seeds[..]];
 
-        transfer(
+        transfer_checked(
             CpiContext::new_with_signer(
-                self.token_program.to_account_info(),
-                Transfer {
+                token_program,
+                TransferChecked {
                     from: vault_ata,
+                    mint,
                     to: user_ata,
                     authority: self.config.to_account_info(),
                 },
                 signer_seeds,
             ),
             amount,
+            decimals,
         )?;
 
         Ok(())
@@ -1,10 +1,15 @@
 use anchor_lang::prelude::*;
 
-mod errors;
-mod instructions;
-mod state;
+pub mod clmm;
+pub mod errors;
+pub mod events;
+mod helpers;
+pub mod instructions;
+pub mod stable_swap;
+pub mod state;
 
 use instructions::*;
+use state::{CurveType, Twap};
 declare_id!("9FVkz5L9LZix4zXYmqJLzKBwRpm9aJ539J26UsZSrLWN");
 
 #[program]
@@ -16,19 +21,327 @@ pub mod anchor_amm_q4_25 {
         seed: u64,
         fee: u16,
         authority: Option<Pubkey>,
+        curve_type: CurveType,
+        amp: u64,
     ) -> Result<()> {
-        ctx.accounts.init(seed, fee, authority, ctx.bumps)
+        let event = ctx
+            .accounts
+            .init(seed, fee, authority, curve_type, amp, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn initialize_registry(
+        ctx: Context<InitializeRegistry>,
+        admin: Pubkey,
+        fee_tiers: Vec<u16>,
+    ) -> Result<()> {
+        ctx.accounts.init(admin, fee_tiers, ctx.bumps)
+    }
+
+    pub fn add_fee_tier(ctx: Context<UpdateRegistry>, fee: u16) -> Result<()> {
+        ctx.accounts.add_fee_tier(fee)
+    }
+
+    pub fn remove_fee_tier(ctx: Context<UpdateRegistry>, fee: u16) -> Result<()> {
+        ctx.accounts.remove_fee_tier(fee)
+    }
+
+    pub fn set_registry_admin(ctx: Context<UpdateRegistry>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.set_registry_admin(new_admin)
     }
 
     pub fn deposit(ctx: Context<Deposit>, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
-        ctx.accounts.deposit(amount, max_x, max_y)
+        let event = ctx.accounts.deposit(amount, max_x, max_y)?;
+        emit_cpi!(event);
+        Ok(())
     }
 
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
-        ctx.accounts.withdraw(amount, max_x, max_y)
+        let event = ctx.accounts.withdraw(amount, max_x, max_y)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn deposit_single(
+        ctx: Context<Deposit>,
+        is_x: bool,
+        amount_in: u64,
+        min_lp_out: u64,
+    ) -> Result<()> {
+        let event = ctx.accounts.deposit_single(is_x, amount_in, min_lp_out)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn withdraw_single(
+        ctx: Context<Withdraw>,
+        is_x: bool,
+        lp_amount: u64,
+        min_out: u64,
+    ) -> Result<()> {
+        let event = ctx.accounts.withdraw_single(is_x, lp_amount, min_out)?;
+        emit_cpi!(event);
+        Ok(())
     }
 
     pub fn swap(ctx: Context<Swap>, is_x: bool, amount_in: u64, min_amount_out: u64) -> Result<()> {
-        ctx.accounts.swap(is_x, amount_in, min_amount_out)
+        let event = ctx.accounts.swap(is_x, amount_in, min_amount_out)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn swap_exact_out(
+        ctx: Context<Swap>,
+        is_x: bool,
+        amount_out: u64,
+        max_amount_in: u64,
+    ) -> Result<()> {
+        let event = ctx
+            .accounts
+            .swap_exact_out(is_x, amount_out, max_amount_in)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn route_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RouteSwap<'info>>,
+        path: Vec<bool>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        let hops = ctx.remaining_accounts;
+        let events = ctx
+            .accounts
+            .route_swap(hops, path, amount_in, min_amount_out)?;
+        for event in events {
+            emit_cpi!(event);
+        }
+        Ok(())
+    }
+
+    pub fn flash_borrow(ctx: Context<FlashLoan>, is_x: bool, amount: u64) -> Result<()> {
+        ctx.accounts.borrow(is_x, amount)
+    }
+
+    pub fn flash_repay(ctx: Context<FlashLoan>) -> Result<()> {
+        ctx.accounts.repay()
+    }
+
+    // Read-only, the TWAP is returned through the transaction's return data
+    pub fn observe(ctx: Context<Observe>, window: i64) -> Result<Twap> {
+        ctx.accounts.observe(window)
+    }
+
+    pub fn lock(ctx: Context<Update>) -> Result<()> {
+        let event = ctx.accounts.lock()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn unlock(ctx: Context<Update>) -> Result<()> {
+        let event = ctx.accounts.unlock()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn set_gate(ctx: Context<Update>, gate: bool) -> Result<()> {
+        let event = ctx.accounts.set_gate(gate)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn add_to_allowlist(
+        ctx: Context<AddToAllowlist>,
+        user: Pubkey,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.add(user, expires_at, ctx.bumps)
+    }
+
+    // Closing the entry is all there is to it
+    pub fn remove_from_allowlist(_ctx: Context<RemoveFromAllowlist>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn update_fee(ctx: Context<UpdateFee>, fee: u16) -> Result<()> {
+        let event = ctx.accounts.update_fee(fee, &ctx.bumps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn set_dynamic_fee(
+        ctx: Context<Update>,
+        max_fee: u16,
+        volatility_factor: u16,
+        decay_period: i64,
+    ) -> Result<()> {
+        let event = ctx
+            .accounts
+            .set_dynamic_fee(max_fee, volatility_factor, decay_period)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn update_protocol_fee(ctx: Context<Update>, protocol_fee_bps: u16) -> Result<()> {
+        let event = ctx.accounts.update_protocol_fee(protocol_fee_bps)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn ramp_amp(ctx: Context<Update>, target_amp: u64, ramp_stop_ts: i64) -> Result<()> {
+        let event = ctx.accounts.ramp_amp(target_amp, ramp_stop_ts)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn initialize_cl_pool(
+        ctx: Context<InitializeClPool>,
+        seed: u64,
+        fee: u16,
+        tick_spacing: u16,
+        sqrt_price: u128,
+    ) -> Result<()> {
+        ctx.accounts
+            .init(seed, fee, tick_spacing, sqrt_price, ctx.bumps)
+    }
+
+    pub fn initialize_tick_array(
+        ctx: Context<InitializeTickArray>,
+        start_tick_index: i32,
+    ) -> Result<()> {
+        ctx.accounts.init(start_tick_index, ctx.bumps)
+    }
+
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        ctx.accounts.open(tick_lower, tick_upper, ctx.bumps)
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+    ) -> Result<()> {
+        let event = ctx.accounts.increase_liquidity(liquidity, max_x, max_y)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn decrease_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+    ) -> Result<()> {
+        let event = ctx.accounts.decrease_liquidity(liquidity, min_x, min_y)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn collect_fees(ctx: Context<ModifyLiquidity>) -> Result<()> {
+        let event = ctx.accounts.collect_fees()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn cl_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        sqrt_price_limit: u128,
+    ) -> Result<()> {
+        let event = ctx.accounts.swap(
+            ctx.remaining_accounts,
+            is_x,
+            amount_in,
+            min_amount_out,
+            sqrt_price_limit,
+        )?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn initialize_farm(ctx: Context<InitializeFarm>) -> Result<()> {
+        ctx.accounts.init(ctx.bumps)
+    }
+
+    pub fn fund_reward(
+        ctx: Context<FundReward>,
+        emissions_per_second: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) -> Result<()> {
+        ctx.accounts.fund(emissions_per_second, start_ts, end_ts)
+    }
+
+    pub fn reclaim_rewards(ctx: Context<ReclaimRewards>) -> Result<()> {
+        ctx.accounts.reclaim()
+    }
+
+    pub fn stake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+        ctx.accounts.stake(amount, ctx.bumps)
+    }
+
+    pub fn unstake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+        ctx.accounts.unstake(amount)
+    }
+
+    pub fn harvest(ctx: Context<Harvest>) -> Result<()> {
+        ctx.accounts.harvest()
+    }
+
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        id: u64,
+        amount: u64,
+        min_amount_out: u64,
+        keeper_reward: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .place(id, amount, min_amount_out, keeper_reward, ctx.bumps)
+    }
+
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        ctx.accounts.cancel()
+    }
+
+    // Permissionless, the keeper is paid the reward of every order it fills
+    pub fn crank_orders<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CrankOrders<'info>>,
+    ) -> Result<()> {
+        let orders = ctx.remaining_accounts;
+        let events = ctx.accounts.crank(orders)?;
+        for event in events {
+            emit_cpi!(event);
+        }
+        Ok(())
+    }
+
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        ctx.accounts.collect()
+    }
+
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        let event = ctx.accounts.close()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn transfer_authority(ctx: Context<Update>, new_authority: Pubkey) -> Result<()> {
+        let event = ctx.accounts.transfer_authority(new_authority)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn renounce_authority(ctx: Context<Update>) -> Result<()> {
+        let event = ctx.accounts.renounce_authority()?;
+        emit_cpi!(event);
+        Ok(())
     }
 }
@@ -0,0 +1,195 @@
+use anchor_lang::prelude::*;
+use constant_product_curve::SwapResult;
+
+use crate::{clmm::mul_div, errors::AmmError};
+
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 10_000;
+pub const MAX_AMP_CHANGE: u64 = 10; // A ramp can multiply or divide amp by at most this much
+pub const MIN_RAMP_DURATION: i64 = 86_400; // Seconds, also the minimum time between two ramps
+
+const N_COINS: u128 = 2;
+const MAX_ITERATIONS: usize = 255;
+
+// Curve's StableSwap invariant for two coins, with ann = amp * n:
+// ann * (x + y) + D = ann * D + D^3 / (n^n * x * y)
+// Everything is u128, which is enough for reserves up to ~1e17 at MAX_AMP.
+pub fn compute_d(x: u64, y: u64, amp: u64) -> Result<u128> {
+    if x == 0 && y == 0 {
+        return Ok(0);
+    }
+    require!(x != 0 && y != 0, AmmError::ZeroBalance);
+
+    let (x, y) = (x as u128, y as u128);
+    let s = x + y;
+    let ann = amp as u128 * N_COINS;
+
+    // Newton's method from D = x + y, which converges from above
+    let mut d = s;
+    for _ in 0..MAX_ITERATIONS {
+        // D^3 / (n^n * x * y), one factor at a time to stay in range
+        let d_p = mul(d, d)? / (x * N_COINS);
+        let d_p = mul(d_p, d)? / (y * N_COINS);
+
+        let prev = d;
+        let numerator = mul(add(mul(ann, s)?, mul(d_p, N_COINS)?)?, d)?;
+        let denominator = add(mul(ann - 1, d)?, mul(N_COINS + 1, d_p)?)?;
+        d = numerator / denominator;
+
+        if d.abs_diff(prev) <= 1 {
+            return Ok(d);
+        }
+    }
+
+    err!(AmmError::CurveError)
+}
+
+// Balance of one coin that keeps the invariant at `d` when the other one is `x`
+pub fn compute_y(x: u128, d: u128, amp: u64) -> Result<u128> {
+    require!(x != 0, AmmError::ZeroBalance);
+
+    let ann = amp as u128 * N_COINS;
+
+    // y^2 + (b - D) * y = c, solved with Newton's method from y = D
+    let c = mul(d, d)? / (x * N_COINS);
+    let c = mul(c, d)? / (ann * N_COINS);
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let prev = y;
+        let denominator = (2 * y + b).checked_sub(d).ok_or(AmmError::Underflow)?;
+        y = add(mul(y, y)?, c)? / denominator;
+
+        if y.abs_diff(prev) <= 1 {
+            return Ok(y);
+        }
+    }
+
+    err!(AmmError::CurveError)
+}
+
+// Q64.64 price of X in Y the curve trades at on (x, y), i.e. -dy/dx along the
+// invariant. With F the invariant and d_p = D^3 / (4 * x * y) as in compute_d:
+// dF/dx = ann + d_p / x and dF/dy = ann + d_p / y, both multiplied by x * y.
+// The price of Y in X is the same call with the reserves swapped.
+pub fn marginal_price(x: u64, y: u64, amp: u64) -> Result<u128> {
+    require!(x != 0 && y != 0, AmmError::ZeroBalance);
+
+    let d = compute_d(x, y, amp)?;
+    let (x, y) = (x as u128, y as u128);
+    let ann = amp as u128 * N_COINS;
+
+    let d_p = mul(d, d)? / (x * N_COINS);
+    let d_p = mul(d_p, d)? / (y * N_COINS);
+
+    let ann_xy = mul(mul(ann, x)?, y)?;
+    let numerator = add(ann_xy, mul(d_p, y)?)?;
+    let denominator = add(ann_xy, mul(d_p, x)?)?;
+
+    mul_div(numerator, 1 << 64, denominator, false)
+}
+
+// Exact-in trade with the fee taken from the input, like the constant product curve
+pub fn swap(
+    x: u64,
+    y: u64,
+    amp: u64,
+    fee: u16,
+    is_x: bool,
+    amount: u64,
+    min: u64,
+) -> Result<SwapResult> {
+    let (reserve_in, reserve_out) = match is_x {
+        true => (x, y),
+        false => (y, x),
+    };
+
+    let fee_amount = (amount as u128 * fee as u128 / 10_000) as u64;
+    let d = compute_d(x, y, amp)?;
+    let new_out = compute_y(
+        add(reserve_in as u128, (amount - fee_amount) as u128)?,
+        d,
+        amp,
+    )?;
+
+    // One unit less than the invariant allows, so rounding always favours the pool
+    let withdraw = (reserve_out as u128).saturating_sub(new_out + 1) as u64;
+    require!(withdraw >= min, AmmError::SlippageExceeded);
+
+    Ok(SwapResult {
+        deposit: amount,
+        withdraw,
+        fee: fee_amount,
+    })
+}
+
+// Input needed to receive exactly `amount_out`, fee included, rounded up
+pub fn amount_in_for_exact_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amp: u64,
+    amount_out: u64,
+    fee: u16,
+) -> Result<u64> {
+    require!(
+        reserve_in != 0 && reserve_out != 0,
+        AmmError::NoLiquidityInPool
+    );
+    require!(amount_out < reserve_out, AmmError::InsufficientBalance);
+    require!(fee < 10_000, AmmError::InvalidFee);
+
+    let d = compute_d(reserve_in, reserve_out, amp)?;
+    let new_in = compute_y((reserve_out - amount_out) as u128, d, amp)?;
+    let amount_in_after_fee = new_in - reserve_in as u128 + 1;
+
+    let amount_in = mul(amount_in_after_fee, 10_000)?.div_ceil((10_000 - fee) as u128);
+
+    u64::try_from(amount_in).map_err(|_| AmmError::Overflow.into())
+}
+
+// LP tokens for adding `amount` to one side, as Curve's add_liquidity does it.
+// The part of each balance a proportional deposit wouldn't have moved pays
+// half the swap fee, which is the full fee on what is implicitly swapped.
+// Returns (lp, fee in the deposited token, fee in the other token).
+pub fn deposit_single(
+    x: u64,
+    y: u64,
+    amp: u64,
+    fee: u16,
+    lp_supply: u64,
+    is_x: bool,
+    amount: u64,
+) -> Result<(u64, u64, u64)> {
+    let (x1, y1) = match is_x {
+        true => (x.checked_add(amount).ok_or(AmmError::Overflow)?, y),
+        false => (x, y.checked_add(amount).ok_or(AmmError::Overflow)?),
+    };
+
+    let d0 = compute_d(x, y, amp)?;
+    let d1 = compute_d(x1, y1, amp)?;
+
+    let imbalance_fee = |old: u64, new: u64| -> Result<u64> {
+        let ideal = mul(d1, old as u128)? / d0;
+        Ok((ideal.abs_diff(new as u128) * fee as u128 / 20_000) as u64)
+    };
+    let fee_x = imbalance_fee(x, x1)?;
+    let fee_y = imbalance_fee(y, y1)?;
+
+    let d2 = compute_d(x1 - fee_x, y1 - fee_y, amp)?;
+    let lp = mul(lp_supply as u128, d2.saturating_sub(d0))? / d0;
+
+    let lp = u64::try_from(lp).map_err(|_| AmmError::Overflow)?;
+    Ok(match is_x {
+        true => (lp, fee_x, fee_y),
+        false => (lp, fee_y, fee_x),
+    })
+}
+
+fn mul(a: u128, b: u128) -> Result<u128> {
+    a.checked_mul(b).ok_or(AmmError::Overflow.into())
+}
+
+fn add(a: u128, b: u128) -> Result<u128> {
+    a.checked_add(b).ok_or(AmmError::Overflow.into())
+}
@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct AllowEntry {
+    pub config: Pubkey,  // Gated pool the user may trade on
+    pub user: Pubkey,    // Allowlisted trader or liquidity provider
+    pub expires_at: i64, // Unix timestamp the entry stops being accepted at
+    pub bump: u8,        // Bump seed for the entry account
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+// Concentrated-liquidity pool. Lives next to the `Config` pools and shares
+// none of their accounts: liquidity is held by `Position`s over tick ranges
+// instead of LP tokens.
+#[account]
+#[derive(InitSpace)]
+pub struct ClPool {
+    pub seed: u64,                 // Seed to be able to create different pools
+    pub mint_x: Pubkey,            // Token X
+    pub mint_y: Pubkey,            // Token Y
+    pub fee: u16,                  // Swap fee in basis points
+    pub tick_spacing: u16,         // Only every tick_spacing-th tick can bound a position
+    pub sqrt_price: u128,          // Q64.64 square root of the price of X in Y
+    pub tick_current: i32,         // Tick the current price is in
+    pub liquidity: u128,           // Liquidity of the positions in range
+    pub fee_growth_global_x: u128, // Q64.64 fees in X earned per unit of liquidity
+    pub fee_growth_global_y: u128, // Q64.64 fees in Y earned per unit of liquidity
+    pub bump: u8,                  // Bump seed for the pool account
+}
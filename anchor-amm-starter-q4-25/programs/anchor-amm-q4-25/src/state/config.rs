@@ -1,14 +1,220 @@
 use anchor_lang::prelude::*;
 
+use crate::{clmm::mul_div, errors::AmmError, stable_swap, state::Observation};
+
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 5_000; // Most of the swap fee the protocol can keep, the LPs earn the rest
+
 #[account]
 #[derive(InitSpace)]
 pub struct Config {
-    pub seed: u64,                 // Seed to be able to create different pools / configs
-    pub authority: Option<Pubkey>, // If we want an authority to lock the config account
-    pub mint_x: Pubkey,            // Token X
-    pub mint_y: Pubkey,            // Token Y
-    pub fee: u16,                  // Swap fee in basis points
-    pub locked: bool,              // If the pool is locked
-    pub config_bump: u8,           // Bump seed for the config account
-    pub lp_bump: u8,               // Bump seed for the LP token
+    pub seed: u64,                  // Seed to be able to create different pools / configs
+    pub authority: Option<Pubkey>,  // If we want an authority to lock the config account
+    pub initializer: Pubkey,        // Can close the drained pool when there's no authority
+    pub mint_x: Pubkey,             // Token X
+    pub mint_y: Pubkey,             // Token Y
+    pub fee: u16,                   // Swap fee in basis points, the base fee of a dynamic fee
+    pub max_fee: u16,               // Cap on the dynamic fee in basis points
+    pub volatility_factor: u16,     // Fee in basis points per 100% price move, 0 for a static fee
+    pub decay_period: i64,          // Seconds the reference price takes to reach the spot price
+    pub reference_price: u128,      // Q64.64 price of X in Y price moves are measured from
+    pub reference_timestamp: i64,   // Unix timestamp the reference price last decayed at
+    pub protocol_fee_bps: u16,      // Share of the swap fee kept by the protocol, in basis points
+    pub protocol_fees_x: u64,       // Protocol fees in token X waiting to be collected
+    pub protocol_fees_y: u64,       // Protocol fees in token Y waiting to be collected
+    pub curve_type: CurveType,      // Invariant the pool trades on
+    pub initial_amp: u64,           // StableSwap amplification when the current ramp started
+    pub target_amp: u64,            // StableSwap amplification once the current ramp ends
+    pub ramp_start_ts: i64,         // Unix timestamp the current amp ramp started at
+    pub ramp_stop_ts: i64,          // Unix timestamp the current amp ramp ends at
+    pub reserve_x: u64,             // LP reserves of X as of the last instruction that moved them
+    pub reserve_y: u64,             // LP reserves of Y, what the price accumulators run on
+    pub price_x_cumulative: u128,   // Sum of Q64.64 price of X in Y weighted by seconds
+    pub price_y_cumulative: u128,   // Sum of Q64.64 price of Y in X weighted by seconds
+    pub last_update_slot: u64,      // Slot of the last price accumulator update
+    pub last_update_timestamp: i64, // Unix timestamp of the last price accumulator update
+    pub locked: bool,               // If the pool is locked
+    pub gate: bool,                 // Swaps and liquidity need an allowlist entry when set
+    pub flash_loan: u64,            // Owed to the vault by the flash loan in progress, fee included
+    pub flash_loan_is_x: bool,      // Whether the flash loan in progress is in token X
+    pub config_bump: u8,            // Bump seed for the config account
+    pub lp_bump: u8,                // Bump seed for the LP token
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum CurveType {
+    ConstantProduct,
+    StableSwap,
+}
+
+impl Config {
+    // StableSwap amplification at `now`, moving linearly over the ramp
+    pub fn amp(&self, now: i64) -> u64 {
+        if now >= self.ramp_stop_ts {
+            return self.target_amp;
+        }
+
+        let elapsed = (now - self.ramp_start_ts) as u128;
+        let duration = (self.ramp_stop_ts - self.ramp_start_ts) as u128;
+        let (initial, target) = (self.initial_amp as u128, self.target_amp as u128);
+
+        let amp = match target >= initial {
+            true => initial + (target - initial) * elapsed / duration,
+            false => initial - (initial - target) * elapsed / duration,
+        };
+
+        amp as u64
+    }
+
+    // Amp for the instruction being priced. Every trade runs
+    // `update_price_cumulatives` first, so the last update is the current time.
+    pub fn current_amp(&self) -> u64 {
+        self.amp(self.last_update_timestamp)
+    }
+
+    // The curve's invariant over reserves (x, y), what the first deposit gets
+    // in LP tokens: sqrt(x * y) for constant product, D for StableSwap
+    pub fn invariant(&self, x: u64, y: u64) -> Result<u128> {
+        match self.curve_type {
+            CurveType::ConstantProduct => Ok((x as u128 * y as u128).isqrt()),
+            CurveType::StableSwap => stable_swap::compute_d(x, y, self.current_amp()),
+        }
+    }
+
+    // Q64.64 price of X in Y the pool trades at on reserves (x, y), none of
+    // which can be 0: y / x for constant product, the curve's marginal price for
+    // StableSwap, which stays near 1 however far the reserves drift apart.
+    // Swap the reserves for the price of Y in X.
+    pub fn spot_price(&self, x: u64, y: u64) -> Result<u128> {
+        match self.curve_type {
+            CurveType::ConstantProduct => Ok(((y as u128) << 64) / x as u128),
+            CurveType::StableSwap => stable_swap::marginal_price(x, y, self.current_amp()),
+        }
+    }
+
+    // Fee in basis points for a swap against reserves (x, y), `fee` unless the
+    // pool has a dynamic fee. The reference price first decays towards the spot
+    // price, then every 100% the spot price sits away from it (capped at 100%)
+    // adds `volatility_factor` basis points, up to `max_fee`. The spot price is
+    // the curve's, so a StableSwap pool that still trades near the peg doesn't
+    // see its reserves drifting apart as a move. Like `current_amp` it runs at
+    // the time of the last `update_price_cumulatives`.
+    pub fn trade_fee(&mut self, reserve_x: u64, reserve_y: u64) -> Result<u16> {
+        if self.volatility_factor == 0 || reserve_x == 0 || reserve_y == 0 {
+            return Ok(self.fee);
+        }
+
+        let now = self.last_update_timestamp;
+        let spot = self.spot_price(reserve_x, reserve_y)?;
+        let elapsed = now - self.reference_timestamp;
+
+        if self.reference_price == 0 || elapsed >= self.decay_period {
+            self.reference_price = spot;
+        } else if elapsed > 0 {
+            let step = mul_div(
+                spot.abs_diff(self.reference_price),
+                elapsed as u128,
+                self.decay_period as u128,
+                false,
+            )?;
+            self.reference_price = match spot > self.reference_price {
+                true => self.reference_price + step,
+                false => self.reference_price - step,
+            };
+        }
+        self.reference_timestamp = now;
+
+        let moved = spot
+            .abs_diff(self.reference_price)
+            .min(self.reference_price);
+        let volatility = mul_div(
+            moved,
+            self.volatility_factor as u128,
+            self.reference_price,
+            false,
+        )?;
+
+        Ok((self.fee as u128 + volatility).min(self.max_fee as u128) as u16)
+    }
+
+    // Gated pools only take swaps and liquidity from users with an allowlist
+    // entry, `expires_at` is the caller's entry when one was passed
+    pub fn check_gate(&self, expires_at: Option<i64>) -> Result<()> {
+        if !self.gate {
+            return Ok(());
+        }
+
+        let expires_at = expires_at.ok_or(AmmError::NotAllowlisted)?;
+        require!(
+            Clock::get()?.unix_timestamp < expires_at,
+            AmmError::AllowlistExpired
+        );
+
+        Ok(())
+    }
+
+    // Vault balances that belong to the LPs, i.e. without the accrued protocol fees
+    pub fn reserves(&self, vault_x: u64, vault_y: u64) -> Result<(u64, u64)> {
+        let x = vault_x
+            .checked_sub(self.protocol_fees_x)
+            .ok_or(AmmError::Underflow)?;
+        let y = vault_y
+            .checked_sub(self.protocol_fees_y)
+            .ok_or(AmmError::Underflow)?;
+        Ok((x, y))
+    }
+
+    // Sets aside the protocol's share of a swap fee paid in token X (is_x) or Y
+    pub fn accrue_protocol_fee(&mut self, is_x: bool, fee: u64) -> Result<()> {
+        let protocol_fee = (fee as u128)
+            .checked_mul(self.protocol_fee_bps as u128)
+            .ok_or(AmmError::Overflow)?
+            .checked_div(10_000)
+            .ok_or(AmmError::Overflow)? as u64;
+
+        let accrued = match is_x {
+            true => &mut self.protocol_fees_x,
+            false => &mut self.protocol_fees_y,
+        };
+        *accrued = accrued
+            .checked_add(protocol_fee)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+
+    // Adds the spot prices of the stored reserves, weighted by the seconds they
+    // held, to the accumulators. StableSwap prices use the amp of the last
+    // update. Has to run before the reserves change. The
+    // vault balances aren't read here, tokens sent straight to a vault only
+    // move the price once an instruction has booked them.
+    pub fn update_price_cumulatives(&mut self) -> Result<()> {
+        let clock = Clock::get()?;
+        let elapsed = clock.unix_timestamp - self.last_update_timestamp;
+
+        if elapsed > 0 && self.reserve_x != 0 && self.reserve_y != 0 {
+            // Accumulators are allowed to wrap, only their differences matter
+            let elapsed = elapsed as u128;
+            let price_x = self.spot_price(self.reserve_x, self.reserve_y)?;
+            let price_y = self.spot_price(self.reserve_y, self.reserve_x)?;
+            self.price_x_cumulative = self
+                .price_x_cumulative
+                .wrapping_add(price_x.wrapping_mul(elapsed));
+            self.price_y_cumulative = self
+                .price_y_cumulative
+                .wrapping_add(price_y.wrapping_mul(elapsed));
+        }
+
+        self.last_update_slot = clock.slot;
+        self.last_update_timestamp = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn observation(&self) -> Observation {
+        Observation {
+            timestamp: self.last_update_timestamp,
+            price_x_cumulative: self.price_x_cumulative,
+            price_y_cumulative: self.price_y_cumulative,
+        }
+    }
 }
@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+
+use crate::{clmm::mul_div, errors::AmmError};
+
+pub const MAX_REWARDS: usize = 3; // Reward tokens a farm can emit side by side
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Reward {
+    pub mint: Pubkey,              // Default until the slot is first funded, then fixed
+    pub emissions_per_second: u64, // Shared by everything staked at the time
+    pub start_ts: i64,             // Unix timestamp emissions start at
+    pub end_ts: i64,               // Unix timestamp emissions stop at
+    pub reward_per_share: u128,    // Q64.64 rewards accrued per staked LP token, wrapping
+    pub unallocated: u64,          // Emitted while nothing was staked, owed to no one
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Farm {
+    pub config: Pubkey,                 // Pool whose LP tokens are staked
+    pub mint_lp: Pubkey,                // LP mint of the pool
+    pub total_staked: u64,              // LP tokens held by the farm
+    pub last_update_ts: i64,            // Unix timestamp the rewards were accrued up to
+    pub rewards: [Reward; MAX_REWARDS], // Reward tokens emitted to the stakers
+    pub bump: u8,                       // Bump seed for the farm account
+}
+
+impl Farm {
+    // Accrues the emissions since the last update to the stakers. Emissions
+    // while nothing is staked aren't owed to anyone, they're set aside for
+    // the authority to reclaim once the schedule is over.
+    pub fn accrue(&mut self, now: i64) -> Result<()> {
+        for reward in self.rewards.iter_mut() {
+            let from = self.last_update_ts.max(reward.start_ts);
+            let to = now.min(reward.end_ts);
+            if to <= from {
+                continue;
+            }
+
+            let emitted = reward.emissions_per_second as u128 * (to - from) as u128;
+            if self.total_staked == 0 {
+                reward.unallocated = u64::try_from(emitted)
+                    .ok()
+                    .and_then(|emitted| reward.unallocated.checked_add(emitted))
+                    .ok_or(AmmError::Overflow)?;
+            } else {
+                let per_share = mul_div(emitted, 1 << 64, self.total_staked as u128, false)?;
+                reward.reward_per_share = reward.reward_per_share.wrapping_add(per_share);
+            }
+        }
+
+        self.last_update_ts = now;
+        Ok(())
+    }
+
+    pub fn reward_index(&self, mint: &Pubkey) -> Option<usize> {
+        self.rewards.iter().position(|reward| reward.mint == *mint)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Stake {
+    pub farm: Pubkey,                          // Farm the LP tokens are staked in
+    pub owner: Pubkey,                         // Only the owner can unstake or harvest
+    pub amount: u64,                           // LP tokens staked
+    pub reward_per_share: [u128; MAX_REWARDS], // Farm's accumulators when rewards were last settled
+    pub rewards_owed: [u64; MAX_REWARDS],      // Settled rewards waiting to be harvested
+    pub bump: u8,                              // Bump seed for the stake account
+}
+
+impl Stake {
+    // Settles the rewards earned since the last update, the farm has to be
+    // accrued first. Done before every change of `amount`.
+    pub fn settle(&mut self, farm: &Farm) -> Result<()> {
+        let settled = self
+            .reward_per_share
+            .iter_mut()
+            .zip(self.rewards_owed.iter_mut())
+            .zip(farm.rewards.iter());
+
+        for ((last, owed), reward) in settled {
+            let growth = reward.reward_per_share.wrapping_sub(*last);
+            let earned = mul_div(growth, self.amount as u128, 1 << 64, false)?;
+            let earned = u64::try_from(earned).map_err(|_| AmmError::Overflow)?;
+
+            *owed = owed.checked_add(earned).ok_or(AmmError::Overflow)?;
+            *last = reward.reward_per_share;
+        }
+
+        Ok(())
+    }
+}
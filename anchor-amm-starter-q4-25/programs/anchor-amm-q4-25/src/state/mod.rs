@@ -1,3 +1,19 @@
+pub mod allowlist;
+pub mod cl_pool;
 pub mod config;
+pub mod farm;
+pub mod oracle;
+pub mod order;
+pub mod position;
+pub mod registry;
+pub mod tick_array;
 
+pub use allowlist::*;
+pub use cl_pool::*;
 pub use config::*;
+pub use farm::*;
+pub use oracle::*;
+pub use order::*;
+pub use position::*;
+pub use registry::*;
+pub use tick_array::*;
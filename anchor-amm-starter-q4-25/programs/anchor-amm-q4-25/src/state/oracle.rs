@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+pub const OBSERVATIONS: usize = 16; // Size of the observation ring buffer
+pub const OBSERVATION_INTERVAL: i64 = 60; // Minimum seconds between two observations
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub price_x_cumulative: u128, // Config.price_x_cumulative at `timestamp`
+    pub price_y_cumulative: u128, // Config.price_y_cumulative at `timestamp`
+}
+
+// Time weighted average prices as Q64.64 fixed point numbers
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Twap {
+    pub price_x: u128, // Price of X denominated in Y
+    pub price_y: u128, // Price of Y denominated in X
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Oracle {
+    pub config: Pubkey,                            // Pool the observations belong to
+    pub index: u16,                                // Position of the most recent observation
+    pub observations: [Observation; OBSERVATIONS], // Ring buffer of cumulative prices
+    pub bump: u8,                                  // Bump seed for the oracle account
+}
+
+impl Oracle {
+    // Stores a new observation, at most one every OBSERVATION_INTERVAL seconds
+    pub fn record(&mut self, observation: Observation) {
+        let latest = self.observations[self.index as usize];
+        if observation.timestamp - latest.timestamp < OBSERVATION_INTERVAL {
+            return;
+        }
+
+        self.index = (self.index + 1) % OBSERVATIONS as u16;
+        self.observations[self.index as usize] = observation;
+    }
+
+    // TWAP between the newest observation that is at least `window` seconds old
+    // and `now`, which should hold the current cumulative prices. Pure so clients
+    // can run it on a fetched Oracle account as well.
+    pub fn twap(&self, now: Observation, window: i64) -> Result<Twap> {
+        require!(window > 0, AmmError::InvalidAmount);
+
+        let target = now.timestamp - window;
+        let start = self
+            .observations
+            .iter()
+            .filter(|o| o.timestamp != 0 && o.timestamp <= target)
+            .max_by_key(|o| o.timestamp)
+            .ok_or(AmmError::ObservationNotFound)?;
+
+        let elapsed = (now.timestamp - start.timestamp) as u128;
+
+        Ok(Twap {
+            price_x: now
+                .price_x_cumulative
+                .wrapping_sub(start.price_x_cumulative)
+                / elapsed,
+            price_y: now
+                .price_y_cumulative
+                .wrapping_sub(start.price_y_cumulative)
+                / elapsed,
+        })
+    }
+}
@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Order {
+    pub config: Pubkey,      // Pool the order fills against
+    pub owner: Pubkey,       // Receives the output, only the owner can cancel
+    pub id: u64,             // Lets an owner keep several orders on a pool
+    pub mint_in: Pubkey,     // Mint sold, held by the order's escrow
+    pub min_amount_out: u64, // Limit price: fills once the escrow buys at least this
+    pub keeper_reward: u64,  // Lamports paid to whoever fills the order
+    pub bump: u8,            // Bump seed for the order account
+}
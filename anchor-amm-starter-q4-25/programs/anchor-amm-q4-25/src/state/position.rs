@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    clmm::{add_delta, mul_div},
+    errors::AmmError,
+};
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,              // ClPool the position provides liquidity to
+    pub owner: Pubkey,             // Only the owner can change the position or collect its fees
+    pub tick_lower: i32,           // Inclusive lower bound of the price range
+    pub tick_upper: i32,           // Exclusive upper bound of the price range
+    pub liquidity: u128,           // Liquidity added over the range
+    pub fee_growth_inside_x: u128, // Fee growth inside the range when fees were last settled
+    pub fee_growth_inside_y: u128, // Same for token Y
+    pub fees_owed_x: u64,          // Settled fees in token X waiting to be collected
+    pub fees_owed_y: u64,          // Settled fees in token Y waiting to be collected
+    pub bump: u8,                  // Bump seed for the position account
+}
+
+impl Position {
+    // Settles the fees earned since the last update, then applies `liquidity_delta`
+    pub fn update(
+        &mut self,
+        liquidity_delta: i128,
+        (inside_x, inside_y): (u128, u128),
+    ) -> Result<()> {
+        let earned = |inside: u128, last: u128| -> Result<u64> {
+            let growth = inside.wrapping_sub(last);
+            let fees = mul_div(growth, self.liquidity, 1 << 64, false)?;
+            u64::try_from(fees).map_err(|_| AmmError::Overflow.into())
+        };
+
+        let earned_x = earned(inside_x, self.fee_growth_inside_x)?;
+        let earned_y = earned(inside_y, self.fee_growth_inside_y)?;
+
+        self.fees_owed_x = self
+            .fees_owed_x
+            .checked_add(earned_x)
+            .ok_or(AmmError::Overflow)?;
+        self.fees_owed_y = self
+            .fees_owed_y
+            .checked_add(earned_y)
+            .ok_or(AmmError::Overflow)?;
+        self.fee_growth_inside_x = inside_x;
+        self.fee_growth_inside_y = inside_y;
+        self.liquidity = add_delta(self.liquidity, liquidity_delta)?;
+
+        Ok(())
+    }
+}
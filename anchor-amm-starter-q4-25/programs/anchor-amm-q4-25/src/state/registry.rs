@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+
+pub const MAX_FEE_TIERS: usize = 8; // Most fee tiers the registry can allow at once
+
+// Global registry every pool, concentrated ones included, is created through
+#[account]
+#[derive(InitSpace)]
+pub struct Registry {
+    pub admin: Pubkey, // Manages the fee tiers
+    #[max_len(MAX_FEE_TIERS)]
+    pub fee_tiers: Vec<u16>, // Swap fees in basis points pools can be created with
+    pub pool_count: u64, // Pools currently registered, closed pools are not counted
+    pub next_index: u64, // Index of the next pool, those of closed pools aren't reused
+    pub bump: u8,      // Bump seed for the registry account
+}
+
+impl Registry {
+    // Hands the new pool the next index and counts it
+    pub fn register(&mut self) -> Result<u64> {
+        let index = self.next_index;
+        self.next_index = index.checked_add(1).ok_or(AmmError::Overflow)?;
+        self.pool_count = self.pool_count.checked_add(1).ok_or(AmmError::Overflow)?;
+        Ok(index)
+    }
+}
+
+// Canonical pool for a sorted mint pair and fee tier, whatever its curve.
+// Its address can be derived from the pair alone.
+#[account]
+#[derive(InitSpace)]
+pub struct PoolEntry {
+    pub config: Pubkey, // Pool config the pair resolves to, a ClPool for concentrated pools
+    pub index: u64,     // Registry.next_index when the pool was created
+    pub bump: u8,       // Bump seed for the pool entry account
+}
+
+// Pool at an index, so walking 0..Registry.next_index lists every pool
+// without scanning the program's accounts. Closed pools leave a gap.
+#[account]
+#[derive(InitSpace)]
+pub struct PoolIndex {
+    pub config: Pubkey, // Pool config at the index, a ClPool for concentrated pools
+    pub bump: u8,       // Bump seed for the pool index account
+}
@@ -0,0 +1,134 @@
+use anchor_lang::prelude::*;
+
+use crate::{clmm::add_delta, errors::AmmError, state::ClPool};
+
+pub const TICK_ARRAY_SIZE: usize = 32; // Ticks per TickArray account
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Tick {
+    pub initialized: bool,          // Some position starts or ends at this tick
+    pub liquidity_net: i128,        // Liquidity added when the price crosses the tick upwards
+    pub liquidity_gross: u128,      // Liquidity of every position bounded by the tick
+    pub fee_growth_outside_x: u128, // Fee growth on the other side of the tick from the price
+    pub fee_growth_outside_y: u128, // Same for token Y
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TickArray {
+    pub pool: Pubkey,                   // ClPool the ticks belong to
+    pub start_tick_index: i32,          // First tick, a multiple of tick_spacing * TICK_ARRAY_SIZE
+    pub ticks: [Tick; TICK_ARRAY_SIZE], // Every tick_spacing-th tick from start_tick_index
+    pub bump: u8,                       // Bump seed for the tick array account
+}
+
+impl TickArray {
+    // Start of the array that holds `tick`
+    pub fn start_index(tick: i32, tick_spacing: u16) -> i32 {
+        let span = tick_spacing as i32 * TICK_ARRAY_SIZE as i32;
+        tick.div_euclid(span) * span
+    }
+
+    fn offset(&self, tick: i32, tick_spacing: u16) -> Result<usize> {
+        let spacing = tick_spacing as i32;
+        require!(
+            tick % spacing == 0 && Self::start_index(tick, tick_spacing) == self.start_tick_index,
+            AmmError::InvalidTickArray
+        );
+
+        Ok(((tick - self.start_tick_index) / spacing) as usize)
+    }
+
+    pub fn tick(&self, tick: i32, tick_spacing: u16) -> Result<&Tick> {
+        Ok(&self.ticks[self.offset(tick, tick_spacing)?])
+    }
+
+    // Adds a position's liquidity to one of its bounds. A tick that becomes
+    // initialized assumes every fee so far was earned below it.
+    pub fn update_tick(
+        &mut self,
+        tick: i32,
+        pool: &ClPool,
+        liquidity_delta: i128,
+        is_upper: bool,
+    ) -> Result<()> {
+        let offset = self.offset(tick, pool.tick_spacing)?;
+        let entry = &mut self.ticks[offset];
+
+        if entry.liquidity_gross == 0 {
+            if tick <= pool.tick_current {
+                entry.fee_growth_outside_x = pool.fee_growth_global_x;
+                entry.fee_growth_outside_y = pool.fee_growth_global_y;
+            }
+            entry.initialized = true;
+        }
+
+        entry.liquidity_gross = add_delta(entry.liquidity_gross, liquidity_delta)?;
+        entry.liquidity_net = match is_upper {
+            true => entry.liquidity_net.checked_sub(liquidity_delta),
+            false => entry.liquidity_net.checked_add(liquidity_delta),
+        }
+        .ok_or(AmmError::Overflow)?;
+
+        if entry.liquidity_gross == 0 {
+            *entry = Tick::default();
+        }
+
+        Ok(())
+    }
+
+    // Flips the tick's outside fee growth to the other side of the price and
+    // returns the liquidity to add when moving up through it
+    pub fn cross(&mut self, tick: i32, pool: &ClPool) -> Result<i128> {
+        let offset = self.offset(tick, pool.tick_spacing)?;
+        let entry = &mut self.ticks[offset];
+
+        entry.fee_growth_outside_x = pool
+            .fee_growth_global_x
+            .wrapping_sub(entry.fee_growth_outside_x);
+        entry.fee_growth_outside_y = pool
+            .fee_growth_global_y
+            .wrapping_sub(entry.fee_growth_outside_y);
+
+        Ok(entry.liquidity_net)
+    }
+
+    // Closest tick of this array a swap moving from `tick_current` has to stop
+    // at, and whether it is initialized. Without an initialized tick that's the
+    // array's edge. None once the price has moved past this array.
+    pub fn next_initialized_tick(
+        &self,
+        tick_current: i32,
+        tick_spacing: u16,
+        is_x: bool,
+    ) -> Option<(i32, bool)> {
+        let spacing = tick_spacing as i32;
+        let last = TICK_ARRAY_SIZE as i32 - 1;
+        let tick_at = |offset: i32| self.start_tick_index + offset * spacing;
+        let initialized = |offset: i32| self.ticks[offset as usize].initialized;
+
+        match is_x {
+            // Price moving down, the current tick itself is the first candidate
+            true => {
+                if tick_current < self.start_tick_index {
+                    return None;
+                }
+                let from = ((tick_current - self.start_tick_index) / spacing).min(last);
+                let offset = (0..=from).rev().find(|&o| initialized(o)).unwrap_or(0);
+                Some((tick_at(offset), initialized(offset)))
+            }
+            // Price moving up, only ticks above the current one
+            false => {
+                if tick_current >= tick_at(last) {
+                    return None;
+                }
+                let from = match tick_current < self.start_tick_index {
+                    true => 0,
+                    false => (tick_current - self.start_tick_index) / spacing + 1,
+                };
+                let offset = (from..=last).find(|&o| initialized(o)).unwrap_or(last);
+                Some((tick_at(offset), initialized(offset)))
+            }
+        }
+    }
+}
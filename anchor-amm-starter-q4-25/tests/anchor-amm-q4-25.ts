@@ -7,7 +7,13 @@ import {
   mintTo,
   getAccount,
   getAssociatedTokenAddress,
+  transfer,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  getMintLen,
+  ExtensionType,
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { assert } from "chai";
@@ -30,12 +36,88 @@ describe("Anchor AMM", () => {
   let userYAccount: anchor.web3.PublicKey;
   let mintLp: anchor.web3.PublicKey;
   let userLp: anchor.web3.PublicKey;
+  let configLp: anchor.web3.PublicKey;
+  let registry: anchor.web3.PublicKey;
 
   const seed = new BN(Math.floor(Math.random() * 1000000));
   const fee = 30;
+  const constantProduct = { constantProduct: {} };
   const initialMintAmount = 1_000_000_000
+  const feeTiers = [5, 30, 100];
+
+  // Canonical registry entry of a sorted pair at a fee tier
+  const poolEntry = (
+    poolMintX: anchor.web3.PublicKey,
+    poolMintY: anchor.web3.PublicKey,
+    poolFee: number,
+  ) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("pool"),
+        poolMintX.toBuffer(),
+        poolMintY.toBuffer(),
+        new BN(poolFee).toArrayLike(Buffer, "le", 2),
+      ],
+      program.programId,
+    )[0];
+
+  // Pool registered at an index, in creation order
+  const poolIndex = (index: BN) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("pool_index"), index.toArrayLike(Buffer, "le", 8)],
+      program.programId,
+    )[0];
+
+  // Mint keypair that sorts after `mint`, so the two make a valid X/Y pair
+  const keypairAfter = (mint: anchor.web3.PublicKey) => {
+    let keypair: anchor.web3.Keypair;
+    do {
+      keypair = anchor.web3.Keypair.generate();
+    } while (
+      Buffer.compare(keypair.publicKey.toBuffer(), mint.toBuffer()) <= 0
+    );
+    return keypair;
+  };
+
+  // Events are the data of a CPI the program makes into itself
+  const emittedEvents = async (signature: string) => {
+    const tx = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const keys = tx.transaction.message.getAccountKeys();
+    return tx.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .filter((ix) => keys.get(ix.programIdIndex).equals(program.programId))
+      .map((ix) => {
+        const data = anchor.utils.bytes.bs58.decode(ix.data);
+        return program.coder.events.decode(
+          anchor.utils.bytes.base64.encode(data.subarray(8)),
+        );
+      });
+  };
 
   before(async () => {
+    [registry] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("registry")],
+      program.programId,
+    );
+    // Only the upgrade authority can create the registry
+    const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111"),
+    );
+    await program.methods
+      .initializeRegistry(wallet.publicKey, feeTiers)
+      .accounts({
+        payer: wallet.publicKey,
+        program: program.programId,
+        programData,
+        registry,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
     mintX = await createMint(
       connection,
       wallet.payer,
@@ -50,6 +132,7 @@ describe("Anchor AMM", () => {
       wallet.publicKey,
       null,
       6,
+      keypairAfter(mintX),
     );
 
     const userXAta = await getOrCreateAssociatedTokenAccount(
@@ -98,21 +181,26 @@ describe("Anchor AMM", () => {
 
     vaultX = await getAssociatedTokenAddress(mintX, config, true);
     vaultY = await getAssociatedTokenAddress(mintY, config, true);
+    configLp = await getAssociatedTokenAddress(mintLp, config, true);
   });
 
   describe("Initialize Pool", () => {
     it("Initializing AMM pool", async () => {
       await program.methods
-        .initialize(seed, fee, null)
+        .initialize(seed, fee, wallet.publicKey, constantProduct, new BN(0))
         .accounts({
           initializer: wallet.publicKey,
           mintX,
           mintY,
+          registry,
+          poolEntry: poolEntry(mintX, mintY, fee),
           config,
           mintLp,
           vaultX,
           vaultY,
           tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -122,12 +210,140 @@ describe("Anchor AMM", () => {
       assert.equal(configAccount.seed.toString(), seed.toString());
       assert.equal(configAccount.fee, fee);
       assert.equal(configAccount.locked, false);
+      assert.equal(
+        configAccount.authority.toBase58(),
+        wallet.publicKey.toBase58(),
+      );
+    });
+  });
+
+  describe("Registry", () => {
+    // Accounts to initialize a fresh config for the given pair and fee tier
+    const initAccounts = async (
+      poolSeed: BN,
+      poolMintX: anchor.web3.PublicKey,
+      poolMintY: anchor.web3.PublicKey,
+      poolFee: number,
+    ) => {
+      const [poolConfig] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config"), poolSeed.toArrayLike(Buffer, "le", 8)],
+        program.programId,
+      );
+      const [poolMintLp] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("lp"), poolConfig.toBuffer()],
+        program.programId,
+      );
+      return {
+        initializer: wallet.publicKey,
+        mintX: poolMintX,
+        mintY: poolMintY,
+        registry,
+        poolEntry: poolEntry(poolMintX, poolMintY, poolFee),
+        config: poolConfig,
+        mintLp: poolMintLp,
+        vaultX: await getAssociatedTokenAddress(poolMintX, poolConfig, true),
+        vaultY: await getAssociatedTokenAddress(poolMintY, poolConfig, true),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgramX: TOKEN_PROGRAM_ID,
+        tokenProgramY: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      };
+    };
+
+    const otherSeed = () =>
+      new BN(Math.floor(Math.random() * 1000000) + 5000000);
+
+    it("Registers the pool under its sorted pair and fee tier", async () => {
+      const entry = await program.account.poolEntry.fetch(
+        poolEntry(mintX, mintY, fee),
+      );
+      assert.equal(entry.config.toBase58(), config.toBase58());
+
+      const registryAccount = await program.account.registry.fetch(registry);
+      assert.deepEqual(registryAccount.feeTiers, feeTiers);
+      assert.equal(registryAccount.poolCount.toNumber(), 1);
+      assert.equal(entry.index.toNumber(), 0);
+
+      // Walking the indexes lists every pool
+      const indexAccount = await program.account.poolIndex.fetch(
+        poolIndex(entry.index),
+      );
+      assert.equal(indexAccount.config.toBase58(), config.toBase58());
+    });
+
+    it("Should fail to create a second pool for the same pair", async () => {
+      const poolSeed = otherSeed();
+      try {
+        await program.methods
+          .initialize(poolSeed, fee, null, constantProduct, new BN(0))
+          .accounts(await initAccounts(poolSeed, mintX, mintY, fee))
+          .rpc();
+        assert.fail("Should have thrown account already in use error");
+      } catch (error) {
+        assert.include(error.toString(), "already in use");
+      }
+    });
+
+    it("Should fail to create a pool with reversed mints", async () => {
+      const poolSeed = otherSeed();
+      try {
+        await program.methods
+          .initialize(poolSeed, fee, null, constantProduct, new BN(0))
+          .accounts(await initAccounts(poolSeed, mintY, mintX, fee))
+          .rpc();
+        assert.fail("Should have thrown invalid mint pair error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidMintPair");
+      }
+    });
+
+    it("Should fail to create a pool at a fee tier not allowed", async () => {
+      const poolSeed = otherSeed();
+      try {
+        await program.methods
+          .initialize(poolSeed, 50, null, constantProduct, new BN(0))
+          .accounts(await initAccounts(poolSeed, mintX, mintY, 50))
+          .rpc();
+        assert.fail("Should have thrown invalid fee tier error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFeeTier");
+      }
+    });
+
+    it("Should fail to create a pool with a fee above 100%", async () => {
+      const poolSeed = otherSeed();
+      try {
+        await program.methods
+          .initialize(poolSeed, 10_001, null, constantProduct, new BN(0))
+          .accounts(await initAccounts(poolSeed, mintX, mintY, 10_001))
+          .rpc();
+        assert.fail("Should have thrown invalid fee error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFee");
+      }
+    });
+
+    it("Should fail to add a fee tier without being the admin", async () => {
+      const notAdmin = anchor.web3.Keypair.generate();
+      try {
+        await program.methods
+          .addFeeTier(50)
+          .accounts({ admin: notAdmin.publicKey, registry })
+          .signers([notAdmin])
+          .rpc();
+        assert.fail("Should have thrown invalid authority error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidAuthority");
+      }
     });
   });
 
   describe("Deposit in AMM", () => {
     it("First deposit", async () => {
-      const LpAmount = new BN(100_000000);
+      const minimumLiquidity = 1_000;
+      // sqrt(100_000000 * 100_000000) minus the permanently locked liquidity
+      const LpAmount = new BN(100_000000 - minimumLiquidity);
       const maxX = new BN(100_000000);
       const maxY = new BN(100_000000);
 
@@ -150,7 +366,10 @@ describe("Anchor AMM", () => {
           userX: userXAccount,
           userY: userYAccount,
           userLp,
+          configLp,
           tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -159,12 +378,278 @@ describe("Anchor AMM", () => {
       const userLpAccount = await getAccount(connection, userLp);
       const vaultXAccount = await getAccount(connection, vaultX);
       const vaultYAccount = await getAccount(connection, vaultY);
+      const configLpAccount = await getAccount(connection, configLp);
       assert.equal(userLpAccount.amount.toString(), LpAmount.toString());
+      assert.equal(
+        configLpAccount.amount.toString(),
+        minimumLiquidity.toString(),
+      );
       assert.equal(vaultXAccount.amount.toString(), maxX.toString());
       assert.equal(vaultYAccount.amount.toString(), maxY.toString());
     });
   });
 
+  describe("Minimum Liquidity", () => {
+    const attackSeed = new BN(Math.floor(Math.random() * 1000000) + 1000000);
+    const attackFee = 100; // The pair already has its 30 bps pool
+    let attackConfig: anchor.web3.PublicKey;
+    let attackMintLp: anchor.web3.PublicKey;
+    let attackVaultX: anchor.web3.PublicKey;
+    let attackVaultY: anchor.web3.PublicKey;
+    let attackUserLp: anchor.web3.PublicKey;
+    let attackConfigLp: anchor.web3.PublicKey;
+
+    const depositAccounts = () => ({
+      user: wallet.publicKey,
+      mintX,
+      mintY,
+      config: attackConfig,
+      mintLp: attackMintLp,
+      vaultX: attackVaultX,
+      vaultY: attackVaultY,
+      userX: userXAccount,
+      userY: userYAccount,
+      userLp: attackUserLp,
+      configLp: attackConfigLp,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    const receiver = anchor.web3.Keypair.generate();
+    const closePoolAccounts = () => ({
+      authority: wallet.publicKey,
+      receiver: receiver.publicKey,
+      mintX,
+      mintY,
+      registry,
+      admin: wallet.publicKey,
+      poolEntry: poolEntry(mintX, mintY, attackFee),
+      config: attackConfig,
+      mintLp: attackMintLp,
+      configLp: attackConfigLp,
+      vaultX: attackVaultX,
+      vaultY: attackVaultY,
+      treasuryX: userXAccount,
+      treasuryY: userYAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      [attackConfig] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config"), attackSeed.toArrayLike(Buffer, "le", 8)],
+        program.programId,
+      );
+      [attackMintLp] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("lp"), attackConfig.toBuffer()],
+        program.programId,
+      );
+      attackVaultX = await getAssociatedTokenAddress(mintX, attackConfig, true);
+      attackVaultY = await getAssociatedTokenAddress(mintY, attackConfig, true);
+      attackUserLp = await getAssociatedTokenAddress(
+        attackMintLp,
+        wallet.publicKey,
+      );
+      attackConfigLp = await getAssociatedTokenAddress(
+        attackMintLp,
+        attackConfig,
+        true,
+      );
+
+      await program.methods
+        .initialize(attackSeed, attackFee, null, constantProduct, new BN(0))
+        .accounts({
+          initializer: wallet.publicKey,
+          mintX,
+          mintY,
+          registry,
+          poolEntry: poolEntry(mintX, mintY, attackFee),
+          config: attackConfig,
+          mintLp: attackMintLp,
+          vaultX: attackVaultX,
+          vaultY: attackVaultY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Should reject a dust first deposit", async () => {
+      try {
+        await program.methods
+          .deposit(new BN(1), new BN(1), new BN(1))
+          .accounts(depositAccounts())
+          .rpc();
+        assert.fail("Should have thrown minimum liquidity error");
+      } catch (error) {
+        assert.include(error.toString(), "LiquidityLessThanMinimum");
+      }
+    });
+
+    it("Makes the donation attack unprofitable", async () => {
+      // Attacker mints the smallest possible share: sqrt(1001 * 1001) - 1000 = 1 LP
+      await program.methods
+        .deposit(new BN(1), new BN(1001), new BN(1001))
+        .accounts(depositAccounts())
+        .rpc();
+
+      const attackerLp = await getAccount(connection, attackUserLp);
+      assert.equal(attackerLp.amount.toString(), "1");
+
+      // ...then donates straight into the vaults to inflate the share price
+      const donation = 10_000000;
+      await transfer(
+        connection,
+        wallet.payer,
+        userXAccount,
+        attackVaultX,
+        wallet.publicKey,
+        donation,
+      );
+      await transfer(
+        connection,
+        wallet.payer,
+        userYAccount,
+        attackVaultY,
+        wallet.publicKey,
+        donation,
+      );
+
+      // The locked liquidity captures almost all of the donation, so the
+      // attacker's single LP token is worth a fraction of what was donated
+      const vaultXAccount = await getAccount(connection, attackVaultX);
+      const supply = 1001;
+      const attackerShareX = Number(vaultXAccount.amount) / supply;
+      assert(attackerShareX < donation / 100);
+    });
+
+    it("Should fail to close a pool that still has liquidity", async () => {
+      try {
+        await program.methods
+          .closePool()
+          .accounts(closePoolAccounts())
+          .rpc();
+        assert.fail("Should have thrown pool not empty error");
+      } catch (error) {
+        assert.include(error.toString(), "PoolNotEmpty");
+      }
+    });
+
+    it("Should fail to close a pool the donation is locked in", async () => {
+      await program.methods
+        .withdraw(new BN(1), new BN(1), new BN(1))
+        .accounts(depositAccounts())
+        .rpc();
+
+      // The locked liquidity keeps the donation, that's more than dust
+      try {
+        await program.methods
+          .closePool()
+          .accounts(closePoolAccounts())
+          .rpc();
+        assert.fail("Should have thrown pool not empty error");
+      } catch (error) {
+        assert.include(error.toString(), "PoolNotEmpty");
+      }
+    });
+
+    it("Closes a drained pool", async () => {
+      // A fresh pool on the pair's 5 bps tier, its only LP exits in full
+      const seed = new BN(Math.floor(Math.random() * 1000000) + 4000000);
+      const [drainedConfig] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config"), seed.toArrayLike(Buffer, "le", 8)],
+        program.programId,
+      );
+      const [drainedMintLp] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("lp"), drainedConfig.toBuffer()],
+        program.programId,
+      );
+      const drained = {
+        config: drainedConfig,
+        mintLp: drainedMintLp,
+        vaultX: await getAssociatedTokenAddress(mintX, drainedConfig, true),
+        vaultY: await getAssociatedTokenAddress(mintY, drainedConfig, true),
+        configLp: await getAssociatedTokenAddress(
+          drainedMintLp,
+          drainedConfig,
+          true,
+        ),
+      };
+      const drainedEntry = poolEntry(mintX, mintY, 5);
+
+      await program.methods
+        .initialize(seed, 5, null, constantProduct, new BN(0))
+        .accounts({
+          initializer: wallet.publicKey,
+          mintX,
+          mintY,
+          registry,
+          poolEntry: drainedEntry,
+          config: drained.config,
+          mintLp: drained.mintLp,
+          vaultX: drained.vaultX,
+          vaultY: drained.vaultY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const liquidityAccounts = {
+        ...depositAccounts(),
+        ...drained,
+        userLp: await getAssociatedTokenAddress(
+          drainedMintLp,
+          wallet.publicKey,
+        ),
+      };
+      await program.methods
+        .deposit(new BN(1), new BN(1001), new BN(1001))
+        .accounts(liquidityAccounts)
+        .rpc();
+      await program.methods
+        .withdraw(new BN(1), new BN(1), new BN(1))
+        .accounts(liquidityAccounts)
+        .rpc();
+
+      // The initializer closes it since the pool has no authority, the dust
+      // behind the locked liquidity goes to the registry admin
+      await program.methods
+        .closePool()
+        .accounts({
+          ...closePoolAccounts(),
+          ...drained,
+          poolEntry: drainedEntry,
+        })
+        .rpc();
+
+      // The StableSwap pool below takes the freed tier
+      for (const address of [
+        drained.config,
+        drained.vaultX,
+        drained.vaultY,
+        drainedEntry,
+      ]) {
+        assert.isNull(await connection.getAccountInfo(address));
+      }
+      assert(
+        (await connection.getBalance(receiver.publicKey)) > 0,
+        "Receiver should get the rent back",
+      );
+    });
+  });
+
   describe("Subsequent Deposit", () => {
     it("Subsequent deposits", async () => {
       const depositAmount = new BN(50_000000);
@@ -184,7 +669,10 @@ describe("Anchor AMM", () => {
           userX: userXAccount,
           userY: userYAccount,
           userLp,
+          configLp,
           tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -212,6 +700,8 @@ describe("Anchor AMM", () => {
           mintY,
           config,
           tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           mintLp,
@@ -247,6 +737,8 @@ describe("Anchor AMM", () => {
           mintY,
           config,
           tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           mintLp,
@@ -266,27 +758,24 @@ describe("Anchor AMM", () => {
       assert.equal(yDiff, Number(swapAmount));
       assert(xDiff > Number(minAmountOut));
     });
-  });
-
 
-  describe("Withdraw Liquidity", () => {
-    it("Should withdraw partial liquidity", async () => {
-      const withdrawAmount = new BN(50_000000);
-      const minX = new BN(30_000000);
-      const minY = new BN(30_000000);
+    it("Swapping Token X for an exact amount of Token Y", async () => {
+      const amountOut = new BN(5_000000);
+      const maxAmountIn = new BN(6_000000);
 
-      const userLpBefore = await getAccount(connection, userLp);
       const userXBefore = await getAccount(connection, userXAccount);
       const userYBefore = await getAccount(connection, userYAccount);
 
       await program.methods
-        .withdraw(withdrawAmount, minX, minY)
+        .swapExactOut(true, amountOut, maxAmountIn)
         .accounts({
           user: wallet.publicKey,
           mintX,
           mintY,
           config,
           tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           mintLp,
@@ -298,35 +787,28 @@ describe("Anchor AMM", () => {
         })
         .rpc();
 
-      const userLpAfter = await getAccount(connection, userLp);
-      const lpBurned = Number(userLpBefore.amount) - Number(userLpAfter.amount);
-      
       const userXAfter = await getAccount(connection, userXAccount);
       const userYAfter = await getAccount(connection, userYAccount);
-      
-      const xReceived = Number(userXAfter.amount) - Number(userXBefore.amount);
-      const yReceived = Number(userYAfter.amount) - Number(userYBefore.amount);
-      
-      assert.equal(lpBurned, Number(withdrawAmount));
-      assert(xReceived > Number(minX));
-      assert(yReceived > Number(minY));
-    });
-  });
+      const xDiff = Number(userXBefore.amount) - Number(userXAfter.amount);
+      const yDiff = Number(userYAfter.amount) - Number(userYBefore.amount);
 
-  describe("Error Cases", () => {
-    it("Should fail when swapping with insufficient slippage tolerance", async () => {
-      const swapAmount = new BN(50_000000);
-      const minAmountOut = new BN(1_000_000_000000);
+      assert.equal(yDiff, Number(amountOut));
+      assert(xDiff > Number(amountOut));
+      assert(xDiff <= Number(maxAmountIn));
+    });
 
+    it("Should fail an exact out swap above max amount in", async () => {
       try {
         await program.methods
-          .swap(true, swapAmount, minAmountOut)
+          .swapExactOut(false, new BN(5_000000), new BN(5_000000))
           .accounts({
             user: wallet.publicKey,
             mintX,
             mintY,
             config,
             tokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgramX: TOKEN_PROGRAM_ID,
+            tokenProgramY: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
             mintLp,
@@ -338,58 +820,2186 @@ describe("Anchor AMM", () => {
           })
           .rpc();
         assert.fail("Should have thrown slippage error");
-      } catch (error) {}
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
     });
+  });
 
-    it("Should fail on depositing zero amount", async () => {
+
+  describe("Events", () => {
+    it("Emits a swap event with the post-trade reserves", async () => {
+      const swapAmount = new BN(1_000000);
+
+      const signature = await program.methods
+        .swap(true, swapAmount, new BN(1))
+        .accounts({
+          user: wallet.publicKey,
+          mintX,
+          mintY,
+          config,
+          mintLp,
+          vaultX,
+          vaultY,
+          userX: userXAccount,
+          userY: userYAccount,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ commitment: "confirmed" });
+
+      const events = await emittedEvents(signature);
+      assert.equal(events.length, 1);
+      assert.equal(events[0].name, "swapEvent");
+
+      const event = events[0].data;
+      assert.equal(event.pool.toBase58(), config.toBase58());
+      assert.equal(event.user.toBase58(), wallet.publicKey.toBase58());
+      assert.equal(event.isX, true);
+      assert.equal(event.amountIn.toString(), swapAmount.toString());
+      // 0.3% of the input
+      assert.equal(event.fee.toNumber(), 3000);
+      assert.equal(event.feeBps, fee);
+
+      const vaultXAccount = await getAccount(connection, vaultX);
+      const vaultYAccount = await getAccount(connection, vaultY);
+      assert.equal(event.reserveX.toString(), vaultXAccount.amount.toString());
+      assert.equal(event.reserveY.toString(), vaultYAccount.amount.toString());
+    });
+  });
+
+  describe("Flash Loans", () => {
+    const loanAmount = new BN(1_000000);
+    const flashAccounts = () => ({
+      user: wallet.publicKey,
+      mintX,
+      mintY,
+      config,
+      vaultX,
+      vaultY,
+      userX: userXAccount,
+      userY: userYAccount,
+      instructionSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+    const repayInstruction = () =>
+      program.methods.flashRepay().accounts(flashAccounts()).instruction();
+
+    it("Should fail to borrow without repaying", async () => {
       try {
         await program.methods
-          .deposit(new BN(0), new BN(100), new BN(100))
-          .accounts({
-            user: wallet.publicKey,
-            mintX,
-            mintY,
-            config,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-            systemProgram: SystemProgram.programId,
-            mintLp,
-            vaultX,
-            vaultY,
-            userX: userXAccount,
-            userY: userYAccount,
-            userLp,
-          })
+          .flashBorrow(true, loanAmount)
+          .accounts(flashAccounts())
           .rpc();
-        assert.fail("Should have thrown invalid amount error");
-      } catch (error) {}
+        assert.fail("Should have thrown InvalidFlashLoan error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFlashLoan");
+      }
     });
 
-    it("Should fail when withdrawing LP tokens", async () => {
-      const userLpAccount = await getAccount(connection, userLp);
-      const LpAmount_exceed = new BN(Number(userLpAccount.amount) + 1000000);
+    it("Should fail to swap before the loan is repaid", async () => {
+      const swapInstruction = await program.methods
+        .swap(false, new BN(1_000), new BN(1))
+        .accounts({
+          user: wallet.publicKey,
+          mintX,
+          mintY,
+          config,
+          mintLp,
+          vaultX,
+          vaultY,
+          userX: userXAccount,
+          userY: userYAccount,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
 
       try {
         await program.methods
-          .withdraw(LpAmount_exceed, new BN(0), new BN(0))
-          .accounts({
-            user: wallet.publicKey,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-            systemProgram: SystemProgram.programId,
-            mintX,
-            mintY,
-            config,
-            mintLp,
-            vaultX,
-            vaultY,
-            userX: userXAccount,
-            userY: userYAccount,
-            userLp,
-          })
+          .flashBorrow(true, loanAmount)
+          .accounts(flashAccounts())
+          .postInstructions([swapInstruction, await repayInstruction()])
           .rpc();
-        assert.fail("Insufficient balance error");
-      } catch (error) {}
+        assert.fail("Should have thrown FlashLoanInProgress error");
+      } catch (error) {
+        assert.include(error.toString(), "FlashLoanInProgress");
+      }
+    });
+
+    it("Borrows and repays with the fee in one transaction", async () => {
+      const vaultXBefore = (await getAccount(connection, vaultX)).amount;
+      const userXBefore = (await getAccount(connection, userXAccount)).amount;
+
+      await program.methods
+        .flashBorrow(true, loanAmount)
+        .accounts(flashAccounts())
+        .postInstructions([await repayInstruction()])
+        .rpc();
+
+      // 0.3% of the loan stays in the vault for the LPs
+      const vaultXAfter = (await getAccount(connection, vaultX)).amount;
+      const userXAfter = (await getAccount(connection, userXAccount)).amount;
+      assert.equal(vaultXAfter - vaultXBefore, BigInt(3000));
+      assert.equal(userXBefore - userXAfter, BigInt(3000));
+
+      const configAccount = await program.account.config.fetch(config);
+      assert.equal(configAccount.flashLoan.toNumber(), 0);
+    });
+  });
+
+  describe("Farming", () => {
+    let farm: anchor.web3.PublicKey;
+    let farmLp: anchor.web3.PublicKey;
+    let stake: anchor.web3.PublicKey;
+    let rewardMint: anchor.web3.PublicKey;
+    let rewardVault: anchor.web3.PublicKey;
+    let userReward: anchor.web3.PublicKey;
+
+    const emissionsPerSecond = new BN(1_000);
+    const duration = 1_000;
+    const stakeAmount = new BN(1_000000);
+
+    const now = async () =>
+      await connection.getBlockTime(await connection.getSlot());
+
+    const stakeAccounts = () => ({
+      user: wallet.publicKey,
+      farm,
+      mintLp,
+      farmLp,
+      userLp,
+      stake,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    const fundAccounts = (mint: anchor.web3.PublicKey) => ({
+      authority: wallet.publicKey,
+      config,
+      farm,
+      rewardMint: mint,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      [farm] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("farm"), config.toBuffer()],
+        program.programId,
+      );
+      [stake] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("stake"), farm.toBuffer(), wallet.publicKey.toBuffer()],
+        program.programId,
+      );
+      farmLp = await getAssociatedTokenAddress(mintLp, farm, true);
+
+      rewardMint = await createMint(
+        connection,
+        wallet.payer,
+        wallet.publicKey,
+        null,
+        6,
+      );
+      rewardVault = await getAssociatedTokenAddress(rewardMint, farm, true);
+      userReward = (
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          wallet.payer,
+          rewardMint,
+          wallet.publicKey,
+        )
+      ).address;
+      await mintTo(
+        connection,
+        wallet.payer,
+        rewardMint,
+        userReward,
+        wallet.publicKey,
+        initialMintAmount,
+      );
+    });
+
+    it("Initializes a farm for the pool", async () => {
+      await program.methods
+        .initializeFarm()
+        .accounts({
+          authority: wallet.publicKey,
+          config,
+          mintLp,
+          farm,
+          farmLp,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const farmAccount = await program.account.farm.fetch(farm);
+      assert.equal(farmAccount.config.toBase58(), config.toBase58());
+      assert.equal(farmAccount.mintLp.toBase58(), mintLp.toBase58());
+      assert.equal(farmAccount.totalStaked.toNumber(), 0);
+    });
+
+    it("Should fail to fund the LP mint as a reward", async () => {
+      const start = (await now()) + 2;
+      try {
+        await program.methods
+          .fundReward(
+            emissionsPerSecond,
+            new BN(start),
+            new BN(start + duration),
+          )
+          .accounts({
+            ...fundAccounts(mintLp),
+            rewardVault: farmLp,
+            authorityReward: userLp,
+          })
+          .rpc();
+        assert.fail("Should have thrown InvalidToken error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidToken");
+      }
+    });
+
+    it("Funds a reward schedule", async () => {
+      // Leaves the validator's clock time to catch up
+      const start = (await now()) + 2;
+
+      await program.methods
+        .fundReward(
+          emissionsPerSecond,
+          new BN(start),
+          new BN(start + duration),
+        )
+        .accounts({
+          ...fundAccounts(rewardMint),
+          rewardVault,
+          authorityReward: userReward,
+        })
+        .rpc();
+
+      const vault = await getAccount(connection, rewardVault);
+      assert.equal(
+        vault.amount.toString(),
+        emissionsPerSecond.muln(duration).toString(),
+      );
+
+      const farmAccount = await program.account.farm.fetch(farm);
+      const reward = farmAccount.rewards[0];
+      assert.equal(reward.mint.toBase58(), rewardMint.toBase58());
+      assert.equal(reward.emissionsPerSecond.toString(), "1000");
+      assert.equal(reward.endTs.toNumber(), start + duration);
+    });
+
+    it("Stakes LP tokens", async () => {
+      const lpBefore = (await getAccount(connection, userLp)).amount;
+
+      await program.methods
+        .stakeLp(stakeAmount)
+        .accounts(stakeAccounts())
+        .rpc();
+
+      const lpAfter = (await getAccount(connection, userLp)).amount;
+      assert.equal((lpBefore - lpAfter).toString(), stakeAmount.toString());
+
+      const farmAccount = await program.account.farm.fetch(farm);
+      assert.equal(farmAccount.totalStaked.toString(), stakeAmount.toString());
+      const stakeAccount = await program.account.stake.fetch(stake);
+      assert.equal(stakeAccount.amount.toString(), stakeAmount.toString());
+    });
+
+    it("Harvests the rewards", async () => {
+      // Let some emissions accrue
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      const before = (await getAccount(connection, userReward)).amount;
+
+      await program.methods
+        .harvest()
+        .accounts({
+          user: wallet.publicKey,
+          farm,
+          stake,
+          rewardMint,
+          rewardVault,
+          userReward,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // Alone in the farm, the stake earns every emission since it was made
+      const after = (await getAccount(connection, userReward)).amount;
+      assert.isTrue(after > before);
+
+      const stakeAccount = await program.account.stake.fetch(stake);
+      assert.equal(stakeAccount.rewardsOwed[0].toNumber(), 0);
+    });
+
+    it("Should fail to unstake more than was staked", async () => {
+      try {
+        await program.methods
+          .unstakeLp(stakeAmount.addn(1))
+          .accounts(stakeAccounts())
+          .rpc();
+        assert.fail("Should have thrown InsufficientBalance error");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientBalance");
+      }
+    });
+
+    it("Unstakes the LP tokens", async () => {
+      const lpBefore = (await getAccount(connection, userLp)).amount;
+
+      await program.methods
+        .unstakeLp(stakeAmount)
+        .accounts(stakeAccounts())
+        .rpc();
+
+      const lpAfter = (await getAccount(connection, userLp)).amount;
+      assert.equal((lpAfter - lpBefore).toString(), stakeAmount.toString());
+
+      const farmAccount = await program.account.farm.fetch(farm);
+      assert.equal(farmAccount.totalStaked.toNumber(), 0);
+    });
+
+    it("Should fail to reclaim rewards before the schedule ends", async () => {
+      try {
+        await program.methods
+          .reclaimRewards()
+          .accounts({
+            authority: wallet.publicKey,
+            config,
+            farm,
+            rewardMint,
+            rewardVault,
+            authorityReward: userReward,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+        assert.fail("Should have thrown InvalidRewardSchedule error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidRewardSchedule");
+      }
+    });
+  });
+
+  describe("Limit Orders", () => {
+    const amount = new BN(1_000000);
+    const keeperReward = new BN(5_000);
+    let minAmountOut: BN;
+
+    const orderAddress = (id: number) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("order"),
+          config.toBuffer(),
+          wallet.publicKey.toBuffer(),
+          new BN(id).toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId,
+      )[0];
+
+    const placeOrder = async (id: number) => {
+      const order = orderAddress(id);
+      await program.methods
+        .placeLimitOrder(new BN(id), amount, minAmountOut, keeperReward)
+        .accounts({
+          owner: wallet.publicKey,
+          config,
+          mintIn: mintX,
+          ownerIn: userXAccount,
+          order,
+          escrow: await getAssociatedTokenAddress(mintX, order, true),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    const swap = async (isX: boolean, amountIn: BN) => {
+      await program.methods
+        .swap(isX, amountIn, new BN(1))
+        .accounts({
+          user: wallet.publicKey,
+          mintX,
+          mintY,
+          config,
+          mintLp,
+          vaultX,
+          vaultY,
+          userX: userXAccount,
+          userY: userYAccount,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    };
+
+    const crank = async (id: number) => {
+      const order = orderAddress(id);
+      await program.methods
+        .crankOrders()
+        .accounts({
+          keeper: wallet.publicKey,
+          mintX,
+          mintY,
+          config,
+          mintLp,
+          vaultX,
+          vaultY,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          [
+            order,
+            await getAssociatedTokenAddress(mintX, order, true),
+            userYAccount,
+            wallet.publicKey,
+          ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+        )
+        .rpc();
+    };
+
+    before(async () => {
+      // 5% above what 1 X is worth in the pool right now
+      const x = (await getAccount(connection, vaultX)).amount;
+      const y = (await getAccount(connection, vaultY)).amount;
+      const worth = (BigInt(amount.toString()) * y) / x;
+      minAmountOut = new BN(((worth * BigInt(105)) / BigInt(100)).toString());
+    });
+
+    it("Places a limit order", async () => {
+      const userXBefore = (await getAccount(connection, userXAccount)).amount;
+
+      await placeOrder(1);
+
+      const userXAfter = (await getAccount(connection, userXAccount)).amount;
+      assert.equal((userXBefore - userXAfter).toString(), amount.toString());
+
+      const order = orderAddress(1);
+      const escrow = await getAssociatedTokenAddress(mintX, order, true);
+      const escrowAccount = await getAccount(connection, escrow);
+      assert.equal(escrowAccount.amount.toString(), amount.toString());
+
+      const orderAccount = await program.account.order.fetch(order);
+      assert.equal(orderAccount.mintIn.toBase58(), mintX.toBase58());
+      assert.equal(
+        orderAccount.minAmountOut.toString(),
+        minAmountOut.toString(),
+      );
+    });
+
+    it("Should fail to crank before the price crosses", async () => {
+      try {
+        await crank(1);
+        assert.fail("Should have thrown NoOrderFilled error");
+      } catch (error) {
+        assert.include(error.toString(), "NoOrderFilled");
+      }
+    });
+
+    it("Cancels an order and gets the escrow back", async () => {
+      await placeOrder(2);
+      const order = orderAddress(2);
+      const userXBefore = (await getAccount(connection, userXAccount)).amount;
+
+      await program.methods
+        .cancelOrder()
+        .accounts({
+          owner: wallet.publicKey,
+          order,
+          mintIn: mintX,
+          escrow: await getAssociatedTokenAddress(mintX, order, true),
+          ownerIn: userXAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const userXAfter = (await getAccount(connection, userXAccount)).amount;
+      assert.equal((userXAfter - userXBefore).toString(), amount.toString());
+      assert.isNull(await connection.getAccountInfo(order));
+    });
+
+    it("Fills the order once the price crosses", async () => {
+      // Buying X pushes its price well past the limit
+      const y = (await getAccount(connection, vaultY)).amount;
+      const userXBefore = (await getAccount(connection, userXAccount)).amount;
+      await swap(false, new BN((y / BigInt(5)).toString()));
+      const userXAfter = (await getAccount(connection, userXAccount)).amount;
+      const bought = userXAfter - userXBefore;
+      const userYBefore = (await getAccount(connection, userYAccount)).amount;
+
+      await crank(1);
+
+      const userYAfter = (await getAccount(connection, userYAccount)).amount;
+      const received = userYAfter - userYBefore;
+      assert.isTrue(received >= BigInt(minAmountOut.toString()));
+      assert.isNull(await connection.getAccountInfo(orderAddress(1)));
+
+      // Sells the X back so the tests below see a balanced pool
+      await swap(true, new BN(bought.toString()));
+    });
+  });
+
+  // Smoke test against a live validator, the invariants are fuzzed properly
+  // by the proptest suite in amm-client/tests/constant_product.rs
+  describe("Swap Invariant", () => {
+    // Deterministic PRNG (mulberry32) so failures are reproducible
+    const prng = (seed: number) => () => {
+      seed = (seed + 0x6d2b79f5) | 0;
+      let t = Math.imul(seed ^ (seed >>> 15), 1 | seed);
+      t = (t + Math.imul(t ^ (t >>> 7), 61 | t)) ^ t;
+      return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+    };
+
+    const k = async () => {
+      const x = (await getAccount(connection, vaultX)).amount;
+      const y = (await getAccount(connection, vaultY)).amount;
+      return x * y;
+    };
+
+    it("k never decreases across random swap sequences", async () => {
+      const random = prng(42);
+      let kBefore = await k();
+
+      for (let i = 0; i < 20; i++) {
+        const isX = random() < 0.5;
+        const amount = new BN(Math.floor(random() * 5_000000) + 1);
+
+        await program.methods
+          .swap(isX, amount, new BN(1))
+          .accounts({
+            user: wallet.publicKey,
+            mintX,
+            mintY,
+            config,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgramX: TOKEN_PROGRAM_ID,
+            tokenProgramY: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            mintLp,
+            vaultX,
+            vaultY,
+            userX: userXAccount,
+            userY: userYAccount,
+            userLp,
+          })
+          .rpc();
+
+        const kAfter = await k();
+        assert(kAfter >= kBefore, `k decreased on swap ${i}`);
+        kBefore = kAfter;
+      }
+    });
+  });
+
+  describe("Price Oracle", () => {
+    const observeAccounts = () => ({ config });
+
+    it("Returns the TWAP over the requested window", async () => {
+      // Let some time pass so the accumulators have something to weigh
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      await program.methods
+        .swap(true, new BN(1_000000), new BN(1))
+        .accounts({
+          user: wallet.publicKey,
+          mintX,
+          mintY,
+          config,
+          mintLp,
+          vaultX,
+          vaultY,
+          userX: userXAccount,
+          userY: userYAccount,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(config);
+      assert(configAccount.priceXCumulative.gt(new BN(0)));
+      assert(configAccount.priceYCumulative.gt(new BN(0)));
+
+      // The swap booked the reserves the next update will accumulate on
+      const vaultXAccount = await getAccount(connection, vaultX);
+      assert.equal(
+        configAccount.reserveX.add(configAccount.protocolFeesX).toString(),
+        vaultXAccount.amount.toString(),
+      );
+
+      const twap = await program.methods
+        .observe(new BN(1))
+        .accounts(observeAccounts())
+        .view();
+
+      // The pool was seeded 1:1 and swaps only nudged it, so both Q64.64
+      // prices stay close to 1 << 64
+      const one = new BN(1).shln(64);
+      assert(twap.priceX.gt(one.divn(2)) && twap.priceX.lt(one.muln(2)));
+      assert(twap.priceY.gt(one.divn(2)) && twap.priceY.lt(one.muln(2)));
+    });
+
+    it("Should fail for a window older than every observation", async () => {
+      try {
+        await program.methods
+          .observe(new BN(365 * 24 * 60 * 60))
+          .accounts(observeAccounts())
+          .view();
+        assert.fail("Should have thrown observation not found error");
+      } catch (error) {
+        assert.include(error.toString(), "ObservationNotFound");
+      }
+    });
+  });
+
+  describe("Route Swap", () => {
+    const routeSeed = new BN(Math.floor(Math.random() * 1000000) + 2000000);
+    let mintZ: anchor.web3.PublicKey;
+    let userZAccount: anchor.web3.PublicKey;
+    let routeConfig: anchor.web3.PublicKey;
+    let routeMintLp: anchor.web3.PublicKey;
+    let routeVaultY: anchor.web3.PublicKey;
+    let routeVaultZ: anchor.web3.PublicKey;
+
+    const pda = (...seeds: Buffer[]) =>
+      anchor.web3.PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+    // config, oracle, mint_x, mint_y, mint_lp, vault_x, vault_y, user_x, user_y
+    const hop = (
+      pool: anchor.web3.PublicKey,
+      poolMintX: anchor.web3.PublicKey,
+      poolMintY: anchor.web3.PublicKey,
+      poolMintLp: anchor.web3.PublicKey,
+      poolVaultX: anchor.web3.PublicKey,
+      poolVaultY: anchor.web3.PublicKey,
+      userX: anchor.web3.PublicKey,
+      userY: anchor.web3.PublicKey,
+    ) =>
+      [
+        [pool, true],
+        [pda(Buffer.from("oracle"), pool.toBuffer()), true],
+        [poolMintX, false],
+        [poolMintY, false],
+        [poolMintLp, false],
+        [poolVaultX, true],
+        [poolVaultY, true],
+        [userX, true],
+        [userY, true],
+      ].map(([pubkey, isWritable]) => ({
+        pubkey: pubkey as anchor.web3.PublicKey,
+        isSigner: false,
+        isWritable: isWritable as boolean,
+      }));
+
+    const route = () => [
+      ...hop(
+        config,
+        mintX,
+        mintY,
+        mintLp,
+        vaultX,
+        vaultY,
+        userXAccount,
+        userYAccount,
+      ),
+      ...hop(
+        routeConfig,
+        mintY,
+        mintZ,
+        routeMintLp,
+        routeVaultY,
+        routeVaultZ,
+        userYAccount,
+        userZAccount,
+      ),
+    ];
+
+    const routeAccounts = () => ({
+      user: wallet.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgram2022: TOKEN_2022_PROGRAM_ID,
+    });
+
+    before(async () => {
+      mintZ = await createMint(
+        connection,
+        wallet.payer,
+        wallet.publicKey,
+        null,
+        6,
+        keypairAfter(mintY),
+      );
+      userZAccount = (
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          wallet.payer,
+          mintZ,
+          wallet.publicKey,
+        )
+      ).address;
+      await mintTo(
+        connection,
+        wallet.payer,
+        mintZ,
+        userZAccount,
+        wallet.publicKey,
+        initialMintAmount,
+      );
+
+      routeConfig = pda(
+        Buffer.from("config"),
+        routeSeed.toArrayLike(Buffer, "le", 8),
+      );
+      routeMintLp = pda(Buffer.from("lp"), routeConfig.toBuffer());
+      routeVaultY = await getAssociatedTokenAddress(mintY, routeConfig, true);
+      routeVaultZ = await getAssociatedTokenAddress(mintZ, routeConfig, true);
+
+      await program.methods
+        .initialize(routeSeed, fee, null, constantProduct, new BN(0))
+        .accounts({
+          initializer: wallet.publicKey,
+          mintX: mintY,
+          mintY: mintZ,
+          registry,
+          poolEntry: poolEntry(mintY, mintZ, fee),
+          config: routeConfig,
+          mintLp: routeMintLp,
+          vaultX: routeVaultY,
+          vaultY: routeVaultZ,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .deposit(new BN(1), new BN(50_000000), new BN(50_000000))
+        .accounts({
+          user: wallet.publicKey,
+          mintX: mintY,
+          mintY: mintZ,
+          config: routeConfig,
+          mintLp: routeMintLp,
+          vaultX: routeVaultY,
+          vaultY: routeVaultZ,
+          userX: userYAccount,
+          userY: userZAccount,
+          userLp: await getAssociatedTokenAddress(
+            routeMintLp,
+            wallet.publicKey,
+          ),
+          configLp: await getAssociatedTokenAddress(
+            routeMintLp,
+            routeConfig,
+            true,
+          ),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it("Swaps X for Z through the X/Y and Y/Z pools", async () => {
+      const amountIn = 1_000000;
+      const userXBefore = await getAccount(connection, userXAccount);
+      const userYBefore = await getAccount(connection, userYAccount);
+      const userZBefore = await getAccount(connection, userZAccount);
+
+      await program.methods
+        .routeSwap([true, true], new BN(amountIn), new BN(1))
+        .accounts(routeAccounts())
+        .remainingAccounts(route())
+        .rpc();
+
+      const userXAfter = await getAccount(connection, userXAccount);
+      const userYAfter = await getAccount(connection, userYAccount);
+      const userZAfter = await getAccount(connection, userZAccount);
+
+      assert.equal(
+        Number(userXBefore.amount) - Number(userXAfter.amount),
+        amountIn,
+      );
+      // Everything the first hop paid out in Y went into the second one
+      assert.equal(Number(userYAfter.amount), Number(userYBefore.amount));
+      // Two 0.3% fees and some price impact on both pools
+      const received = Number(userZAfter.amount) - Number(userZBefore.amount);
+      assert(received > 0 && received < amountIn);
+    });
+
+    it("Should fail below the end-to-end minimum", async () => {
+      try {
+        await program.methods
+          .routeSwap([true, true], new BN(1_000000), new BN(1_000000))
+          .accounts(routeAccounts())
+          .remainingAccounts(route())
+          .rpc();
+        assert.fail("Should have thrown slippage exceeded error");
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
+    });
+
+    it("Should fail when a hop doesn't spend the previous output", async () => {
+      try {
+        // The second hop would spend Z while the first one pays out Y
+        await program.methods
+          .routeSwap([true, false], new BN(1_000000), new BN(1))
+          .accounts(routeAccounts())
+          .remainingAccounts(route())
+          .rpc();
+        assert.fail("Should have thrown invalid route error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidRoute");
+      }
+    });
+
+    it("Should fail on a config that isn't a pool PDA", async () => {
+      const accounts = route();
+      // Pass the LP mint where the second hop's config goes
+      accounts[9] = { ...accounts[9], pubkey: routeMintLp };
+
+      try {
+        await program.methods
+          .routeSwap([true, true], new BN(1_000000), new BN(1))
+          .accounts(routeAccounts())
+          .remainingAccounts(accounts)
+          .rpc();
+        assert.fail("Should have rejected the config account");
+      } catch (error) {
+        assert.include(error.toString(), "AccountOwnedByWrongProgram");
+      }
+    });
+  });
+
+  describe("Withdraw Liquidity", () => {
+    it("Should withdraw partial liquidity", async () => {
+      const withdrawAmount = new BN(50_000000);
+      const minX = new BN(30_000000);
+      const minY = new BN(30_000000);
+
+      const userLpBefore = await getAccount(connection, userLp);
+      const userXBefore = await getAccount(connection, userXAccount);
+      const userYBefore = await getAccount(connection, userYAccount);
+
+      await program.methods
+        .withdraw(withdrawAmount, minX, minY)
+        .accounts({
+          user: wallet.publicKey,
+          mintX,
+          mintY,
+          config,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          mintLp,
+          vaultX,
+          vaultY,
+          userX: userXAccount,
+          userY: userYAccount,
+          userLp,
+        })
+        .rpc();
+
+      const userLpAfter = await getAccount(connection, userLp);
+      const lpBurned = Number(userLpBefore.amount) - Number(userLpAfter.amount);
+      
+      const userXAfter = await getAccount(connection, userXAccount);
+      const userYAfter = await getAccount(connection, userYAccount);
+      
+      const xReceived = Number(userXAfter.amount) - Number(userXBefore.amount);
+      const yReceived = Number(userYAfter.amount) - Number(userYBefore.amount);
+      
+      assert.equal(lpBurned, Number(withdrawAmount));
+      assert(xReceived > Number(minX));
+      assert(yReceived > Number(minY));
+    });
+  });
+
+  describe("Single-Sided Liquidity", () => {
+    const liquidityAccounts = () => ({
+      user: wallet.publicKey,
+      mintX,
+      mintY,
+      config,
+      mintLp,
+      vaultX,
+      vaultY,
+      userX: userXAccount,
+      userY: userYAccount,
+      userLp,
+      configLp,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    it("Deposits only token X", async () => {
+      const amountIn = 2_000000;
+      const userXBefore = await getAccount(connection, userXAccount);
+      const userYBefore = await getAccount(connection, userYAccount);
+      const userLpBefore = await getAccount(connection, userLp);
+
+      await program.methods
+        .depositSingle(true, new BN(amountIn), new BN(1))
+        .accounts(liquidityAccounts())
+        .rpc();
+
+      const userXAfter = await getAccount(connection, userXAccount);
+      const userYAfter = await getAccount(connection, userYAccount);
+      const userLpAfter = await getAccount(connection, userLp);
+
+      assert.equal(
+        Number(userXBefore.amount) - Number(userXAfter.amount),
+        amountIn,
+      );
+      assert.equal(Number(userYAfter.amount), Number(userYBefore.amount));
+      assert(userLpAfter.amount > userLpBefore.amount);
+    });
+
+    it("Should fail a single-sided deposit below min LP out", async () => {
+      try {
+        await program.methods
+          .depositSingle(true, new BN(1_000000), new BN(1_000000))
+          .accounts(liquidityAccounts())
+          .rpc();
+        assert.fail("Should have thrown slippage exceeded error");
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
+    });
+
+    it("Withdraws only token Y", async () => {
+      const lpAmount = 1_000000;
+      const userXBefore = await getAccount(connection, userXAccount);
+      const userYBefore = await getAccount(connection, userYAccount);
+      const userLpBefore = await getAccount(connection, userLp);
+
+      await program.methods
+        .withdrawSingle(false, new BN(lpAmount), new BN(1))
+        .accounts(liquidityAccounts())
+        .rpc();
+
+      const userXAfter = await getAccount(connection, userXAccount);
+      const userYAfter = await getAccount(connection, userYAccount);
+      const userLpAfter = await getAccount(connection, userLp);
+
+      assert.equal(
+        Number(userLpBefore.amount) - Number(userLpAfter.amount),
+        lpAmount,
+      );
+      assert.equal(Number(userXAfter.amount), Number(userXBefore.amount));
+      assert(userYAfter.amount > userYBefore.amount);
+    });
+
+    it("Loses the swap fee on a single-sided round trip", async () => {
+      const amountIn = 1_000000;
+      const userXBefore = await getAccount(connection, userXAccount);
+      const userLpBefore = await getAccount(connection, userLp);
+
+      await program.methods
+        .depositSingle(true, new BN(amountIn), new BN(1))
+        .accounts(liquidityAccounts())
+        .rpc();
+
+      const userLpAfter = await getAccount(connection, userLp);
+      const lpMinted = new BN(
+        (userLpAfter.amount - userLpBefore.amount).toString(),
+      );
+
+      await program.methods
+        .withdrawSingle(true, lpMinted, new BN(1))
+        .accounts(liquidityAccounts())
+        .rpc();
+
+      const userXAfter = await getAccount(connection, userXAccount);
+      const lost = Number(userXBefore.amount) - Number(userXAfter.amount);
+      assert(lost > 0 && lost < amountIn / 100);
+    });
+
+    it("Should fail a single-sided withdraw below min out", async () => {
+      try {
+        await program.methods
+          .withdrawSingle(true, new BN(1_000000), new BN(100_000000))
+          .accounts(liquidityAccounts())
+          .rpc();
+        assert.fail("Should have thrown slippage exceeded error");
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
+    });
+  });
+
+  describe("Error Cases", () => {
+    it("Should fail when swapping with insufficient slippage tolerance", async () => {
+      const swapAmount = new BN(50_000000);
+      const minAmountOut = new BN(1_000_000_000000);
+
+      try {
+        await program.methods
+          .swap(true, swapAmount, minAmountOut)
+          .accounts({
+            user: wallet.publicKey,
+            mintX,
+            mintY,
+            config,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgramX: TOKEN_PROGRAM_ID,
+            tokenProgramY: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            mintLp,
+            vaultX,
+            vaultY,
+            userX: userXAccount,
+            userY: userYAccount,
+            userLp,
+          })
+          .rpc();
+        assert.fail("Should have thrown slippage error");
+      } catch (error) {}
+    });
+
+    it("Should fail on depositing zero amount", async () => {
+      try {
+        await program.methods
+          .deposit(new BN(0), new BN(100), new BN(100))
+          .accounts({
+            user: wallet.publicKey,
+            mintX,
+            mintY,
+            config,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgramX: TOKEN_PROGRAM_ID,
+            tokenProgramY: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            mintLp,
+            vaultX,
+            vaultY,
+            userX: userXAccount,
+            userY: userYAccount,
+            userLp,
+            configLp,
+          })
+          .rpc();
+        assert.fail("Should have thrown invalid amount error");
+      } catch (error) {}
+    });
+
+    it("Should fail when withdrawing LP tokens", async () => {
+      const userLpAccount = await getAccount(connection, userLp);
+      const LpAmount_exceed = new BN(Number(userLpAccount.amount) + 1000000);
+
+      try {
+        await program.methods
+          .withdraw(LpAmount_exceed, new BN(0), new BN(0))
+          .accounts({
+            user: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgramX: TOKEN_PROGRAM_ID,
+            tokenProgramY: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            mintX,
+            mintY,
+            config,
+            mintLp,
+            vaultX,
+            vaultY,
+            userX: userXAccount,
+            userY: userYAccount,
+            userLp,
+          })
+          .rpc();
+        assert.fail("Insufficient balance error");
+      } catch (error) {}
+    });
+  });
+
+  describe("Token-2022", () => {
+    const seed2022 = new BN(Math.floor(Math.random() * 1000000) + 2000000);
+    const transferFeeBps = 100; // 1%
+    let mint2022: anchor.web3.PublicKey;
+    let config2022: anchor.web3.PublicKey;
+    let mintLp2022: anchor.web3.PublicKey;
+    let vaultX2022: anchor.web3.PublicKey;
+    let vaultY2022: anchor.web3.PublicKey;
+    let userY2022: anchor.web3.PublicKey;
+
+    // Classic mint X paired with a Token-2022 mint Y charging a transfer fee
+    const poolAccounts = () => ({
+      user: wallet.publicKey,
+      mintX,
+      mintY: mint2022,
+      config: config2022,
+      mintLp: mintLp2022,
+      vaultX: vaultX2022,
+      vaultY: vaultY2022,
+      userX: userXAccount,
+      userY: userY2022,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_2022_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    before(async () => {
+      const mintKeypair = keypairAfter(mintX);
+      mint2022 = mintKeypair.publicKey;
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      const lamports = await connection.getMinimumBalanceForRentExemption(
+        mintLen,
+      );
+
+      const tx = new anchor.web3.Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: wallet.publicKey,
+          newAccountPubkey: mint2022,
+          space: mintLen,
+          lamports,
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferFeeConfigInstruction(
+          mint2022,
+          wallet.publicKey,
+          wallet.publicKey,
+          transferFeeBps,
+          BigInt(1_000_000_000),
+          TOKEN_2022_PROGRAM_ID,
+        ),
+        createInitializeMintInstruction(
+          mint2022,
+          6,
+          wallet.publicKey,
+          null,
+          TOKEN_2022_PROGRAM_ID,
+        ),
+      );
+      await provider.sendAndConfirm(tx, [mintKeypair]);
+
+      userY2022 = (
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          wallet.payer,
+          mint2022,
+          wallet.publicKey,
+          false,
+          undefined,
+          undefined,
+          TOKEN_2022_PROGRAM_ID,
+        )
+      ).address;
+      await mintTo(
+        connection,
+        wallet.payer,
+        mint2022,
+        userY2022,
+        wallet.publicKey,
+        initialMintAmount,
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+
+      [config2022] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config"), seed2022.toArrayLike(Buffer, "le", 8)],
+        program.programId,
+      );
+      [mintLp2022] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("lp"), config2022.toBuffer()],
+        program.programId,
+      );
+      vaultX2022 = await getAssociatedTokenAddress(mintX, config2022, true);
+      vaultY2022 = await getAssociatedTokenAddress(
+        mint2022,
+        config2022,
+        true,
+        TOKEN_2022_PROGRAM_ID,
+      );
+    });
+
+    it("Initializes a mixed classic / Token-2022 pool", async () => {
+      await program.methods
+        .initialize(seed2022, fee, null, constantProduct, new BN(0))
+        .accounts({
+          ...poolAccounts(),
+          initializer: wallet.publicKey,
+          registry,
+          poolEntry: poolEntry(mintX, mint2022, fee),
+        })
+        .rpc();
+
+      const vaultY = await getAccount(
+        connection,
+        vaultY2022,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+      assert.equal(vaultY.mint.toBase58(), mint2022.toBase58());
+    });
+
+    it("Prices the first deposit on what the vault received", async () => {
+      const amount = 10_000000;
+
+      await program.methods
+        .deposit(new BN(1), new BN(amount), new BN(amount))
+        .accounts(poolAccounts())
+        .rpc();
+
+      const vaultY = await getAccount(
+        connection,
+        vaultY2022,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+      // 1% of the deposit is withheld by the transfer fee extension
+      assert.equal(Number(vaultY.amount), amount - amount / 100);
+    });
+
+    it("Quotes swaps on the amount received by the vault", async () => {
+      const amountIn = 1_000000;
+      const vaultXBefore = await getAccount(connection, vaultX2022);
+      const vaultYBefore = await getAccount(
+        connection,
+        vaultY2022,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+
+      await program.methods
+        .swap(false, new BN(amountIn), new BN(1))
+        .accounts(poolAccounts())
+        .rpc();
+
+      const vaultXAfter = await getAccount(connection, vaultX2022);
+      const vaultYAfter = await getAccount(
+        connection,
+        vaultY2022,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+      const received =
+        Number(vaultYAfter.amount) - Number(vaultYBefore.amount);
+      assert.equal(received, amountIn - amountIn / 100);
+
+      // k is computed on the received amount, so it never decreases
+      const kBefore = vaultXBefore.amount * vaultYBefore.amount;
+      const kAfter = vaultXAfter.amount * vaultYAfter.amount;
+      assert(kAfter >= kBefore);
+    });
+  });
+
+  describe("StableSwap", () => {
+    const stableSeed = new BN(Math.floor(Math.random() * 1000000) + 3000000);
+    const amp = new BN(100);
+    const stableFee = 5;
+    let stableConfig: anchor.web3.PublicKey;
+    let stableMintLp: anchor.web3.PublicKey;
+    let stableVaultX: anchor.web3.PublicKey;
+    let stableVaultY: anchor.web3.PublicKey;
+    let stableUserLp: anchor.web3.PublicKey;
+    let stableConfigLp: anchor.web3.PublicKey;
+
+    const stableAccounts = () => ({
+      user: wallet.publicKey,
+      initializer: wallet.publicKey,
+      mintX,
+      mintY,
+      registry,
+      poolEntry: poolEntry(mintX, mintY, stableFee),
+      config: stableConfig,
+      mintLp: stableMintLp,
+      vaultX: stableVaultX,
+      vaultY: stableVaultY,
+      userX: userXAccount,
+      userY: userYAccount,
+      userLp: stableUserLp,
+      configLp: stableConfigLp,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    const now = async () =>
+      await connection.getBlockTime(await connection.getSlot());
+
+    before(async () => {
+      [stableConfig] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config"), stableSeed.toArrayLike(Buffer, "le", 8)],
+        program.programId,
+      );
+      [stableMintLp] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("lp"), stableConfig.toBuffer()],
+        program.programId,
+      );
+      stableVaultX = await getAssociatedTokenAddress(mintX, stableConfig, true);
+      stableVaultY = await getAssociatedTokenAddress(mintY, stableConfig, true);
+      stableUserLp = await getAssociatedTokenAddress(
+        stableMintLp,
+        wallet.publicKey,
+      );
+      stableConfigLp = await getAssociatedTokenAddress(
+        stableMintLp,
+        stableConfig,
+        true,
+      );
+    });
+
+    it("Should reject an amp on a constant product pool", async () => {
+      try {
+        await program.methods
+          .initialize(stableSeed, stableFee, wallet.publicKey, constantProduct, amp)
+          .accounts(stableAccounts())
+          .rpc();
+        assert.fail("Should have thrown invalid amp error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidAmp");
+      }
+    });
+
+    it("Initializes a StableSwap pool", async () => {
+      await program.methods
+        .initialize(
+          stableSeed,
+          stableFee,
+          wallet.publicKey,
+          { stableSwap: {} },
+          amp,
+        )
+        .accounts(stableAccounts())
+        .rpc();
+
+      await program.methods
+        .deposit(new BN(1), new BN(100_000000), new BN(100_000000))
+        .accounts(stableAccounts())
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(stableConfig);
+      assert.deepEqual(configAccount.curveType, { stableSwap: {} });
+      assert.equal(configAccount.targetAmp.toNumber(), amp.toNumber());
+
+      // A balanced pool's D is x + y
+      const lp = await getAccount(connection, stableUserLp);
+      assert.equal(Number(lp.amount), 200_000000 - 1000);
+    });
+
+    it("Trades much closer to 1:1 than constant product", async () => {
+      const amountIn = 10_000000;
+      const userYBefore = await getAccount(connection, userYAccount);
+
+      await program.methods
+        .swap(true, new BN(amountIn), new BN(1))
+        .accounts(stableAccounts())
+        .rpc();
+
+      const userYAfter = await getAccount(connection, userYAccount);
+      const received = Number(userYAfter.amount) - Number(userYBefore.amount);
+
+      // A 30 bps constant product pool would pay 100 * 9.97 / 109.97 ~= 9.07
+      assert(received > 9_900000 && received < amountIn);
+    });
+
+    it("Ramps the amplification coefficient", async () => {
+      const stop = (await now()) + 2 * 24 * 60 * 60;
+
+      await program.methods
+        .rampAmp(new BN(200), new BN(stop))
+        .accounts({ authority: wallet.publicKey, config: stableConfig })
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(stableConfig);
+      assert.equal(configAccount.initialAmp.toNumber(), 100);
+      assert.equal(configAccount.targetAmp.toNumber(), 200);
+      assert.equal(configAccount.rampStopTs.toNumber(), stop);
+    });
+
+    it("Should fail to start another ramp right away", async () => {
+      try {
+        await program.methods
+          .rampAmp(new BN(300), new BN((await now()) + 2 * 24 * 60 * 60))
+          .accounts({ authority: wallet.publicKey, config: stableConfig })
+          .rpc();
+        assert.fail("Should have thrown invalid ramp error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidRamp");
+      }
+    });
+
+    it("Should fail to ramp a constant product pool", async () => {
+      try {
+        await program.methods
+          .rampAmp(new BN(200), new BN((await now()) + 2 * 24 * 60 * 60))
+          .accounts({ authority: wallet.publicKey, config })
+          .rpc();
+        assert.fail("Should have thrown invalid curve type error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidCurveType");
+      }
+    });
+  });
+
+  describe("Concentrated Liquidity", () => {
+    const clSeed = new BN(Math.floor(Math.random() * 1000000) + 4000000);
+    const tickSpacing = 8;
+    const [tickLower, tickUpper] = [-128, 128];
+    const liquidity = new BN(1_000_000_000);
+    // Q64.64 square roots of a price of 1 and of the lowest supported price
+    const sqrtPriceOne = new BN(1).shln(64);
+    const minSqrtPrice = new BN("4295048016");
+    let pool: anchor.web3.PublicKey;
+    let clMintX: anchor.web3.PublicKey;
+    let clMintY: anchor.web3.PublicKey;
+    let clUserX: anchor.web3.PublicKey;
+    let clUserY: anchor.web3.PublicKey;
+    let position: anchor.web3.PublicKey;
+    let clVaultX: anchor.web3.PublicKey;
+    let clVaultY: anchor.web3.PublicKey;
+    let tickArrayLow: anchor.web3.PublicKey;
+    let tickArrayHigh: anchor.web3.PublicKey;
+
+    const i32 = (value: number) => {
+      const buffer = Buffer.alloc(4);
+      buffer.writeInt32LE(value);
+      return buffer;
+    };
+
+    const tickArray = (start: number) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("tick_array"), pool.toBuffer(), i32(start)],
+        program.programId,
+      )[0];
+
+    const positionAccounts = () => ({
+      owner: wallet.publicKey,
+      mintX: clMintX,
+      mintY: clMintY,
+      pool,
+      position,
+      tickArrayLower: tickArrayLow,
+      tickArrayUpper: tickArrayHigh,
+      vaultX: clVaultX,
+      vaultY: clVaultY,
+      userX: clUserX,
+      userY: clUserY,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+    const clPoolAccounts = async (
+      poolSeed: BN,
+      poolMintX: anchor.web3.PublicKey,
+      poolMintY: anchor.web3.PublicKey,
+    ) => {
+      const [clPool] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("cl_pool"), poolSeed.toArrayLike(Buffer, "le", 8)],
+        program.programId,
+      );
+      return {
+        initializer: wallet.publicKey,
+        mintX: poolMintX,
+        mintY: poolMintY,
+        registry,
+        poolEntry: poolEntry(poolMintX, poolMintY, fee),
+        pool: clPool,
+        vaultX: await getAssociatedTokenAddress(poolMintX, clPool, true),
+        vaultY: await getAssociatedTokenAddress(poolMintY, clPool, true),
+        tokenProgramX: TOKEN_PROGRAM_ID,
+        tokenProgramY: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      };
+    };
+
+    before(async () => {
+      // A pair of its own, every tier of the X/Y pair already has a pool
+      clMintX = await createMint(
+        connection,
+        wallet.payer,
+        wallet.publicKey,
+        null,
+        6,
+      );
+      clMintY = await createMint(
+        connection,
+        wallet.payer,
+        wallet.publicKey,
+        null,
+        6,
+        keypairAfter(clMintX),
+      );
+      clUserX = (
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          wallet.payer,
+          clMintX,
+          wallet.publicKey,
+        )
+      ).address;
+      clUserY = (
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          wallet.payer,
+          clMintY,
+          wallet.publicKey,
+        )
+      ).address;
+      await mintTo(
+        connection,
+        wallet.payer,
+        clMintX,
+        clUserX,
+        wallet.publicKey,
+        initialMintAmount,
+      );
+      await mintTo(
+        connection,
+        wallet.payer,
+        clMintY,
+        clUserY,
+        wallet.publicKey,
+        initialMintAmount,
+      );
+
+      [pool] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("cl_pool"), clSeed.toArrayLike(Buffer, "le", 8)],
+        program.programId,
+      );
+      [position] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("position"),
+          pool.toBuffer(),
+          wallet.publicKey.toBuffer(),
+          i32(tickLower),
+          i32(tickUpper),
+        ],
+        program.programId,
+      );
+      clVaultX = await getAssociatedTokenAddress(clMintX, pool, true);
+      clVaultY = await getAssociatedTokenAddress(clMintY, pool, true);
+      // 32 ticks of spacing 8 per array, the position spans two of them
+      tickArrayLow = tickArray(-256);
+      tickArrayHigh = tickArray(0);
+    });
+
+    it("Initializes a concentrated liquidity pool", async () => {
+      await program.methods
+        .initializeClPool(clSeed, fee, tickSpacing, sqrtPriceOne)
+        .accounts(await clPoolAccounts(clSeed, clMintX, clMintY))
+        .rpc();
+
+      for (const start of [-256, 0]) {
+        await program.methods
+          .initializeTickArray(start)
+          .accounts({
+            payer: wallet.publicKey,
+            pool,
+            tickArray: tickArray(start),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      const poolAccount = await program.account.clPool.fetch(pool);
+      assert.equal(poolAccount.tickCurrent, 0);
+      assert.equal(poolAccount.liquidity.toNumber(), 0);
+
+      // Registered like the other curves
+      const entry = await program.account.poolEntry.fetch(
+        poolEntry(clMintX, clMintY, fee),
+      );
+      assert.equal(entry.config.toBase58(), pool.toBase58());
+      const indexAccount = await program.account.poolIndex.fetch(
+        poolIndex(entry.index),
+      );
+      assert.equal(indexAccount.config.toBase58(), pool.toBase58());
+    });
+
+    it("Should fail to create a second concentrated pool for the pair", async () => {
+      const poolSeed = clSeed.addn(1);
+      try {
+        await program.methods
+          .initializeClPool(poolSeed, fee, tickSpacing, sqrtPriceOne)
+          .accounts(await clPoolAccounts(poolSeed, clMintX, clMintY))
+          .rpc();
+        assert.fail("Should have thrown account already in use error");
+      } catch (error) {
+        assert.include(error.toString(), "already in use");
+      }
+    });
+
+    it("Should fail to create a concentrated pool with reversed mints", async () => {
+      const poolSeed = clSeed.addn(1);
+      try {
+        await program.methods
+          .initializeClPool(poolSeed, fee, tickSpacing, sqrtPriceOne)
+          .accounts(await clPoolAccounts(poolSeed, clMintY, clMintX))
+          .rpc();
+        assert.fail("Should have thrown invalid mint pair error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidMintPair");
+      }
+    });
+
+    it("Should reject a tick array that isn't aligned", async () => {
+      try {
+        await program.methods
+          .initializeTickArray(8)
+          .accounts({
+            payer: wallet.publicKey,
+            pool,
+            tickArray: tickArray(8),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("Should have thrown invalid tick array error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidTickArray");
+      }
+    });
+
+    it("Should reject a range off the tick spacing", async () => {
+      const [misaligned] = anchor.web3.PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("position"),
+          pool.toBuffer(),
+          wallet.publicKey.toBuffer(),
+          i32(-100),
+          i32(tickUpper),
+        ],
+        program.programId,
+      );
+
+      try {
+        await program.methods
+          .openPosition(-100, tickUpper)
+          .accounts({
+            owner: wallet.publicKey,
+            pool,
+            position: misaligned,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("Should have thrown invalid tick range error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidTickRange");
+      }
+    });
+
+    it("Opens a position and adds liquidity", async () => {
+      await program.methods
+        .openPosition(tickLower, tickUpper)
+        .accounts({
+          owner: wallet.publicKey,
+          pool,
+          position,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .increaseLiquidity(liquidity, new BN(10_000000), new BN(10_000000))
+        .accounts(positionAccounts())
+        .rpc();
+
+      const positionAccount = await program.account.position.fetch(position);
+      assert.equal(positionAccount.liquidity.toString(), liquidity.toString());
+
+      // In range at a price of 1, so both sides are needed in equal parts
+      const vaultXAccount = await getAccount(connection, clVaultX);
+      const vaultYAccount = await getAccount(connection, clVaultY);
+      const [depositedX, depositedY] = [
+        Number(vaultXAccount.amount),
+        Number(vaultYAccount.amount),
+      ];
+      assert(depositedX > 0 && Math.abs(depositedX - depositedY) <= 2);
+
+      const poolAccount = await program.account.clPool.fetch(pool);
+      assert.equal(poolAccount.liquidity.toString(), liquidity.toString());
+    });
+
+    it("Swaps against the position", async () => {
+      const userYBefore = await getAccount(connection, clUserY);
+
+      const signature = await program.methods
+        .clSwap(true, new BN(1_000000), new BN(1), minSqrtPrice)
+        .accounts({
+          user: wallet.publicKey,
+          mintX: clMintX,
+          mintY: clMintY,
+          pool,
+          vaultX: clVaultX,
+          vaultY: clVaultY,
+          userX: clUserX,
+          userY: clUserY,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          [tickArrayHigh, tickArrayLow].map((pubkey) => ({
+            pubkey,
+            isSigner: false,
+            isWritable: true,
+          })),
+        )
+        .rpc({ commitment: "confirmed" });
+
+      const userYAfter = await getAccount(connection, clUserY);
+      const received = Number(userYAfter.amount) - Number(userYBefore.amount);
+      assert(received > 0 && received < 1_000000);
+
+      const [event] = await emittedEvents(signature);
+      assert.equal(event.name, "swapEvent");
+      assert.equal(event.data.pool.toBase58(), pool.toBase58());
+      assert.equal(event.data.amountIn.toNumber(), 1_000000);
+      assert.equal(event.data.amountOut.toNumber(), received);
+
+      const poolAccount = await program.account.clPool.fetch(pool);
+      assert(poolAccount.tickCurrent < 0);
+    });
+
+    it("Collects the swap fee", async () => {
+      const userXBefore = await getAccount(connection, clUserX);
+
+      await program.methods.collectFees().accounts(positionAccounts()).rpc();
+
+      // Only position in range, so it earned the whole 0.3% fee
+      const userXAfter = await getAccount(connection, clUserX);
+      const collected = Number(userXAfter.amount) - Number(userXBefore.amount);
+      assert(collected >= 2_999 && collected <= 3_000);
+    });
+
+    it("Should fail to remove more liquidity than the position holds", async () => {
+      try {
+        await program.methods
+          .decreaseLiquidity(liquidity.addn(1), new BN(0), new BN(0))
+          .accounts(positionAccounts())
+          .rpc();
+        assert.fail("Should have thrown invalid amount error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidAmount");
+      }
+    });
+
+    it("Removes the liquidity", async () => {
+      await program.methods
+        .decreaseLiquidity(liquidity, new BN(1), new BN(1))
+        .accounts(positionAccounts())
+        .rpc();
+
+      const positionAccount = await program.account.position.fetch(position);
+      assert.equal(positionAccount.liquidity.toNumber(), 0);
+
+      const poolAccount = await program.account.clPool.fetch(pool);
+      assert.equal(poolAccount.liquidity.toNumber(), 0);
+    });
+  });
+
+  describe("Admin", () => {
+    const newAuthority = anchor.web3.Keypair.generate();
+    let allowEntry: anchor.web3.PublicKey;
+
+    before(() => {
+      [allowEntry] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("allow"), config.toBuffer(), wallet.publicKey.toBuffer()],
+        program.programId,
+      );
+    });
+
+    const swapAccounts = () => ({
+      user: wallet.publicKey,
+      mintX,
+      mintY,
+      config,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgramX: TOKEN_PROGRAM_ID,
+      tokenProgramY: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      mintLp,
+      vaultX,
+      vaultY,
+      userX: userXAccount,
+      userY: userYAccount,
+      userLp,
+    });
+
+    it("Locks the pool and rejects swaps", async () => {
+      await program.methods
+        .lock()
+        .accounts({ authority: wallet.publicKey, config })
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(config);
+      assert.equal(configAccount.locked, true);
+
+      try {
+        await program.methods
+          .swap(true, new BN(1_000000), new BN(1))
+          .accounts(swapAccounts())
+          .rpc();
+        assert.fail("Should have thrown pool locked error");
+      } catch (error) {
+        assert.include(error.toString(), "PoolLocked");
+      }
+    });
+
+    it("Unlocks the pool and allows swaps again", async () => {
+      await program.methods
+        .unlock()
+        .accounts({ authority: wallet.publicKey, config })
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(config);
+      assert.equal(configAccount.locked, false);
+
+      await program.methods
+        .swap(true, new BN(1_000000), new BN(1))
+        .accounts(swapAccounts())
+        .rpc();
+    });
+
+    it("Gates the pool and rejects users off the allowlist", async () => {
+      await program.methods
+        .setGate(true)
+        .accounts({ authority: wallet.publicKey, config })
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(config);
+      assert.equal(configAccount.gate, true);
+
+      try {
+        await program.methods
+          .swap(true, new BN(1_000000), new BN(1))
+          .accounts(swapAccounts())
+          .rpc();
+        assert.fail("Should have thrown NotAllowlisted error");
+      } catch (error) {
+        assert.include(error.toString(), "NotAllowlisted");
+      }
+    });
+
+    it("Should fail to add an entry that already expired", async () => {
+      const now = await connection.getBlockTime(await connection.getSlot());
+      try {
+        await program.methods
+          .addToAllowlist(wallet.publicKey, new BN(now - 1))
+          .accounts({ authority: wallet.publicKey, config, allowEntry })
+          .rpc();
+        assert.fail("Should have thrown InvalidExpiry error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidExpiry");
+      }
+    });
+
+    it("Allows swaps with an allowlist entry", async () => {
+      const now = await connection.getBlockTime(await connection.getSlot());
+      await program.methods
+        .addToAllowlist(wallet.publicKey, new BN(now + 3600))
+        .accounts({ authority: wallet.publicKey, config, allowEntry })
+        .rpc();
+
+      const entry = await program.account.allowEntry.fetch(allowEntry);
+      assert.equal(entry.user.toBase58(), wallet.publicKey.toBase58());
+      assert.equal(entry.expiresAt.toNumber(), now + 3600);
+
+      await program.methods
+        .swap(true, new BN(1_000000), new BN(1))
+        .accounts({ ...swapAccounts(), allowEntry })
+        .rpc();
+    });
+
+    it("Removes the entry and lifts the gate", async () => {
+      await program.methods
+        .removeFromAllowlist()
+        .accounts({ authority: wallet.publicKey, config, allowEntry })
+        .rpc();
+      assert.isNull(await connection.getAccountInfo(allowEntry));
+
+      await program.methods
+        .setGate(false)
+        .accounts({ authority: wallet.publicKey, config })
+        .rpc();
+
+      await program.methods
+        .swap(true, new BN(1_000000), new BN(1))
+        .accounts(swapAccounts())
+        .rpc();
+    });
+
+    const updateFeeAccounts = (from: number, to: number) => ({
+      authority: wallet.publicKey,
+      config,
+      registry,
+      poolEntry: poolEntry(mintX, mintY, from),
+      newPoolEntry: poolEntry(mintX, mintY, to),
+      systemProgram: SystemProgram.programId,
+    });
+
+    it("Should fail to move the pool off the fee tiers", async () => {
+      try {
+        await program.methods
+          .updateFee(50)
+          .accounts(updateFeeAccounts(fee, 50))
+          .rpc();
+        assert.fail("Should have thrown invalid fee tier error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFeeTier");
+      }
+    });
+
+    it("Updates the fee", async () => {
+      await program.methods
+        .addFeeTier(50)
+        .accounts({ admin: wallet.publicKey, registry })
+        .rpc();
+      await program.methods
+        .updateFee(50)
+        .accounts(updateFeeAccounts(fee, 50))
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(config);
+      assert.equal(configAccount.fee, 50);
+
+      // The pool is registered under its new tier only
+      assert.isNull(
+        await connection.getAccountInfo(poolEntry(mintX, mintY, fee)),
+      );
+      const entry = await program.account.poolEntry.fetch(
+        poolEntry(mintX, mintY, 50),
+      );
+      assert.equal(entry.config.toBase58(), config.toBase58());
+    });
+
+    it("Should fail to set a fee above 100%", async () => {
+      try {
+        await program.methods
+          .updateFee(10_001)
+          .accounts(updateFeeAccounts(50, 10_001))
+          .rpc();
+        assert.fail("Should have thrown invalid fee error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFee");
+      }
+    });
+
+    it("Should fail to cap the dynamic fee below the base fee", async () => {
+      try {
+        await program.methods
+          .setDynamicFee(40, 10_000, new BN(600))
+          .accounts({ authority: wallet.publicKey, config })
+          .rpc();
+        assert.fail("Should have thrown invalid fee error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFee");
+      }
+    });
+
+    it("Raises the fee after a price move with a dynamic fee", async () => {
+      const maxFee = 300;
+      await program.methods
+        .setDynamicFee(maxFee, 10_000, new BN(600))
+        .accounts({ authority: wallet.publicKey, config })
+        .rpc();
+
+      // The first trade sets the reference price, then moves the price a lot
+      await program.methods
+        .swap(true, new BN(20_000000), new BN(1))
+        .accounts(swapAccounts())
+        .rpc();
+
+      const signature = await program.methods
+        .swap(false, new BN(1_000000), new BN(1))
+        .accounts(swapAccounts())
+        .rpc({ commitment: "confirmed" });
+
+      const [event] = await emittedEvents(signature);
+      assert.equal(event.data.feeBps, maxFee);
+
+      // Back to the static fee for the tests below
+      await program.methods
+        .setDynamicFee(50, 0, new BN(0))
+        .accounts({ authority: wallet.publicKey, config })
+        .rpc();
+      const configAccount = await program.account.config.fetch(config);
+      assert.equal(configAccount.volatilityFactor, 0);
+    });
+
+    it("Should fail to give the protocol more than half the swap fee", async () => {
+      try {
+        await program.methods
+          .updateProtocolFee(5_001)
+          .accounts({ authority: wallet.publicKey, config })
+          .rpc();
+        assert.fail("Should have thrown invalid fee error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFee");
+      }
+    });
+
+    it("Accrues and collects protocol fees", async () => {
+      const treasury = anchor.web3.Keypair.generate();
+      const treasuryX = (
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          wallet.payer,
+          mintX,
+          treasury.publicKey,
+        )
+      ).address;
+      const treasuryY = (
+        await getOrCreateAssociatedTokenAccount(
+          connection,
+          wallet.payer,
+          mintY,
+          treasury.publicKey,
+        )
+      ).address;
+
+      await program.methods
+        .updateProtocolFee(5_000)
+        .accounts({ authority: wallet.publicKey, config })
+        .rpc();
+
+      await program.methods
+        .swap(true, new BN(10_000000), new BN(1))
+        .accounts(swapAccounts())
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(config);
+      assert.equal(configAccount.protocolFeeBps, 5_000);
+      // 10_000000 * 50bps fee * 50% protocol share
+      assert.equal(configAccount.protocolFeesX.toNumber(), 25_000);
+      assert.equal(configAccount.protocolFeesY.toNumber(), 0);
+
+      await program.methods
+        .collectProtocolFees()
+        .accounts({
+          authority: wallet.publicKey,
+          mintX,
+          mintY,
+          config,
+          vaultX,
+          vaultY,
+          treasuryX,
+          treasuryY,
+          tokenProgramX: TOKEN_PROGRAM_ID,
+          tokenProgramY: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const treasuryXAccount = await getAccount(connection, treasuryX);
+      assert.equal(treasuryXAccount.amount.toString(), "25000");
+
+      const configAfter = await program.account.config.fetch(config);
+      assert.equal(configAfter.protocolFeesX.toNumber(), 0);
+    });
+
+    it("Transfers the authority", async () => {
+      await program.methods
+        .transferAuthority(newAuthority.publicKey)
+        .accounts({ authority: wallet.publicKey, config })
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(config);
+      assert.equal(
+        configAccount.authority.toBase58(),
+        newAuthority.publicKey.toBase58(),
+      );
+
+      try {
+        await program.methods
+          .lock()
+          .accounts({ authority: wallet.publicKey, config })
+          .rpc();
+        assert.fail("Should have thrown invalid authority error");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidAuthority");
+      }
+    });
+
+    it("Should fail to renounce the authority of a locked pool", async () => {
+      await program.methods
+        .lock()
+        .accounts({ authority: newAuthority.publicKey, config })
+        .signers([newAuthority])
+        .rpc();
+
+      try {
+        await program.methods
+          .renounceAuthority()
+          .accounts({ authority: newAuthority.publicKey, config })
+          .signers([newAuthority])
+          .rpc();
+        assert.fail("Should have thrown pool locked error");
+      } catch (error) {
+        assert.include(error.toString(), "PoolLocked");
+      }
+
+      await program.methods
+        .unlock()
+        .accounts({ authority: newAuthority.publicKey, config })
+        .signers([newAuthority])
+        .rpc();
+    });
+
+    it("Renounces the authority", async () => {
+      await program.methods
+        .renounceAuthority()
+        .accounts({ authority: newAuthority.publicKey, config })
+        .signers([newAuthority])
+        .rpc();
+
+      const configAccount = await program.account.config.fetch(config);
+      assert.isNull(configAccount.authority);
+
+      try {
+        await program.methods
+          .lock()
+          .accounts({ authority: newAuthority.publicKey, config })
+          .signers([newAuthority])
+          .rpc();
+        assert.fail("Should have thrown no authority set error");
+      } catch (error) {
+        assert.include(error.toString(), "NoAuthoritySet");
+      }
     });
   });
 
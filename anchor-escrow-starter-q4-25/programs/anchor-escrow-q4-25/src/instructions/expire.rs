@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowError, Refund};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+pub struct Expire<'info> {
+    // Anyone can clean up an expired escrow, and gets its crank tip
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = caller,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = mint_a @ EscrowError::InvalidMintA,
+        has_one = maker @ EscrowError::InvalidMaker,
+        seeds = [b"escrow", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Expire<'info> {
+    pub fn expire_and_close_vault(&mut self) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp >= self.escrow.expires_at,
+            EscrowError::NotExpired
+        );
+
+        Refund::return_vault(
+            &self.escrow,
+            &self.vault,
+            &self.mint_a,
+            &self.maker_ata_a,
+            &self.maker.to_account_info(),
+            &self.token_program,
+        )?;
+
+        // The rest of the escrow's lamports go to the maker when it's closed
+        let crank_tip = self.escrow.crank_tip;
+        self.escrow.sub_lamports(crank_tip)?;
+        self.caller.add_lamports(crank_tip)?;
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 
 use crate::Escrow;
 use anchor_spl::{
@@ -51,19 +54,62 @@ pub struct Make<'info> {
 }
 
 impl<'info> Make<'info> {
-    pub fn init_escrow(&mut self, seed: u64, receive: u64, bumps: &MakeBumps) -> Result<()> {
+    pub fn init_escrow(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        min_fill: u64,
+        expires_at: i64,
+        taker: Option<Pubkey>, // None for an escrow anyone can take
+        bumps: &MakeBumps,
+    ) -> Result<()> {
+        require!(receive != 0, EscrowError::InvalidAmount);
+        require!(min_fill <= receive, EscrowError::InvalidAmount);
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            EscrowError::InvalidExpiry
+        );
+
         self.escrow.set_inner(Escrow {
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
+            taker,
             receive,
+            remaining: receive,
+            min_fill,
+            expires_at,
+            crank_tip: 0,
+            version: 0,
             bump: bumps.escrow,
         });
         Ok(())
     }
 
+    // Lamports kept on the escrow for whoever calls `expire`, they go back to
+    // the maker if the escrow is taken or refunded instead
+    pub fn fund_crank_tip(&mut self, crank_tip: u64) -> Result<()> {
+        if crank_tip == 0 {
+            return Ok(());
+        }
+        self.escrow.crank_tip = crank_tip;
+
+        transfer(
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.maker.to_account_info(),
+                    to: self.escrow.to_account_info(),
+                },
+            ),
+            crank_tip,
+        )
+    }
+
     pub fn deposit(&mut self, deposit: u64) -> Result<()> {
+        require!(deposit != 0, EscrowError::InvalidAmount);
+
         let tranfer_accounts = TransferChecked {
             from: self.maker_ata_a.to_account_info(),
             mint: self.mint_a.to_account_info(),
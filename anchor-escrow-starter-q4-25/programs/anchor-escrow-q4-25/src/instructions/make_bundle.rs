@@ -0,0 +1,123 @@
+use anchor_lang::prelude::*;
+
+use crate::{BundleEscrow, BundleLeg, EscrowError, MAX_BUNDLE_MINTS};
+use anchor_spl::{
+    associated_token::{create, AssociatedToken, Create},
+    token_interface::{transfer_checked, Mint, TokenInterface, TransferChecked},
+};
+
+// Remaining accounts per deposited mint, in this order:
+// mint, maker_ata, vault
+pub const MAKE_BUNDLE_ACCOUNTS: usize = 3;
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBundle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"bundle", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        space = BundleEscrow::DISCRIMINATOR.len() + BundleEscrow::INIT_SPACE,
+        bump
+    )]
+    pub bundle: Account<'info, BundleEscrow>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>, // Owns every mint of the bundle
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeBundle<'info> {
+    pub fn init_bundle(
+        &mut self,
+        seed: u64,
+        give: Vec<BundleLeg>,
+        receive: Vec<BundleLeg>,
+        bumps: &MakeBundleBumps,
+    ) -> Result<()> {
+        check_legs(&give)?;
+        check_legs(&receive)?;
+
+        self.bundle.set_inner(BundleEscrow {
+            seed,
+            maker: self.maker.key(),
+            give,
+            receive,
+            bump: bumps.bundle,
+        });
+        Ok(())
+    }
+
+    // Creates a vault for every mint given and moves the maker's deposit in
+    pub fn deposit(&self, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(
+            accounts.len() == self.bundle.give.len() * MAKE_BUNDLE_ACCOUNTS,
+            EscrowError::InvalidBundleAccounts
+        );
+
+        for (leg, accounts) in self
+            .bundle
+            .give
+            .iter()
+            .zip(accounts.chunks(MAKE_BUNDLE_ACCOUNTS))
+        {
+            let [mint, maker_ata, vault] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidBundleAccounts);
+            require_keys_eq!(
+                vault.key(),
+                BundleEscrow::vault(&self.bundle.key(), &leg.mint, &self.token_program.key()),
+                EscrowError::InvalidBundleAccounts
+            );
+            let decimals = InterfaceAccount::<Mint>::try_from(mint)?.decimals;
+
+            create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.maker.to_account_info(),
+                    associated_token: vault.clone(),
+                    authority: self.bundle.to_account_info(),
+                    mint: mint.clone(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                },
+            ))?;
+
+            // Transfer the mint: maker_ata => Vault
+            transfer_checked(
+                CpiContext::new(
+                    self.token_program.to_account_info(),
+                    TransferChecked {
+                        from: maker_ata.clone(),
+                        mint: mint.clone(),
+                        to: vault.clone(),
+                        authority: self.maker.to_account_info(),
+                    },
+                ),
+                leg.amount,
+                decimals,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// One to MAX_BUNDLE_MINTS distinct mints, each for a non-zero amount
+fn check_legs(legs: &[BundleLeg]) -> Result<()> {
+    require!(
+        !legs.is_empty() && legs.len() <= MAX_BUNDLE_MINTS,
+        EscrowError::InvalidBundle
+    );
+
+    for (i, leg) in legs.iter().enumerate() {
+        require!(leg.amount != 0, EscrowError::InvalidAmount);
+        require!(
+            legs[..i].iter().all(|other| other.mint != leg.mint),
+            EscrowError::InvalidBundle
+        );
+    }
+    Ok(())
+}
@@ -1,8 +1,26 @@
+pub mod expire;
+pub use expire::*;
+
 pub mod make;
 pub use make::*;
 
+pub mod make_bundle;
+pub use make_bundle::*;
+
 pub mod refund;
 pub use refund::*;
 
+pub mod refund_bundle;
+pub use refund_bundle::*;
+
 pub mod take;
 pub use take::*;
+
+pub mod take_bundle;
+pub use take_bundle::*;
+
+pub mod update_offer;
+pub use update_offer::*;
+
+pub mod update_taker;
+pub use update_taker::*;
@@ -8,7 +8,6 @@ use anchor_spl::{
         TransferChecked,
     },
 };
-use crate::EscrowError;
 
 
 #[derive(Accounts)]
@@ -48,36 +47,56 @@ pub struct Refund<'info> {
 
 impl<'info> Refund<'info> {
     pub fn refund_and_close_vault(&mut self) -> Result<()> {
+        Self::return_vault(
+            &self.escrow,
+            &self.vault,
+            &self.mint_a,
+            &self.maker_ata_a,
+            &self.maker.to_account_info(),
+            &self.token_program,
+        )
+    }
+
+    // Sends the whole vault back to the maker and closes it, its rent going to
+    // the maker too. Doesn't need the maker to sign so `expire` can use it.
+    pub fn return_vault(
+        escrow: &Account<'info, Escrow>,
+        vault: &InterfaceAccount<'info, TokenAccount>,
+        mint_a: &InterfaceAccount<'info, Mint>,
+        maker_ata_a: &InterfaceAccount<'info, TokenAccount>,
+        maker: &AccountInfo<'info>,
+        token_program: &Interface<'info, TokenInterface>,
+    ) -> Result<()> {
         let signer_seeds: &[&[&[u8]]] = &[&[
             b"escrow",
-            self.maker.to_account_info().key.as_ref(),
-            &self.escrow.seed.to_le_bytes(),
-            &[self.escrow.bump],
+            escrow.maker.as_ref(),
+            &escrow.seed.to_le_bytes(),
+            &[escrow.bump],
         ]];
 
         let transfer_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
+            from: vault.to_account_info(),
+            mint: mint_a.to_account_info(),
+            to: maker_ata_a.to_account_info(),
+            authority: escrow.to_account_info(),
         };
 
         let tranfer_cpi_ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            token_program.to_account_info(),
             transfer_accounts,
             signer_seeds,
         );
 
-        transfer_checked(tranfer_cpi_ctx, self.vault.amount, self.mint_a.decimals)?;
+        transfer_checked(tranfer_cpi_ctx, vault.amount, mint_a.decimals)?;
 
         let close_accounts = CloseAccount {
-            account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
-            authority: self.escrow.to_account_info(),
+            account: vault.to_account_info(),
+            destination: maker.clone(),
+            authority: escrow.to_account_info(),
         };
 
         let close_cpi_ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            token_program.to_account_info(),
             close_accounts,
             signer_seeds,
         );
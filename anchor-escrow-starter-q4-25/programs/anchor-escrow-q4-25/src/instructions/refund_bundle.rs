@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+
+use crate::{BundleEscrow, EscrowError};
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+// Remaining accounts per deposited mint, in this order:
+// mint, vault, maker_ata
+pub const REFUND_BUNDLE_ACCOUNTS: usize = 3;
+
+#[derive(Accounts)]
+pub struct RefundBundle<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::InvalidMaker,
+        seeds = [b"bundle", maker.key().as_ref(), &bundle.seed.to_le_bytes()],
+        bump = bundle.bump
+    )]
+    pub bundle: Account<'info, BundleEscrow>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundBundle<'info> {
+    pub fn refund_and_close_vaults(&self, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(
+            accounts.len() == self.bundle.give.len() * REFUND_BUNDLE_ACCOUNTS,
+            EscrowError::InvalidBundleAccounts
+        );
+
+        for (leg, accounts) in self
+            .bundle
+            .give
+            .iter()
+            .zip(accounts.chunks(REFUND_BUNDLE_ACCOUNTS))
+        {
+            let [mint, vault, maker_ata] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidBundleAccounts);
+            require_keys_eq!(
+                maker_ata.key(),
+                get_associated_token_address_with_program_id(
+                    &self.maker.key(),
+                    &leg.mint,
+                    &self.token_program.key(),
+                ),
+                EscrowError::InvalidBundleAccounts
+            );
+
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                Create {
+                    payer: self.maker.to_account_info(),
+                    associated_token: maker_ata.clone(),
+                    authority: self.maker.to_account_info(),
+                    mint: mint.clone(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                },
+            ))?;
+
+            Self::release_vault(
+                &self.bundle,
+                mint,
+                vault,
+                maker_ata,
+                &self.maker.to_account_info(),
+                &self.token_program,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Sends everything in the vault of `mint` to `to` and closes the vault,
+    // its rent going to the maker. Doesn't need the maker to sign so
+    // `take_bundle` can use it.
+    pub fn release_vault(
+        bundle: &Account<'info, BundleEscrow>,
+        mint: &'info AccountInfo<'info>,
+        vault: &'info AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        maker: &AccountInfo<'info>,
+        token_program: &Interface<'info, TokenInterface>,
+    ) -> Result<()> {
+        require_keys_eq!(
+            vault.key(),
+            BundleEscrow::vault(&bundle.key(), &mint.key(), &token_program.key()),
+            EscrowError::InvalidBundleAccounts
+        );
+        let decimals = InterfaceAccount::<Mint>::try_from(mint)?.decimals;
+        let amount = InterfaceAccount::<TokenAccount>::try_from(vault)?.amount;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"bundle",
+            bundle.maker.as_ref(),
+            &bundle.seed.to_le_bytes(),
+            &[bundle.bump],
+        ]];
+
+        transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: vault.clone(),
+                    mint: mint.clone(),
+                    to: to.clone(),
+                    authority: bundle.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            decimals,
+        )?;
+
+        close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: vault.clone(),
+                destination: maker.clone(),
+                authority: bundle.to_account_info(),
+            },
+            signer_seeds,
+        ))
+    }
+}
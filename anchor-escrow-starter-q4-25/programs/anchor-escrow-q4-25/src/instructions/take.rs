@@ -21,14 +21,15 @@ pub struct Take<'info> {
     #[account(mut)]
     pub maker: SystemAccount<'info>,
 
+    // Closed by the fill that leaves nothing to receive
     #[account(
         mut,
-        close = maker,
         seeds = [b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
         has_one = maker @ EscrowError::InvalidMaker,
         has_one = mint_a @ EscrowError::InvalidMintA,
-        has_one = mint_b @ EscrowError::InvalidMintB
+        has_one = mint_b @ EscrowError::InvalidMintB,
+        constraint = escrow.taker.is_none_or(|t| t == taker.key()) @ EscrowError::InvalidTaker
     )]
     pub escrow: Box<Account<'info, Escrow>>,
 
@@ -74,7 +75,48 @@ pub struct Take<'info> {
 }
 
 impl<'info> Take<'info> {
-    pub fn deposit(&self) -> Result<()> {
+    // Fails if the maker updated the offer after the taker read it
+    pub fn check_terms(&self, expected_version: u64, expected_receive: u64) -> Result<()> {
+        require!(
+            self.escrow.version == expected_version && self.escrow.receive == expected_receive,
+            EscrowError::OfferChanged
+        );
+        Ok(())
+    }
+
+    // Pays `amount_b` to the maker for the same share of what's left in the
+    // vault. The last fill may be below `min_fill` so no remainder gets stuck.
+    pub fn take_partial(&mut self, amount_b: u64) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp < self.escrow.expires_at,
+            EscrowError::EscrowExpired
+        );
+        let remaining = self.escrow.remaining;
+        require!(
+            amount_b != 0 && amount_b <= remaining,
+            EscrowError::InvalidAmount
+        );
+        require!(
+            amount_b >= self.escrow.min_fill || amount_b == remaining,
+            EscrowError::FillTooSmall
+        );
+
+        // Rounded down, the vault only pays out in full on the last fill
+        let amount_a = (self.vault.amount as u128 * amount_b as u128 / remaining as u128) as u64;
+        require!(amount_a != 0, EscrowError::ZeroPayout);
+
+        self.deposit(amount_b)?;
+        self.withdraw(amount_a)?;
+        self.escrow.remaining -= amount_b;
+
+        if self.escrow.remaining == 0 {
+            self.close_vault()?;
+            self.escrow.close(self.maker.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    pub fn deposit(&self, amount_b: u64) -> Result<()> {
         // Transfer funds from taker_ata_b => maker_ata_b
         transfer_checked(
             CpiContext::new(
@@ -86,13 +128,13 @@ impl<'info> Take<'info> {
                     authority: self.taker.to_account_info(),
                 },
             ),
-            self.escrow.receive,
+            amount_b,
             self.mint_b.decimals,
         )?;
         Ok(())
     }
 
-    pub fn withdraw_and_close_vault(&self) -> Result<()> {
+    pub fn withdraw(&self, amount_a: u64) -> Result<()> {
         let maker_key = self.maker.key();
         let signer_seeds = &[
             b"escrow",
@@ -112,10 +154,20 @@ impl<'info> Take<'info> {
                 },
                 &[signer_seeds],
             ),
-            self.vault.amount,
+            amount_a,
             self.mint_a.decimals,
         )?;
+        Ok(())
+    }
 
+    pub fn close_vault(&self) -> Result<()> {
+        let maker_key = self.maker.key();
+        let signer_seeds = &[
+            b"escrow",
+            maker_key.as_ref(),
+            &self.escrow.seed.to_le_bytes(),
+            &[self.escrow.bump],
+        ];
         // Closing Vault Account
         close_account(CpiContext::new_with_signer(
             self.token_program.to_account_info(),
@@ -0,0 +1,134 @@
+use anchor_lang::prelude::*;
+
+use crate::{BundleEscrow, EscrowError, RefundBundle, REFUND_BUNDLE_ACCOUNTS};
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{transfer_checked, Mint, TokenInterface, TransferChecked},
+};
+
+// Remaining accounts, first for every mint the maker gave, in this order:
+// mint, vault, taker_ata
+// then for every mint the maker receives, in this order:
+// mint, taker_ata, maker_ata
+pub const TAKE_BUNDLE_GIVE_ACCOUNTS: usize = REFUND_BUNDLE_ACCOUNTS;
+pub const TAKE_BUNDLE_RECEIVE_ACCOUNTS: usize = 3;
+
+#[derive(Accounts)]
+pub struct TakeBundle<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = maker,
+        seeds = [b"bundle", maker.key().as_ref(), bundle.seed.to_le_bytes().as_ref()],
+        bump = bundle.bump,
+        has_one = maker @ EscrowError::InvalidMaker
+    )]
+    pub bundle: Box<Account<'info, BundleEscrow>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeBundle<'info> {
+    // Every leg settles in the same instruction, any leg failing fails the take
+    pub fn take(&self, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let give_accounts = self.bundle.give.len() * TAKE_BUNDLE_GIVE_ACCOUNTS;
+        require!(
+            accounts.len()
+                == give_accounts + self.bundle.receive.len() * TAKE_BUNDLE_RECEIVE_ACCOUNTS,
+            EscrowError::InvalidBundleAccounts
+        );
+        let (give, receive) = accounts.split_at(give_accounts);
+
+        for (leg, accounts) in self
+            .bundle
+            .receive
+            .iter()
+            .zip(receive.chunks(TAKE_BUNDLE_RECEIVE_ACCOUNTS))
+        {
+            let [mint, taker_ata, maker_ata] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidBundleAccounts);
+            self.create_ata(mint, maker_ata, &self.maker.to_account_info())?;
+            let decimals = InterfaceAccount::<Mint>::try_from(mint)?.decimals;
+
+            // Transfer the mint: taker_ata => maker_ata
+            transfer_checked(
+                CpiContext::new(
+                    self.token_program.to_account_info(),
+                    TransferChecked {
+                        from: taker_ata.clone(),
+                        mint: mint.clone(),
+                        to: maker_ata.clone(),
+                        authority: self.taker.to_account_info(),
+                    },
+                ),
+                leg.amount,
+                decimals,
+            )?;
+        }
+
+        for (leg, accounts) in self
+            .bundle
+            .give
+            .iter()
+            .zip(give.chunks(TAKE_BUNDLE_GIVE_ACCOUNTS))
+        {
+            let [mint, vault, taker_ata] = accounts else {
+                return err!(EscrowError::InvalidBundleAccounts);
+            };
+            require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidBundleAccounts);
+            self.create_ata(mint, taker_ata, &self.taker.to_account_info())?;
+
+            RefundBundle::release_vault(
+                &self.bundle,
+                mint,
+                vault,
+                taker_ata,
+                &self.maker.to_account_info(),
+                &self.token_program,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Checks `ata` is the associated token account of `authority` for `mint`
+    // and creates it if it doesn't exist yet, the taker paying for it
+    fn create_ata(
+        &self,
+        mint: &AccountInfo<'info>,
+        ata: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+    ) -> Result<()> {
+        require_keys_eq!(
+            ata.key(),
+            get_associated_token_address_with_program_id(
+                &authority.key(),
+                &mint.key(),
+                &self.token_program.key(),
+            ),
+            EscrowError::InvalidBundleAccounts
+        );
+
+        create_idempotent(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.taker.to_account_info(),
+                associated_token: ata.clone(),
+                authority: authority.clone(),
+                mint: mint.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        ))
+    }
+}
@@ -0,0 +1,117 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowError};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+#[derive(Accounts)]
+pub struct UpdateOffer<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = mint_a @ EscrowError::InvalidMintA,
+        has_one = maker @ EscrowError::InvalidMaker,
+        seeds = [b"escrow", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateOffer<'info> {
+    // `receive` is the new total over the escrow's life, what was already
+    // filled still counts towards it. The vault can't be emptied this way,
+    // that's what `refund` is for, and an expired escrow can only be refunded.
+    pub fn update_offer(&mut self, receive: u64, top_up: u64, withdraw: u64) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp < self.escrow.expires_at,
+            EscrowError::EscrowExpired
+        );
+        let filled = self.escrow.receive - self.escrow.remaining;
+        require!(receive > filled, EscrowError::InvalidAmount);
+        let available = self
+            .vault
+            .amount
+            .checked_add(top_up)
+            .ok_or(EscrowError::InvalidAmount)?;
+        require!(withdraw < available, EscrowError::InvalidAmount);
+
+        if top_up != 0 {
+            self.top_up(top_up)?;
+        }
+        if withdraw != 0 {
+            self.withdraw(withdraw)?;
+        }
+
+        self.escrow.receive = receive;
+        self.escrow.remaining = receive - filled;
+        // Otherwise the rest could only be taken in one last fill
+        self.escrow.min_fill = self.escrow.min_fill.min(self.escrow.remaining);
+        self.escrow.version += 1;
+        Ok(())
+    }
+
+    pub fn top_up(&self, amount: u64) -> Result<()> {
+        // Transfer mint_a: maker_ata_a => Vault
+        transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.maker_ata_a.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: self.maker.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint_a.decimals,
+        )
+    }
+
+    pub fn withdraw(&self, amount: u64) -> Result<()> {
+        let maker_key = self.maker.key();
+        let signer_seeds = &[
+            b"escrow",
+            maker_key.as_ref(),
+            &self.escrow.seed.to_le_bytes(),
+            &[self.escrow.bump],
+        ];
+        // Transfer mint_a: Vault => maker_ata_a
+        transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                    to: self.maker_ata_a.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                },
+                &[signer_seeds],
+            ),
+            amount,
+            self.mint_a.decimals,
+        )
+    }
+}
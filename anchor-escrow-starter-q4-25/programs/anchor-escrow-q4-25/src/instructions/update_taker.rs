@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowError};
+
+#[derive(Accounts)]
+pub struct UpdateTaker<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::InvalidMaker,
+        seeds = [b"escrow", maker.key().as_ref(), &escrow.seed.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> UpdateTaker<'info> {
+    // Locks the escrow to `taker`, or opens it to anyone with None. Who may
+    // take is part of the terms, so takes signed against the old ones fail.
+    pub fn update_taker(&mut self, taker: Option<Pubkey>) -> Result<()> {
+        self.escrow.taker = taker;
+        self.escrow.version += 1;
+        Ok(())
+    }
+}
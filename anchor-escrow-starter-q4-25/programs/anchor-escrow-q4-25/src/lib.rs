@@ -11,18 +11,80 @@ declare_id!("5UFZzEt5vU9fxtUAgsD11z63ApZEHJ5bH7Z4QpFwZ2CQ");
 #[program]
 pub mod anchor_escrow_q4_25 {
     use super::*;
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn make(
+        ctx: Context<Make>,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        min_fill: u64,
+        expires_at: i64,
+        crank_tip: u64,
+        taker: Option<Pubkey>,
+    ) -> Result<()> {
         ctx.accounts.deposit(deposit)?;
-        ctx.accounts.init_escrow(seed, receive, &ctx.bumps)
+        ctx.accounts
+            .init_escrow(seed, receive, min_fill, expires_at, taker, &ctx.bumps)?;
+        ctx.accounts.fund_crank_tip(crank_tip)
     }
 
     pub fn refund(ctx: Context<Refund>) -> Result<()> {
         ctx.accounts.refund_and_close_vault()
     }
 
-    pub fn take(ctx: Context<Take>) -> Result<()> {
-        ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+    pub fn take(ctx: Context<Take>, expected_version: u64, expected_receive: u64) -> Result<()> {
+        ctx.accounts
+            .check_terms(expected_version, expected_receive)?;
+        let amount_b = ctx.accounts.escrow.remaining;
+        ctx.accounts.take_partial(amount_b)
+    }
+
+    pub fn take_partial(
+        ctx: Context<Take>,
+        amount_b: u64,
+        expected_version: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .check_terms(expected_version, expected_receive)?;
+        ctx.accounts.take_partial(amount_b)
+    }
+
+    pub fn expire(ctx: Context<Expire>) -> Result<()> {
+        ctx.accounts.expire_and_close_vault()
+    }
+
+    pub fn update_taker(ctx: Context<UpdateTaker>, taker: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.update_taker(taker)
+    }
+
+    pub fn update_offer(
+        ctx: Context<UpdateOffer>,
+        receive: u64,
+        top_up: u64,
+        withdraw: u64,
+    ) -> Result<()> {
+        ctx.accounts.update_offer(receive, top_up, withdraw)
+    }
+
+    pub fn make_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeBundle<'info>>,
+        seed: u64,
+        give: Vec<BundleLeg>,
+        receive: Vec<BundleLeg>,
+    ) -> Result<()> {
+        ctx.accounts.init_bundle(seed, give, receive, &ctx.bumps)?;
+        ctx.accounts.deposit(ctx.remaining_accounts)
+    }
+
+    pub fn take_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, TakeBundle<'info>>) -> Result<()> {
+        ctx.accounts.take(ctx.remaining_accounts)
+    }
+
+    pub fn refund_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundBundle<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_and_close_vaults(ctx.remaining_accounts)
     }
 }
 
@@ -36,4 +98,22 @@ pub enum EscrowError {
     InvalidMintA,
     #[msg("Invalid mint b")]
     InvalidMintB,
+    #[msg("Invalid taker")]
+    InvalidTaker,
+    #[msg("Fill below the minimum fill size")]
+    FillTooSmall,
+    #[msg("Fill too small to pay out any token a")]
+    ZeroPayout,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Escrow expired")]
+    EscrowExpired,
+    #[msg("Escrow not expired yet")]
+    NotExpired,
+    #[msg("Offer changed since it was read")]
+    OfferChanged,
+    #[msg("Bundle needs one to five distinct mints a side")]
+    InvalidBundle,
+    #[msg("Remaining accounts don't match the bundle")]
+    InvalidBundleAccounts,
 }
\ No newline at end of file
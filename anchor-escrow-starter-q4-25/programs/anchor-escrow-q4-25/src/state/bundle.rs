@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+pub const MAX_BUNDLE_MINTS: usize = 5;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct BundleLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BundleEscrow {
+    pub seed: u64,
+    pub maker: Pubkey,
+    #[max_len(MAX_BUNDLE_MINTS)]
+    pub give: Vec<BundleLeg>,
+    #[max_len(MAX_BUNDLE_MINTS)]
+    pub receive: Vec<BundleLeg>,
+    pub bump: u8,
+}
+
+impl BundleEscrow {
+    // Vault holding the deposit of `mint`, owned by the bundle escrow at `bundle`
+    pub fn vault(bundle: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(bundle, mint, token_program)
+    }
+}
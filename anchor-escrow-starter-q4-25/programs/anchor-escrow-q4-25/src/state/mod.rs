@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
 
+pub mod bundle;
+pub use bundle::*;
+
 #[account]
 #[derive(InitSpace)]
 pub struct Escrow {
@@ -7,6 +10,12 @@ pub struct Escrow {
     pub maker: Pubkey,
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
+    pub taker: Option<Pubkey>,
     pub receive: u64,
+    pub remaining: u64,
+    pub min_fill: u64,
+    pub expires_at: i64,
+    pub crank_tip: u64,
+    pub version: u64,
     pub bump: u8
 }
\ No newline at end of file
@@ -27,6 +27,9 @@ describe("anchor_escrow_q4_25", () => {
 
   const depositAmount = 100;
   const receiveAmount = 200;
+  const crankTip = 1_000_000;
+
+  const inAnHour = () => new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
 
   before(async () => {
     // Airdrop SOL to maker and taker
@@ -44,14 +47,14 @@ describe("anchor_escrow_q4_25", () => {
       createAssociatedTokenAccountInstruction(provider.wallet.publicKey, makerAtaA, maker, mintA)
     );
     await provider.sendAndConfirm(makerAtaATx);
-    await mintTo(provider.connection, provider.wallet.payer, mintA, makerAtaA, provider.wallet.payer, depositAmount * 2);
+    await mintTo(provider.connection, provider.wallet.payer, mintA, makerAtaA, provider.wallet.payer, depositAmount * 4);
 
     takerAtaB = getAssociatedTokenAddressSync(mintB, taker.publicKey);
     const takerAtaBTx = new anchor.web3.Transaction().add(
       createAssociatedTokenAccountInstruction(taker.publicKey, takerAtaB, taker.publicKey, mintB)
     );
     await provider.sendAndConfirm(takerAtaBTx, [taker]);
-    await mintTo(provider.connection, taker, mintB, takerAtaB, taker, receiveAmount * 2);
+    await mintTo(provider.connection, taker, mintB, takerAtaB, taker, receiveAmount * 4);
 
   });
 
@@ -65,7 +68,7 @@ describe("anchor_escrow_q4_25", () => {
 
     // Make
     await program.methods
-      .make(seed1, new anchor.BN(depositAmount), new anchor.BN(receiveAmount))
+      .make(seed1, new anchor.BN(depositAmount), new anchor.BN(receiveAmount), new anchor.BN(0), inAnHour(), new anchor.BN(0), null)
       .accountsStrict({
         maker: maker,
         mintA: mintA,
@@ -122,7 +125,7 @@ describe("anchor_escrow_q4_25", () => {
 
     // Make (again for take path)
     await program.methods
-      .make(seed2, new anchor.BN(depositAmount), new anchor.BN(receiveAmount))
+      .make(seed2, new anchor.BN(depositAmount), new anchor.BN(receiveAmount), new anchor.BN(0), inAnHour(), new anchor.BN(0), null)
       .accountsStrict({
         maker: maker,
         mintA: mintA,
@@ -142,7 +145,7 @@ describe("anchor_escrow_q4_25", () => {
 
     // Take
     await program.methods
-      .take()
+      .take(new anchor.BN(0), new anchor.BN(receiveAmount))
       .accountsStrict({
         taker: taker.publicKey,
         maker: maker,
@@ -174,4 +177,627 @@ describe("anchor_escrow_q4_25", () => {
     const makerBalanceB = (await provider.connection.getTokenAccountBalance(makerAtaB)).value.uiAmount;
     expect(makerBalanceB).to.equal(receiveAmount);
   });
+
+  it("Fills the escrow across several partial takes", async () => {
+    const seed3 = new anchor.BN(3333);
+    const minFill = 50;
+    [escrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.toBuffer(), seed3.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    vault = getAssociatedTokenAddressSync(mintA, escrowPda, true);
+
+    await program.methods
+      .make(seed3, new anchor.BN(depositAmount), new anchor.BN(receiveAmount), new anchor.BN(minFill), inAnHour(), new anchor.BN(0), null)
+      .accountsStrict({
+        maker: maker,
+        mintA: mintA,
+        mintB: mintB,
+        makerAtaA: makerAtaA,
+        escrow: escrowPda,
+        vault: vault,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const takeAccounts = {
+      taker: taker.publicKey,
+      maker: maker,
+      mintA: mintA,
+      mintB: mintB,
+      takerAtaA: takerAtaA,
+      takerAtaB: takerAtaB,
+      makerAtaB: makerAtaB,
+      escrow: escrowPda,
+      vault: vault,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+    const takerBalanceBefore = (await provider.connection.getTokenAccountBalance(takerAtaA)).value.uiAmount;
+
+    // A quarter of the receive pays out a quarter of the deposit
+    await program.methods
+      .takePartial(new anchor.BN(minFill), new anchor.BN(0), new anchor.BN(receiveAmount))
+      .accountsStrict(takeAccounts)
+      .signers([taker])
+      .rpc();
+
+    const escrowAccount = await program.account.escrow.fetch(escrowPda);
+    expect(escrowAccount.remaining.toNumber()).to.equal(receiveAmount - minFill);
+    const vaultBalance = (await provider.connection.getTokenAccountBalance(vault)).value.uiAmount;
+    expect(vaultBalance).to.equal(depositAmount * 3 / 4);
+
+    // Below the minimum fill size
+    try {
+      await program.methods
+        .takePartial(new anchor.BN(minFill - 1), new anchor.BN(0), new anchor.BN(receiveAmount))
+        .accountsStrict(takeAccounts)
+        .signers([taker])
+        .rpc();
+      expect.fail("Should have thrown FillTooSmall");
+    } catch (error) {
+      expect(error.toString()).to.include("FillTooSmall");
+    }
+
+    // The last fill takes whatever is left and closes the escrow
+    await program.methods
+      .takePartial(new anchor.BN(receiveAmount - minFill), new anchor.BN(0), new anchor.BN(receiveAmount))
+      .accountsStrict(takeAccounts)
+      .signers([taker])
+      .rpc();
+
+    const escrowInfo = await provider.connection.getAccountInfo(escrowPda);
+    expect(escrowInfo).to.be.null;
+
+    const vaultInfo = await provider.connection.getAccountInfo(vault);
+    expect(vaultInfo).to.be.null;
+
+    const takerBalanceAfter = (await provider.connection.getTokenAccountBalance(takerAtaA)).value.uiAmount;
+    expect(takerBalanceAfter - takerBalanceBefore).to.equal(depositAmount);
+  });
+
+  it("Expires the escrow and tips whoever cleans it up", async () => {
+    const seed4 = new anchor.BN(4444);
+    [escrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.toBuffer(), seed4.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    vault = getAssociatedTokenAddressSync(mintA, escrowPda, true);
+
+    const expiresAt = new anchor.BN(Math.floor(Date.now() / 1000) + 2);
+    await program.methods
+      .make(seed4, new anchor.BN(depositAmount), new anchor.BN(receiveAmount), new anchor.BN(0), expiresAt, new anchor.BN(crankTip), null)
+      .accountsStrict({
+        maker: maker,
+        mintA: mintA,
+        mintB: mintB,
+        makerAtaA: makerAtaA,
+        escrow: escrowPda,
+        vault: vault,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const expireAccounts = {
+      caller: taker.publicKey,
+      maker: maker,
+      mintA: mintA,
+      makerAtaA: makerAtaA,
+      escrow: escrowPda,
+      vault: vault,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+
+    try {
+      await program.methods.expire().accountsStrict(expireAccounts).signers([taker]).rpc();
+      expect.fail("Should have thrown NotExpired");
+    } catch (error) {
+      expect(error.toString()).to.include("NotExpired");
+    }
+
+    await new Promise(resolve => setTimeout(resolve, 4000));
+
+    try {
+      await program.methods
+        .take(new anchor.BN(0), new anchor.BN(receiveAmount))
+        .accountsStrict({
+          taker: taker.publicKey,
+          maker: maker,
+          mintA: mintA,
+          mintB: mintB,
+          takerAtaA: takerAtaA,
+          takerAtaB: takerAtaB,
+          makerAtaB: makerAtaB,
+          escrow: escrowPda,
+          vault: vault,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([taker])
+        .rpc();
+      expect.fail("Should have thrown EscrowExpired");
+    } catch (error) {
+      expect(error.toString()).to.include("EscrowExpired");
+    }
+
+    const makerBalanceBefore = (await provider.connection.getTokenAccountBalance(makerAtaA)).value.uiAmount;
+    const callerLamportsBefore = await provider.connection.getBalance(taker.publicKey);
+
+    await program.methods.expire().accountsStrict(expireAccounts).signers([taker]).rpc();
+
+    const escrowInfo = await provider.connection.getAccountInfo(escrowPda);
+    expect(escrowInfo).to.be.null;
+
+    const vaultInfo = await provider.connection.getAccountInfo(vault);
+    expect(vaultInfo).to.be.null;
+
+    const makerBalanceAfter = (await provider.connection.getTokenAccountBalance(makerAtaA)).value.uiAmount;
+    expect(makerBalanceAfter - makerBalanceBefore).to.equal(depositAmount);
+
+    // The tip covers more than the transaction fee
+    const callerLamportsAfter = await provider.connection.getBalance(taker.publicKey);
+    expect(callerLamportsAfter).to.be.greaterThan(callerLamportsBefore);
+  });
+
+  it("Only lets the designated taker fill a private escrow", async () => {
+    const seed5 = new anchor.BN(5555);
+    const stranger = anchor.web3.Keypair.generate();
+    [escrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.toBuffer(), seed5.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    vault = getAssociatedTokenAddressSync(mintA, escrowPda, true);
+
+    await program.methods
+      .make(seed5, new anchor.BN(depositAmount), new anchor.BN(receiveAmount), new anchor.BN(0), inAnHour(), new anchor.BN(0), stranger.publicKey)
+      .accountsStrict({
+        maker: maker,
+        mintA: mintA,
+        mintB: mintB,
+        makerAtaA: makerAtaA,
+        escrow: escrowPda,
+        vault: vault,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const takeAccounts = {
+      taker: taker.publicKey,
+      maker: maker,
+      mintA: mintA,
+      mintB: mintB,
+      takerAtaA: takerAtaA,
+      takerAtaB: takerAtaB,
+      makerAtaB: makerAtaB,
+      escrow: escrowPda,
+      vault: vault,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+
+    try {
+      await program.methods.take(new anchor.BN(0), new anchor.BN(receiveAmount)).accountsStrict(takeAccounts).signers([taker]).rpc();
+      expect.fail("Should have thrown InvalidTaker");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidTaker");
+    }
+
+    // The maker agrees to the taker instead
+    await program.methods
+      .updateTaker(taker.publicKey)
+      .accountsStrict({ maker: maker, escrow: escrowPda })
+      .rpc();
+
+    const escrowAccount = await program.account.escrow.fetch(escrowPda);
+    expect(escrowAccount.taker.toBase58()).to.equal(taker.publicKey.toBase58());
+    expect(escrowAccount.version.toNumber()).to.equal(1);
+
+    await program.methods.take(new anchor.BN(1), new anchor.BN(receiveAmount)).accountsStrict(takeAccounts).signers([taker]).rpc();
+
+    const escrowInfo = await provider.connection.getAccountInfo(escrowPda);
+    expect(escrowInfo).to.be.null;
+  });
+
+  it("Updates the offer and rejects takes on stale terms", async () => {
+    const seed6 = new anchor.BN(6666);
+    const newReceive = 150;
+    [escrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.toBuffer(), seed6.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    vault = getAssociatedTokenAddressSync(mintA, escrowPda, true);
+
+    await program.methods
+      .make(seed6, new anchor.BN(depositAmount / 2), new anchor.BN(receiveAmount), new anchor.BN(0), inAnHour(), new anchor.BN(0), null)
+      .accountsStrict({
+        maker: maker,
+        mintA: mintA,
+        mintB: mintB,
+        makerAtaA: makerAtaA,
+        escrow: escrowPda,
+        vault: vault,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // Better price and a bigger deposit, without closing the escrow
+    await program.methods
+      .updateOffer(new anchor.BN(newReceive), new anchor.BN(depositAmount / 4), new anchor.BN(0))
+      .accountsStrict({
+        maker: maker,
+        mintA: mintA,
+        makerAtaA: makerAtaA,
+        escrow: escrowPda,
+        vault: vault,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const escrowAccount = await program.account.escrow.fetch(escrowPda);
+    expect(escrowAccount.receive.toNumber()).to.equal(newReceive);
+    expect(escrowAccount.version.toNumber()).to.equal(1);
+    const vaultBalance = (await provider.connection.getTokenAccountBalance(vault)).value.uiAmount;
+    expect(vaultBalance).to.equal(depositAmount * 3 / 4);
+
+    const takeAccounts = {
+      taker: taker.publicKey,
+      maker: maker,
+      mintA: mintA,
+      mintB: mintB,
+      takerAtaA: takerAtaA,
+      takerAtaB: takerAtaB,
+      makerAtaB: makerAtaB,
+      escrow: escrowPda,
+      vault: vault,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+
+    // Terms the taker read before the update
+    try {
+      await program.methods
+        .take(new anchor.BN(0), new anchor.BN(receiveAmount))
+        .accountsStrict(takeAccounts)
+        .signers([taker])
+        .rpc();
+      expect.fail("Should have thrown OfferChanged");
+    } catch (error) {
+      expect(error.toString()).to.include("OfferChanged");
+    }
+
+    // Changing who may take is a new offer too
+    await program.methods
+      .updateTaker(taker.publicKey)
+      .accountsStrict({ maker: maker, escrow: escrowPda })
+      .rpc();
+    expect((await program.account.escrow.fetch(escrowPda)).version.toNumber()).to.equal(2);
+
+    try {
+      await program.methods
+        .take(new anchor.BN(1), new anchor.BN(newReceive))
+        .accountsStrict(takeAccounts)
+        .signers([taker])
+        .rpc();
+      expect.fail("Should have thrown OfferChanged");
+    } catch (error) {
+      expect(error.toString()).to.include("OfferChanged");
+    }
+
+    const takerBalanceBefore = (await provider.connection.getTokenAccountBalance(takerAtaA)).value.uiAmount;
+
+    await program.methods
+      .take(new anchor.BN(2), new anchor.BN(newReceive))
+      .accountsStrict(takeAccounts)
+      .signers([taker])
+      .rpc();
+
+    const takerBalanceAfter = (await provider.connection.getTokenAccountBalance(takerAtaA)).value.uiAmount;
+    expect(takerBalanceAfter - takerBalanceBefore).to.equal(depositAmount * 3 / 4);
+  });
+
+  it("Keeps updates within the escrow's terms and lifetime", async () => {
+    const seed8 = new anchor.BN(8888);
+    [escrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.toBuffer(), seed8.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    vault = getAssociatedTokenAddressSync(mintA, escrowPda, true);
+    await mintTo(provider.connection, provider.wallet.payer, mintA, makerAtaA, provider.wallet.payer, depositAmount);
+
+    // Only fills of the whole amount
+    const expiresAt = new anchor.BN(Math.floor(Date.now() / 1000) + 2);
+    await program.methods
+      .make(seed8, new anchor.BN(depositAmount), new anchor.BN(receiveAmount), new anchor.BN(receiveAmount), expiresAt, new anchor.BN(0), null)
+      .accountsStrict({
+        maker: maker,
+        mintA: mintA,
+        mintB: mintB,
+        makerAtaA: makerAtaA,
+        escrow: escrowPda,
+        vault: vault,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const updateAccounts = {
+      maker: maker,
+      mintA: mintA,
+      makerAtaA: makerAtaA,
+      escrow: escrowPda,
+      vault: vault,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+
+    try {
+      await program.methods
+        .updateOffer(new anchor.BN(receiveAmount), new anchor.BN("18446744073709551615"), new anchor.BN(0))
+        .accountsStrict(updateAccounts)
+        .rpc();
+      expect.fail("Should have thrown InvalidAmount");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidAmount");
+    }
+
+    // Asking for less brings the minimum fill down with it
+    const newReceive = receiveAmount / 2;
+    await program.methods
+      .updateOffer(new anchor.BN(newReceive), new anchor.BN(0), new anchor.BN(0))
+      .accountsStrict(updateAccounts)
+      .rpc();
+
+    const escrowAccount = await program.account.escrow.fetch(escrowPda);
+    expect(escrowAccount.remaining.toNumber()).to.equal(newReceive);
+    expect(escrowAccount.minFill.toNumber()).to.equal(newReceive);
+
+    await new Promise(resolve => setTimeout(resolve, 4000));
+
+    try {
+      await program.methods
+        .updateOffer(new anchor.BN(receiveAmount), new anchor.BN(0), new anchor.BN(0))
+        .accountsStrict(updateAccounts)
+        .rpc();
+      expect.fail("Should have thrown EscrowExpired");
+    } catch (error) {
+      expect(error.toString()).to.include("EscrowExpired");
+    }
+
+    await program.methods
+      .refund()
+      .accountsStrict({
+        maker: maker,
+        mintA: mintA,
+        makerAtaA: makerAtaA,
+        escrow: escrowPda,
+        vault: vault,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  it("Settles a bundle of mints on each side in one take", async () => {
+    const seed7 = new anchor.BN(7777);
+    const [bundlePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("bundle"), maker.toBuffer(), seed7.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    // A second mint on each side: the maker gives A and C for B and D
+    const mintC = await createMint(provider.connection, provider.wallet.payer, maker, null, 0);
+    const mintD = await createMint(provider.connection, provider.wallet.payer, taker.publicKey, null, 0);
+    const makerAtaC = getAssociatedTokenAddressSync(mintC, maker);
+    const takerAtaD = getAssociatedTokenAddressSync(mintD, taker.publicKey);
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(
+      createAssociatedTokenAccountInstruction(provider.wallet.publicKey, makerAtaC, maker, mintC),
+      createAssociatedTokenAccountInstruction(provider.wallet.publicKey, takerAtaD, taker.publicKey, mintD)
+    ));
+    await mintTo(provider.connection, provider.wallet.payer, mintC, makerAtaC, provider.wallet.payer, 30);
+    await mintTo(provider.connection, taker, mintD, takerAtaD, taker, 60);
+
+    const give = [
+      { mint: mintA, amount: new anchor.BN(20) },
+      { mint: mintC, amount: new anchor.BN(30) },
+    ];
+    const receive = [
+      { mint: mintB, amount: new anchor.BN(40) },
+      { mint: mintD, amount: new anchor.BN(60) },
+    ];
+    const vaultA = getAssociatedTokenAddressSync(mintA, bundlePda, true);
+    const vaultC = getAssociatedTokenAddressSync(mintC, bundlePda, true);
+    const takerAtaC = getAssociatedTokenAddressSync(mintC, taker.publicKey);
+    const makerAtaD = getAssociatedTokenAddressSync(mintD, maker);
+    const meta = (pubkey: anchor.web3.PublicKey, isWritable: boolean) => ({ pubkey, isSigner: false, isWritable });
+
+    await program.methods
+      .makeBundle(seed7, give, receive)
+      .accountsStrict({
+        maker: maker,
+        bundle: bundlePda,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts([
+        meta(mintA, false), meta(makerAtaA, true), meta(vaultA, true),
+        meta(mintC, false), meta(makerAtaC, true), meta(vaultC, true),
+      ])
+      .rpc();
+
+    const bundleAccount = await program.account.bundleEscrow.fetch(bundlePda);
+    expect(bundleAccount.give.length).to.equal(2);
+    expect(bundleAccount.receive.length).to.equal(2);
+    const vaultCBalance = (await provider.connection.getTokenAccountBalance(vaultC)).value.uiAmount;
+    expect(vaultCBalance).to.equal(30);
+
+    const takeAccounts = {
+      taker: taker.publicKey,
+      maker: maker,
+      bundle: bundlePda,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+    const giveAccounts = [
+      meta(mintA, false), meta(vaultA, true), meta(takerAtaA, true),
+      meta(mintC, false), meta(vaultC, true), meta(takerAtaC, true),
+    ];
+    const receiveAccounts = [
+      meta(mintB, false), meta(takerAtaB, true), meta(makerAtaB, true),
+      meta(mintD, false), meta(takerAtaD, true), meta(makerAtaD, true),
+    ];
+
+    // Leaving out a leg of the bundle, legs out of order, a vault of another
+    // mint of the bundle and an ATA that isn't the maker's
+    const invalidRemainingAccounts = [
+      [...giveAccounts, ...receiveAccounts.slice(0, 3)],
+      [...giveAccounts, ...receiveAccounts.slice(3), ...receiveAccounts.slice(0, 3)],
+      [
+        meta(mintA, false), meta(vaultC, true), meta(takerAtaA, true),
+        meta(mintC, false), meta(vaultA, true), meta(takerAtaC, true),
+        ...receiveAccounts,
+      ],
+      [
+        ...giveAccounts,
+        meta(mintB, false), meta(takerAtaB, true), meta(takerAtaB, true),
+        ...receiveAccounts.slice(3),
+      ],
+    ];
+    for (const remainingAccounts of invalidRemainingAccounts) {
+      try {
+        await program.methods
+          .takeBundle()
+          .accountsStrict(takeAccounts)
+          .remainingAccounts(remainingAccounts)
+          .signers([taker])
+          .rpc();
+        expect.fail("Should have thrown InvalidBundleAccounts");
+      } catch (error) {
+        expect(error.toString()).to.include("InvalidBundleAccounts");
+      }
+    }
+
+    await program.methods
+      .takeBundle()
+      .accountsStrict(takeAccounts)
+      .remainingAccounts([...giveAccounts, ...receiveAccounts])
+      .signers([taker])
+      .rpc();
+
+    for (const account of [bundlePda, vaultA, vaultC]) {
+      expect(await provider.connection.getAccountInfo(account)).to.be.null;
+    }
+
+    const takerBalanceC = (await provider.connection.getTokenAccountBalance(takerAtaC)).value.uiAmount;
+    expect(takerBalanceC).to.equal(30);
+    const makerBalanceD = (await provider.connection.getTokenAccountBalance(makerAtaD)).value.uiAmount;
+    expect(makerBalanceD).to.equal(60);
+  });
+
+  it("Refunds every mint of a bundle to the maker", async () => {
+    const seed9 = new anchor.BN(9999);
+    const [bundlePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("bundle"), maker.toBuffer(), seed9.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    const mintE = await createMint(provider.connection, provider.wallet.payer, maker, null, 0);
+    const makerAtaE = getAssociatedTokenAddressSync(mintE, maker);
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(
+      createAssociatedTokenAccountInstruction(provider.wallet.publicKey, makerAtaE, maker, mintE)
+    ));
+    await mintTo(provider.connection, provider.wallet.payer, mintE, makerAtaE, provider.wallet.payer, 50);
+    await mintTo(provider.connection, provider.wallet.payer, mintA, makerAtaA, provider.wallet.payer, 10);
+
+    const vaultA = getAssociatedTokenAddressSync(mintA, bundlePda, true);
+    const vaultE = getAssociatedTokenAddressSync(mintE, bundlePda, true);
+    const meta = (pubkey: anchor.web3.PublicKey, isWritable: boolean) => ({ pubkey, isSigner: false, isWritable });
+    const bundleAccounts = {
+      maker: maker,
+      bundle: bundlePda,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+
+    await program.methods
+      .makeBundle(
+        seed9,
+        [
+          { mint: mintA, amount: new anchor.BN(10) },
+          { mint: mintE, amount: new anchor.BN(50) },
+        ],
+        [{ mint: mintB, amount: new anchor.BN(10) }]
+      )
+      .accountsStrict(bundleAccounts)
+      .remainingAccounts([
+        meta(mintA, false), meta(makerAtaA, true), meta(vaultA, true),
+        meta(mintE, false), meta(makerAtaE, true), meta(vaultE, true),
+      ])
+      .rpc();
+
+    const makerBalanceA = (await provider.connection.getTokenAccountBalance(makerAtaA)).value.uiAmount;
+
+    // Vaults swapped between the mints, then the taker's ATA instead of the maker's
+    const invalidRemainingAccounts = [
+      [
+        meta(mintA, false), meta(vaultE, true), meta(makerAtaA, true),
+        meta(mintE, false), meta(vaultA, true), meta(makerAtaE, true),
+      ],
+      [
+        meta(mintA, false), meta(vaultA, true), meta(takerAtaA, true),
+        meta(mintE, false), meta(vaultE, true), meta(makerAtaE, true),
+      ],
+    ];
+    for (const remainingAccounts of invalidRemainingAccounts) {
+      try {
+        await program.methods
+          .refundBundle()
+          .accountsStrict(bundleAccounts)
+          .remainingAccounts(remainingAccounts)
+          .rpc();
+        expect.fail("Should have thrown InvalidBundleAccounts");
+      } catch (error) {
+        expect(error.toString()).to.include("InvalidBundleAccounts");
+      }
+    }
+
+    await program.methods
+      .refundBundle()
+      .accountsStrict(bundleAccounts)
+      .remainingAccounts([
+        meta(mintA, false), meta(vaultA, true), meta(makerAtaA, true),
+        meta(mintE, false), meta(vaultE, true), meta(makerAtaE, true),
+      ])
+      .rpc();
+
+    for (const account of [bundlePda, vaultA, vaultE]) {
+      expect(await provider.connection.getAccountInfo(account)).to.be.null;
+    }
+
+    const makerBalanceAAfter = (await provider.connection.getTokenAccountBalance(makerAtaA)).value.uiAmount;
+    expect(makerBalanceAAfter - makerBalanceA).to.equal(10);
+    const makerBalanceE = (await provider.connection.getTokenAccountBalance(makerAtaE)).value.uiAmount;
+    expect(makerBalanceE).to.equal(50);
+  });
 });
\ No newline at end of file